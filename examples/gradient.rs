@@ -1,12 +1,9 @@
-#[macro_use]
 extern crate bmp;
-use bmp::{Image, Pixel};
+use bmp::{generators, Pixel};
 
 fn main() {
-    let mut img = Image::new(256, 256);
-
-    for (x, y) in img.coordinates() {
-        img.set_pixel(x, y, px!(x, y, 200));
-    }
+    let from = Pixel::new(0, 0, 200);
+    let to = Pixel::new(255, 255, 200);
+    let img = generators::linear_gradient(256, 256, from, to, 45.0);
     let _ = img.save("img.bmp");
 }
@@ -0,0 +1,487 @@
+use {Image, Pixel};
+
+/// The ways of measuring the distance between two colors.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorDistance {
+    /// The straight-line distance between the color channels, from 0 up to about 441.7.
+    Euclidean,
+    /// The sum of the differences of the color channels, from 0 up to 765.
+    Manhattan,
+    /// The largest difference of the color channels, from 0 up to 255.
+    Chebyshev,
+    /// The "redmean" approximation of the perceived difference, weighting the channels by the
+    /// sensitivity of the eye. Goes from 0 up to about 765.
+    Redmean,
+}
+
+impl ColorDistance {
+    /// Returns the distance between the colors `a` and `b`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{ColorDistance, Pixel};
+    ///
+    /// let (a, b) = (Pixel::new(10, 20, 30), Pixel::new(13, 24, 30));
+    /// assert_eq!(5.0, ColorDistance::Euclidean.distance(a, b));
+    /// assert_eq!(7.0, ColorDistance::Manhattan.distance(a, b));
+    /// assert_eq!(4.0, ColorDistance::Chebyshev.distance(a, b));
+    /// ```
+    pub fn distance(self, a: Pixel, b: Pixel) -> f32 {
+        let dr = a.r as f32 - b.r as f32;
+        let dg = a.g as f32 - b.g as f32;
+        let db = a.b as f32 - b.b as f32;
+        match self {
+            ColorDistance::Euclidean => (dr * dr + dg * dg + db * db).sqrt(),
+            ColorDistance::Manhattan => dr.abs() + dg.abs() + db.abs(),
+            ColorDistance::Chebyshev => dr.abs().max(dg.abs()).max(db.abs()),
+            ColorDistance::Redmean => {
+                let mean = (a.r as f32 + b.r as f32) / 2.0;
+                let wr = 2.0 + mean / 256.0;
+                let wb = 2.0 + (255.0 - mean) / 256.0;
+                (wr * dr * dr + 4.0 * dg * dg + wb * db * db).sqrt()
+            }
+        }
+    }
+}
+
+impl Pixel {
+    /// Returns the perceived brightness of the pixel, using the Rec. 601 weights of the color
+    /// channels.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::consts;
+    ///
+    /// assert_eq!(255, consts::WHITE.luma());
+    /// assert_eq!(150, consts::LIME.luma());
+    /// ```
+    #[inline]
+    pub fn luma(&self) -> u8 {
+        ((299 * self.r as u32 + 587 * self.g as u32 + 114 * self.b as u32 + 500) / 1000) as u8
+    }
+
+    /// Returns the hue, saturation and value of the pixel. The hue is in degrees from 0 up to
+    /// 360, where red is 0, and the saturation and value are between 0 and 1.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::consts;
+    ///
+    /// assert_eq!((240.0, 1.0, 1.0), consts::BLUE.to_hsv());
+    /// assert_eq!((0.0, 0.0, 1.0), consts::WHITE.to_hsv());
+    /// ```
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.r as f32, self.g as f32, self.b as f32);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue.rem_euclid(360.0), saturation, max / 255.0)
+    }
+
+    /// Creates a new `Pixel` from the `hue` in degrees, and the `saturation` and `value` between
+    /// 0 and 1. The hue wraps around, and the saturation and value are clamped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Pixel};
+    ///
+    /// assert_eq!(consts::LIME, Pixel::from_hsv(120.0, 1.0, 1.0));
+    /// assert_eq!(consts::RED, Pixel::from_hsv(-360.0, 1.0, 1.0));
+    /// ```
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Pixel {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let s = saturation.clamp(0.0, 1.0);
+        let v = value.clamp(0.0, 1.0) * 255.0;
+
+        let c = v * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = v - c;
+        let channel = |c: f32| (c + m).round() as u8;
+        Pixel::new(channel(r), channel(g), channel(b))
+    }
+}
+
+// The lookup table mapping every channel value through the levels adjustment
+fn levels_table(in_black: u8, in_white: u8, out_black: u8, out_white: u8, gamma: f32) -> [u8; 256] {
+    let mut table = [0; 256];
+    for (v, out) in table.iter_mut().enumerate() {
+        let t = if in_white > in_black {
+            ((v as f32 - in_black as f32) / (in_white as f32 - in_black as f32)).clamp(0.0, 1.0)
+        } else if v >= in_black as usize {
+            1.0
+        } else {
+            0.0
+        };
+        let t = t.powf(1.0 / gamma);
+        *out = (out_black as f32 + t * (out_white as f32 - out_black as f32)).round() as u8;
+    }
+    table
+}
+
+impl Image {
+    // Maps every color channel through the lookup `table`
+    fn map_channels(&mut self, table: &[u8; 256]) {
+        for px in &mut self.data {
+            *px = Pixel::new(
+                table[px.r as usize],
+                table[px.g as usize],
+                table[px.b as usize],
+            );
+        }
+    }
+
+    /// Adjusts the levels of every color channel. Values at or below `in_black` become
+    /// `out_black`, values at or above `in_white` become `out_white`, and the values between
+    /// them are stretched in between. A `gamma` above 1 brightens the midtones, and a `gamma`
+    /// below 1 darkens them.
+    ///
+    /// Panics if `gamma` is not positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Image, Pixel};
+    ///
+    /// // Stretch a washed-out scan to the full range
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(Pixel::new(40, 128, 220));
+    /// img.levels(40, 220, 0, 255, 1.0);
+    /// assert_eq!(Pixel::new(0, 125, 255), img.get_pixel(0, 0));
+    /// ```
+    pub fn levels(&mut self, in_black: u8, in_white: u8, out_black: u8, out_white: u8, gamma: f32) {
+        assert!(gamma > 0.0, "The gamma must be positive, but was {}", gamma);
+        let table = levels_table(in_black, in_white, out_black, out_white, gamma);
+        self.map_channels(&table);
+    }
+
+    /// Adjusts the levels of the luma of every pixel like `levels`, and shifts the color channels
+    /// by the same amount. This changes the brightness without changing the hue as much as
+    /// adjusting each channel does.
+    ///
+    /// Panics if `gamma` is not positive.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(Pixel::new(100, 50, 50));
+    /// img.levels_luma(0, 128, 0, 255, 1.0);
+    /// assert_eq!(Pixel::new(164, 114, 114), img.get_pixel(0, 0));
+    /// ```
+    pub fn levels_luma(
+        &mut self,
+        in_black: u8,
+        in_white: u8,
+        out_black: u8,
+        out_white: u8,
+        gamma: f32,
+    ) {
+        assert!(gamma > 0.0, "The gamma must be positive, but was {}", gamma);
+        let table = levels_table(in_black, in_white, out_black, out_white, gamma);
+        for px in &mut self.data {
+            let luma = px.luma();
+            let shift = table[luma as usize] as i32 - luma as i32;
+            let channel = |v: u8| (v as i32 + shift).clamp(0, 255) as u8;
+            *px = Pixel::new(channel(px.r), channel(px.g), channel(px.b));
+        }
+    }
+
+    /// Reduces every color channel to `levels` evenly spaced values, including 0 and 255.
+    ///
+    /// Panics if `levels` is less than 2.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(Pixel::new(40, 100, 200));
+    /// img.posterize(2);
+    /// assert_eq!(Pixel::new(0, 0, 255), img.get_pixel(0, 0));
+    /// ```
+    pub fn posterize(&mut self, levels: u8) {
+        assert!(
+            levels >= 2,
+            "Expected at least 2 levels, but was {}",
+            levels
+        );
+        let steps = (levels - 1) as u32;
+        let mut table = [0; 256];
+        for (v, out) in table.iter_mut().enumerate() {
+            let level = (v as u32 * steps + 127) / 255;
+            *out = ((level * 255 + steps / 2) / steps) as u8;
+        }
+        self.map_channels(&table);
+    }
+
+    /// Replaces the color channels of every pixel with the products of `matrix` and the
+    /// channels. Each row of the matrix gives the weights of the red, green and blue input
+    /// channels for one output channel, in the same order. The results are rounded and clamped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Image, Pixel};
+    ///
+    /// // Swap the red and blue channels
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(Pixel::new(10, 20, 30));
+    /// img.apply_color_matrix(&[[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]);
+    /// assert_eq!(Pixel::new(30, 20, 10), img.get_pixel(0, 0));
+    /// ```
+    pub fn apply_color_matrix(&mut self, matrix: &[[f32; 3]; 3]) {
+        for px in &mut self.data {
+            let (r, g, b) = (px.r as f32, px.g as f32, px.b as f32);
+            let channel = |row: [f32; 3]| {
+                (row[0] * r + row[1] * g + row[2] * b)
+                    .round()
+                    .clamp(0.0, 255.0) as u8
+            };
+            *px = Pixel::new(channel(matrix[0]), channel(matrix[1]), channel(matrix[2]));
+        }
+    }
+
+    /// Gives the image the brownish tones of an old photograph.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::GRAY);
+    /// img.sepia();
+    /// assert_eq!(Pixel::new(173, 154, 120), img.get_pixel(0, 0));
+    /// ```
+    pub fn sepia(&mut self) {
+        self.apply_color_matrix(&[
+            [0.393, 0.769, 0.189],
+            [0.349, 0.686, 0.168],
+            [0.272, 0.534, 0.131],
+        ]);
+    }
+
+    /// Tints the image towards `color`, where `strength` is between 0 (unchanged) and 1 (only
+    /// the brightness of the pixels remains, in shades of `color`). Values outside of the range
+    /// are clamped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::WHITE);
+    /// img.tint(Pixel::new(0, 128, 255), 1.0);
+    /// assert_eq!(Pixel::new(0, 128, 255), img.get_pixel(0, 0));
+    /// ```
+    pub fn tint(&mut self, color: Pixel, strength: f32) {
+        let s = strength.clamp(0.0, 1.0);
+        let weights = [0.299, 0.587, 0.114];
+        let mut matrix = [[0.0; 3]; 3];
+        for (i, &c) in [color.r, color.g, color.b].iter().enumerate() {
+            for (j, &w) in weights.iter().enumerate() {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                // Mix each channel with the luma scaled by the channel of the color
+                matrix[i][j] = (1.0 - s) * identity + s * w * c as f32 / 255.0;
+            }
+        }
+        self.apply_color_matrix(&matrix);
+    }
+
+    /// Rotates the hue of every pixel by `hue_shift` degrees, and multiplies the saturation and
+    /// value by `saturation` and `value`. A `saturation` of 0 turns the image into shades of
+    /// gray.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::RED);
+    /// img.adjust_hsv(120.0, 1.0, 1.0);
+    /// assert_eq!(consts::LIME, img.get_pixel(0, 0));
+    /// ```
+    pub fn adjust_hsv(&mut self, hue_shift: f32, saturation: f32, value: f32) {
+        for px in &mut self.data {
+            let (h, s, v) = px.to_hsv();
+            *px = Pixel::from_hsv(h + hue_shift, s * saturation, v * value);
+        }
+    }
+
+    /// Replaces every pixel within the Euclidean distance `tolerance` of the color `from` with
+    /// the color `to`. A small tolerance also catches the blended colors along anti-aliased
+    /// edges.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(Pixel::new(250, 2, 0));
+    /// img.replace_color(consts::RED, consts::BLUE, 10.0);
+    /// assert_eq!(consts::BLUE, img.get_pixel(0, 0));
+    /// ```
+    pub fn replace_color(&mut self, from: Pixel, to: Pixel, tolerance: f32) {
+        self.replace_color_with(from, to, tolerance, ColorDistance::Euclidean);
+    }
+
+    /// Replaces every pixel within `tolerance` of the color `from` with the color `to`, where the
+    /// distance is measured by `metric`.
+    pub fn replace_color_with(
+        &mut self,
+        from: Pixel,
+        to: Pixel,
+        tolerance: f32,
+        metric: ColorDistance,
+    ) {
+        for px in &mut self.data {
+            if metric.distance(*px, from) <= tolerance {
+                *px = to;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_map_the_input_range_to_the_output_range() {
+        let table = levels_table(0, 255, 0, 255, 1.0);
+        assert!(table.iter().enumerate().all(|(i, &v)| i == v as usize));
+
+        let table = levels_table(10, 20, 100, 50, 1.0);
+        assert_eq!(table[0], 100);
+        assert_eq!(table[15], 75);
+        assert_eq!(table[255], 50);
+
+        // Brighter midtones with a higher gamma
+        let table = levels_table(0, 255, 0, 255, 2.0);
+        assert_eq!(table[64], 128);
+        assert_eq!(table[255], 255);
+
+        // A threshold when the input range is empty
+        let table = levels_table(128, 128, 0, 255, 1.0);
+        assert_eq!((table[127], table[128]), (0, 255));
+    }
+
+    #[test]
+    fn posterize_reduces_the_number_of_values() {
+        let mut img = Image::new(256, 1);
+        for x in 0..256 {
+            img.set_pixel(x, 0, Pixel::new(x as u8, 0, 255));
+        }
+        img.posterize(4);
+        let mut values: Vec<u8> = img.data.iter().map(|px| px.r).collect();
+        values.dedup();
+        assert_eq!(values, vec![0, 85, 170, 255]);
+        assert!(img.data.iter().all(|px| px.g == 0 && px.b == 255));
+    }
+
+    #[test]
+    fn tint_mixes_the_color_with_the_luma() {
+        let px = Pixel::new(200, 100, 0);
+
+        let mut img = Image::new(1, 1);
+        img.fill(px);
+        img.tint(Pixel::new(255, 0, 0), 0.0);
+        assert_eq!(img.get_pixel(0, 0), px);
+
+        img.tint(Pixel::new(255, 255, 255), 1.0);
+        let luma = px.luma();
+        assert_eq!(img.get_pixel(0, 0), Pixel::new(luma, luma, luma));
+
+        let mut img = Image::new(1, 1);
+        img.fill(px);
+        img.tint(Pixel::new(0, 0, 255), 0.5);
+        assert_eq!(img.get_pixel(0, 0), Pixel::new(100, 50, 59));
+    }
+
+    #[test]
+    fn hsv_conversion_round_trips() {
+        for r in (0..256).step_by(15) {
+            for g in (0..256).step_by(17) {
+                for b in (0..256).step_by(51) {
+                    let px = Pixel::new(r as u8, g as u8, b as u8);
+                    let (h, s, v) = px.to_hsv();
+                    assert_eq!(Pixel::from_hsv(h, s, v), px);
+                }
+            }
+        }
+        assert_eq!(Pixel::new(255, 128, 0).to_hsv().0.round(), 30.0);
+        assert_eq!(Pixel::new(255, 0, 128).to_hsv().0.round(), 330.0);
+    }
+
+    #[test]
+    fn replace_color_uses_the_metric() {
+        let mut img = Image::new(3, 1);
+        img.set_pixel(0, 0, Pixel::new(100, 100, 100));
+        img.set_pixel(1, 0, Pixel::new(104, 104, 100));
+        img.set_pixel(2, 0, Pixel::new(106, 100, 100));
+
+        let mut chebyshev = img.clone();
+        chebyshev.replace_color_with(
+            Pixel::new(100, 100, 100),
+            Pixel::new(0, 0, 0),
+            5.0,
+            ColorDistance::Chebyshev,
+        );
+        let replaced: Vec<bool> = chebyshev
+            .data
+            .iter()
+            .map(|&px| px == Pixel::new(0, 0, 0))
+            .collect();
+        assert_eq!(replaced, vec![true, true, false]);
+
+        let mut manhattan = img.clone();
+        manhattan.replace_color_with(
+            Pixel::new(100, 100, 100),
+            Pixel::new(0, 0, 0),
+            6.0,
+            ColorDistance::Manhattan,
+        );
+        let replaced: Vec<bool> = manhattan
+            .data
+            .iter()
+            .map(|&px| px == Pixel::new(0, 0, 0))
+            .collect();
+        assert_eq!(replaced, vec![true, false, true]);
+
+        let (a, b) = (Pixel::new(0, 0, 0), Pixel::new(255, 255, 255));
+        assert_eq!(
+            ColorDistance::Redmean.distance(a, b),
+            ColorDistance::Redmean.distance(b, a)
+        );
+        assert!((ColorDistance::Redmean.distance(a, b) - 764.8).abs() < 0.1);
+    }
+}
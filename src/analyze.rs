@@ -0,0 +1,142 @@
+use {Image, Pixel};
+
+impl Image {
+    /// Returns the color of the image if every pixel has the same color, and `None` otherwise.
+    /// An image without pixels has no color.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::WHITE);
+    /// assert_eq!(Some(consts::WHITE), img.is_solid_color());
+    ///
+    /// img.set_pixel(50, 40, consts::RED);
+    /// assert_eq!(None, img.is_solid_color());
+    /// ```
+    pub fn is_solid_color(&self) -> Option<Pixel> {
+        let first = *self.data.first()?;
+        if self.data.iter().all(|&px| px == first) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the image is nearly a single color, where no color channel varies by
+    /// more than `tolerance` over the whole image. This ignores noise and compression artifacts
+    /// in empty screenshots. An image without pixels is blank.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::WHITE);
+    /// img.set_pixel(50, 40, Pixel::new(250, 255, 252));
+    /// assert!(img.is_blank(5));
+    /// assert!(!img.is_blank(4));
+    /// ```
+    pub fn is_blank(&self, tolerance: u8) -> bool {
+        let first = match self.data.first() {
+            Some(&px) => px,
+            None => return true,
+        };
+        let (mut min, mut max) = (first, first);
+        for px in &self.data {
+            min = Pixel::new(min.r.min(px.r), min.g.min(px.g), min.b.min(px.b));
+            max = Pixel::new(max.r.max(px.r), max.g.max(px.g), max.b.max(px.b));
+            if max.r - min.r > tolerance || max.g - min.g > tolerance || max.b - min.b > tolerance {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns the smallest rectangle containing every pixel that differs from the color
+    /// `background` by more than `tolerance` in a color channel, as the `x` and `y` of the upper
+    /// left corner, and the `width` and `height`. Returns `None` if the whole image is
+    /// background.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::WHITE);
+    /// img.fill_rect(10, 20, 30, 40, consts::BLACK);
+    /// assert_eq!(Some((10, 20, 30, 40)), img.content_bounds(consts::WHITE, 0));
+    /// ```
+    pub fn content_bounds(&self, background: Pixel, tolerance: u8) -> Option<(u32, u32, u32, u32)> {
+        let is_content = |px: &Pixel| {
+            px.r.abs_diff(background.r) > tolerance
+                || px.g.abs_diff(background.g) > tolerance
+                || px.b.abs_diff(background.b) > tolerance
+        };
+        let (mut x0, mut y0) = (u32::MAX, u32::MAX);
+        let (mut x1, mut y1) = (0, 0);
+        for (x, y) in self.coordinates() {
+            if is_content(&self.get_pixel(x, y)) {
+                x0 = x0.min(x);
+                y0 = y0.min(y);
+                x1 = x1.max(x);
+                y1 = y1.max(y);
+            }
+        }
+        if x0 == u32::MAX {
+            None
+        } else {
+            Some((x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+        }
+    }
+
+    /// Returns a new `Image` with the borders of the color `background` cropped away, where the
+    /// pixels are compared like in `content_bounds`. Returns an empty image if the whole image is
+    /// background.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::WHITE);
+    /// img.fill_rect(10, 20, 30, 40, consts::BLACK);
+    ///
+    /// let trimmed = img.trim(consts::WHITE, 0);
+    /// assert_eq!((30, 40), (trimmed.get_width(), trimmed.get_height()));
+    /// ```
+    pub fn trim(&self, background: Pixel, tolerance: u8) -> Image {
+        match self.content_bounds(background, tolerance) {
+            Some((x, y, width, height)) => self.view(x, y, width, height).to_image(),
+            None => Image::new(0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn content_bounds_ignore_the_background() {
+        let mut img = Image::new(10, 8);
+        img.fill(Pixel::new(250, 250, 250));
+        assert_eq!(img.content_bounds(consts::WHITE, 5), None);
+        assert_eq!(img.trim(consts::WHITE, 5), Image::new(0, 0));
+
+        img.set_pixel(2, 6, Pixel::new(240, 250, 250));
+        img.set_pixel(7, 3, consts::BLACK);
+        assert_eq!(img.content_bounds(consts::WHITE, 5), Some((2, 3, 6, 4)));
+        assert_eq!(img.content_bounds(consts::WHITE, 15), Some((7, 3, 1, 1)));
+
+        let trimmed = img.trim(consts::WHITE, 5);
+        assert_eq!(trimmed.get_pixel(0, 3), Pixel::new(240, 250, 250));
+        assert_eq!(trimmed.get_pixel(5, 0), consts::BLACK);
+    }
+}
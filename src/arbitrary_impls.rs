@@ -0,0 +1,53 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use {Image, Pixel};
+
+// The largest width and height of the generated images, which keeps fuzz targets fast
+const MAX_SIZE: u32 = 64;
+
+impl<'a> Arbitrary<'a> for Pixel {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Pixel> {
+        Ok(Pixel::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (3, Some(3))
+    }
+}
+
+/// Generates images of up to 64x64 pixels. Once the input data runs out, the remaining pixels
+/// are black.
+impl<'a> Arbitrary<'a> for Image {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Image> {
+        let width = u.int_in_range(0..=MAX_SIZE)?;
+        let height = u.int_in_range(0..=MAX_SIZE)?;
+        let mut img = Image::new(width, height);
+        for px in img.data.iter_mut() {
+            *px = u.arbitrary()?;
+        }
+        Ok(img)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (2, Some(2 + MAX_SIZE as usize * MAX_SIZE as usize * 3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_images_are_bounded() {
+        let data: Vec<u8> = (0..=255).cycle().take(20_000).collect();
+        let mut u = Unstructured::new(&data);
+        for _ in 0..5 {
+            let img = Image::arbitrary(&mut u).unwrap();
+            assert!(img.get_width() <= MAX_SIZE && img.get_height() <= MAX_SIZE);
+        }
+        assert_eq!(
+            Pixel::arbitrary(&mut Unstructured::new(&[1, 2, 3])).unwrap(),
+            Pixel::new(1, 2, 3)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+// The crate uses the 2015 edition, without `async` and `await`, so the futures are written out by
+// hand. The pixels are decoded and encoded in memory, only the reading and writing is asynchronous.
+
+use std::future::{self, Future};
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::fs;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use {decoder, encoder, BmpResult, Image};
+
+type IoFuture<T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send>>;
+
+// Decodes the bytes from a future once they have been read
+struct Decode {
+    bytes: IoFuture<Vec<u8>>,
+}
+
+impl Future for Decode {
+    type Output = BmpResult<Image>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<BmpResult<Image>> {
+        match self.bytes.as_mut().poll(cx) {
+            Poll::Ready(Ok(bytes)) => Poll::Ready(decoder::decode_image(&bytes[..])),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err.into())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+// Reads the whole source before decoding it
+struct ReadImage<'a, R: 'a> {
+    source: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<'a, R: AsyncRead + Unpin> Future for ReadImage<'a, R> {
+    type Output = BmpResult<Image>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<BmpResult<Image>> {
+        let this = &mut *self;
+        let mut chunk = [0; 8192];
+        loop {
+            let mut buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut *this.source).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => {
+                    return Poll::Ready(decoder::decode_image(&this.bytes[..]));
+                }
+                Poll::Ready(Ok(())) => this.bytes.extend_from_slice(buf.filled()),
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+// Writes the encoded bytes to the destination, starting at `written`
+struct WriteImage<'a, W: 'a> {
+    destination: &'a mut W,
+    bytes: io::Result<Vec<u8>>,
+    written: usize,
+}
+
+impl<'a, W: AsyncWrite + Unpin> Future for WriteImage<'a, W> {
+    type Output = io::Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.bytes.is_err() {
+            return Poll::Ready(mem::replace(&mut this.bytes, Ok(Vec::new())).map(|_| ()));
+        }
+        let bytes = this.bytes.as_ref().unwrap();
+        while this.written < bytes.len() {
+            match Pin::new(&mut *this.destination).poll_write(cx, &bytes[this.written..]) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(n)) => this.written += n,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Pin::new(&mut *this.destination).poll_flush(cx)
+    }
+}
+
+/// Loads an `Image` from the file specified by `path` without blocking the executor while the
+/// file is read.
+/// Returns a `BmpResult`, either containing an `Image` or a `BmpError`.
+///
+/// # Example
+///
+/// ```
+/// extern crate bmp;
+/// extern crate tokio;
+///
+/// # fn main() {
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let img = runtime.block_on(bmp::open_async("test/rgbw.bmp")).unwrap();
+/// assert_eq!(bmp::consts::RED, img.get_pixel(0, 0));
+/// # }
+/// ```
+pub fn open_async<P: AsRef<Path>>(path: P) -> impl Future<Output = BmpResult<Image>> + Send {
+    Decode {
+        bytes: Box::pin(fs::read(path.as_ref().to_path_buf())),
+    }
+}
+
+/// Attempts to construct a new `Image` from the given asynchronous reader.
+/// Returns a `BmpResult`, either containing an `Image` or a `BmpError`.
+pub fn from_async_reader<R: AsyncRead + Unpin>(
+    source: &mut R,
+) -> impl Future<Output = BmpResult<Image>> + '_ {
+    ReadImage {
+        source,
+        bytes: Vec::new(),
+    }
+}
+
+impl Image {
+    /// Saves the `Image` instance to the path specified by `path` without blocking the executor
+    /// while the file is written.
+    /// The function will overwrite the contents if a file already exists at the given path.
+    ///
+    /// The function returns the `io::Result` from the underlying writer.
+    pub fn save_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> impl Future<Output = io::Result<()>> + Send {
+        let write: IoFuture<()> = match encoder::encode_image(self) {
+            Ok(bmp_data) => Box::pin(fs::write(path.as_ref().to_path_buf(), bmp_data)),
+            Err(err) => Box::pin(future::ready(Err(err))),
+        };
+        write
+    }
+
+    /// Writes the `Image` instance to the asynchronous writer referenced by `destination`.
+    pub fn to_async_writer<'a, W: AsyncWrite + Unpin>(
+        &self,
+        destination: &'a mut W,
+    ) -> impl Future<Output = io::Result<()>> + 'a {
+        WriteImage {
+            destination,
+            bytes: encoder::encode_image(self),
+            written: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+    use tokio::runtime::{Builder, Runtime};
+
+    fn runtime() -> Runtime {
+        Builder::new_current_thread().build().unwrap()
+    }
+
+    #[test]
+    fn images_round_trip_through_async_io() {
+        let img = runtime().block_on(open_async("test/rgbw.bmp")).unwrap();
+        assert_eq!(img.get_pixel(0, 0), consts::RED);
+
+        let mut bytes = Vec::new();
+        runtime().block_on(img.to_async_writer(&mut bytes)).unwrap();
+        let decoded = runtime()
+            .block_on(from_async_reader(&mut &bytes[..]))
+            .unwrap();
+        assert_eq!(decoded.as_raw_rgb(), img.as_raw_rgb());
+
+        let path = ::tests::temp_path("async.bmp");
+        runtime().block_on(img.save_async(&path)).unwrap();
+        let saved = runtime().block_on(open_async(&path)).unwrap();
+        ::std::fs::remove_file(&path).unwrap();
+        assert_eq!(saved.as_raw_rgb(), img.as_raw_rgb());
+    }
+}
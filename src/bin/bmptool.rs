@@ -0,0 +1,344 @@
+//! A command line tool for inspecting, re-encoding, resizing and comparing BMP images.
+//!
+//! Run `bmptool help` for the usage of every subcommand. The tool is built when the `cli` feature
+//! is enabled:
+//!
+//! ```text
+//! cargo run --features cli --bin bmptool -- info image.bmp
+//! ```
+
+extern crate bmp;
+
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+use bmp::{Filter, Image, Palette, Pixel, Quantizer};
+
+const USAGE: &str = "\
+Usage: bmptool <command> [arguments]
+
+Commands:
+    info <file>...
+        Prints the headers of each file, without decoding the pixels.
+    convert <input> <output> [--bpp <bits>]
+        Decodes the input, and re-encodes it as the output without
+        compression. The bits per pixel are 24 (the default), or 1, 4 or 8
+        for an image with a palette of at most 2, 16 or 256 colors.
+    resize <input> <output> <width>x<height> [--filter <filter>]
+        Resizes the input with the filter nearest (the default), bilinear,
+        bicubic or lanczos3.
+    diff <a> <b> [--heatmap <output>]
+        Compares two images pixel by pixel, and exits with 1 if they differ.
+    help
+        Prints this message.";
+
+// Errors are reported as a message, and exit the tool with the status 2
+type CliResult<T> = Result<T, String>;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let stdout = io::stdout();
+    match run(&args, &mut stdout.lock()) {
+        Ok(status) => process::exit(status),
+        Err(msg) => {
+            eprintln!("bmptool: {}", msg);
+            process::exit(2);
+        }
+    }
+}
+
+// Runs the command given by `args`, and returns the exit status of the tool
+fn run(args: &[String], out: &mut dyn Write) -> CliResult<i32> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(format!("missing command\n\n{}", USAGE)),
+    };
+    match command {
+        "info" => info(&Args::parse(rest, &[])?, out).map(|_| 0),
+        "convert" => convert(&Args::parse(rest, &["bpp"])?, out).map(|_| 0),
+        "resize" => resize(&Args::parse(rest, &["filter"])?, out).map(|_| 0),
+        "diff" => diff(&Args::parse(rest, &["heatmap"])?, out),
+        "help" | "-h" | "--help" => write(out, USAGE).map(|_| 0),
+        other => Err(format!("unknown command `{}`\n\n{}", other, USAGE)),
+    }
+}
+
+// The positional arguments and the `--name value` options of a command
+struct Args<'a> {
+    positional: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Args<'a> {
+    fn parse(args: &'a [String], known_options: &[&str]) -> CliResult<Args<'a>> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg);
+                continue;
+            }
+            let name = &arg[2..];
+            if !known_options.contains(&name) {
+                return Err(format!("unknown option `{}`", arg));
+            }
+            match args.next() {
+                Some(value) => parsed.options.push((name, value)),
+                None => return Err(format!("missing value for `{}`", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    // Returns the positional arguments, if there is one for each of `names`
+    fn expect(&self, names: &[&str]) -> CliResult<&[&'a str]> {
+        if self.positional.len() == names.len() {
+            Ok(&self.positional)
+        } else {
+            Err(format!(
+                "expected the arguments <{}>, but got {}",
+                names.join("> <"),
+                self.positional.len()
+            ))
+        }
+    }
+
+    // Returns the value of the last occurrence of the option
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, value)| value)
+    }
+}
+
+fn write(out: &mut dyn Write, text: &str) -> CliResult<()> {
+    writeln!(out, "{}", text).map_err(|e| e.to_string())
+}
+
+fn open(path: &str) -> CliResult<Image> {
+    bmp::open(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn save(img: &Image, path: &str) -> CliResult<()> {
+    img.save(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn info(args: &Args, out: &mut dyn Write) -> CliResult<()> {
+    if args.positional.is_empty() {
+        return Err(String::from("expected at least one <file>"));
+    }
+    for path in &args.positional {
+        let meta = bmp::open_metadata(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut lines = vec![
+            format!("{}:", path),
+            format!("  version:        {:?}", meta.version),
+            format!("  dimensions:     {}x{}", meta.width, meta.height),
+            format!("  bits per pixel: {}", meta.bits_per_pixel),
+            format!("  compression:    {:?}", meta.compression),
+            format!("  palette size:   {}", meta.palette_size),
+            format!("  dpi:            {:.0}x{:.0}", meta.dpi.0, meta.dpi.1),
+            format!("  file size:      {}", meta.file_size),
+        ];
+        if let Some(color_space) = meta.color_space {
+            lines.push(format!("  color space:    {:?}", color_space.cs_type()));
+        }
+        write(out, &lines.join("\n"))?;
+    }
+    Ok(())
+}
+
+fn convert(args: &Args, out: &mut dyn Write) -> CliResult<()> {
+    let paths = args.expect(&["input", "output"])?;
+    let colors = match args.option("bpp") {
+        None | Some("24") => None,
+        Some("1") => Some(2),
+        Some("4") => Some(16),
+        Some("8") => Some(256),
+        Some(other) => return Err(format!("unsupported bits per pixel `{}`", other)),
+    };
+    let img = open(paths[0])?;
+    match colors {
+        None => save(&img, paths[1])?,
+        Some(colors) => {
+            // The palette is padded to the requested depth, even if the image has fewer colors
+            let quantized = img.quantize(colors, Quantizer::MedianCut);
+            let mut palette = quantized.palette().colors().to_vec();
+            palette.resize(colors, Pixel::new(0, 0, 0));
+            img.to_indexed(&Palette::new(palette))
+                .save(paths[1])
+                .map_err(|e| format!("{}: {}", paths[1], e))?
+        }
+    }
+    write(out, &format!("{} -> {}", paths[0], paths[1]))
+}
+
+fn resize(args: &Args, out: &mut dyn Write) -> CliResult<()> {
+    let paths = args.expect(&["input", "output", "width>x<height"])?;
+    let (width, height) = parse_size(paths[2])?;
+    let filter = match args.option("filter") {
+        None | Some("nearest") => Filter::Nearest,
+        Some("bilinear") => Filter::Bilinear,
+        Some("bicubic") => Filter::Bicubic,
+        Some("lanczos3") => Filter::Lanczos3,
+        Some(other) => return Err(format!("unknown filter `{}`", other)),
+    };
+    let img = open(paths[0])?;
+    save(&img.resize_with(width, height, filter), paths[1])?;
+    write(
+        out,
+        &format!(
+            "{} ({}x{}) -> {} ({}x{})",
+            paths[0],
+            img.get_width(),
+            img.get_height(),
+            paths[1],
+            width,
+            height
+        ),
+    )
+}
+
+fn parse_size(size: &str) -> CliResult<(u32, u32)> {
+    let invalid = || format!("invalid size `{}`, expected <width>x<height>", size);
+    let mut parts = size.splitn(2, 'x');
+    let width = parts
+        .next()
+        .and_then(|w| w.parse().ok())
+        .ok_or_else(invalid)?;
+    let height = parts
+        .next()
+        .and_then(|h| h.parse().ok())
+        .ok_or_else(invalid)?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+fn diff(args: &Args, out: &mut dyn Write) -> CliResult<i32> {
+    let paths = args.expect(&["a", "b"])?;
+    let (a, b) = (open(paths[0])?, open(paths[1])?);
+    let diff = bmp::diff(&a, &b).map_err(|e| e.to_string())?;
+    if let Some(path) = args.option("heatmap") {
+        save(&diff.heatmap(), path)?;
+    }
+    if diff.is_identical() {
+        write(out, "identical")?;
+        return Ok(0);
+    }
+    write(
+        out,
+        &format!(
+            "{} of {} pixels differ, by at most {}",
+            diff.differing_pixels,
+            a.get_width() as u64 * a.get_height() as u64,
+            diff.max_difference
+        ),
+    )?;
+    Ok(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // A file in the temporary directory, which is removed when it is dropped, even if the test
+    // fails
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            let name = format!("bmptool-{}-{}", process::id(), name);
+            TempFile(env::temp_dir().join(name))
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn run_args(args: &[&str]) -> (CliResult<i32>, String) {
+        let args: Vec<String> = args.iter().map(|&arg| String::from(arg)).collect();
+        let mut out = Vec::new();
+        let status = run(&args, &mut out);
+        (status, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn info_prints_the_headers() {
+        let (status, out) = run_args(&["info", "test/bmpsuite-2.5/g/pal8v5.bmp"]);
+        assert_eq!(Ok(0), status);
+        assert!(out.contains("version:        Five"));
+        assert!(out.contains("dimensions:     127x64"));
+        assert!(out.contains("color space:    SRgb"));
+    }
+
+    #[test]
+    fn convert_writes_the_bits_per_pixel() {
+        let output = TempFile::new("convert.bmp");
+        let input = "test/bmptestsuite-0.9/valid/8bpp-1x1.bmp";
+        assert_eq!(Ok(0), run_args(&["convert", input, output.path()]).0);
+        let meta = bmp::open_metadata(output.path()).unwrap();
+        assert_eq!(24, meta.bits_per_pixel);
+
+        let expected = bmp::open("test/rgbw.bmp").unwrap();
+        for &bpp in &["1", "4", "8"] {
+            let args = ["convert", "test/rgbw.bmp", output.path(), "--bpp", bpp];
+            assert_eq!(Ok(0), run_args(&args).0);
+            let meta = bmp::open_metadata(output.path()).unwrap();
+            assert_eq!(bpp, meta.bits_per_pixel.to_string());
+            if bpp != "1" {
+                // The four colors fit in the palette
+                let img = bmp::open(output.path()).unwrap();
+                assert_eq!(expected.as_raw_rgb(), img.as_raw_rgb());
+            }
+        }
+
+        let (status, _) = run_args(&["convert", "in.bmp", "out.bmp", "--bpp", "16"]);
+        assert!(status.is_err());
+    }
+
+    #[test]
+    fn resize_and_diff_images() {
+        let output = TempFile::new("resize.bmp");
+        let args = ["resize", "test/rgbw.bmp", output.path(), "4x6"];
+        assert_eq!(Ok(0), run_args(&args).0);
+        let img = bmp::open(output.path()).unwrap();
+        assert_eq!((4, 6), (img.get_width(), img.get_height()));
+
+        let (status, out) = run_args(&["diff", output.path(), output.path()]);
+        assert_eq!((Ok(0), "identical\n"), (status, out.as_str()));
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        assert!(run_args(&[]).0.is_err());
+        assert!(run_args(&["frobnicate"]).0.is_err());
+        assert!(run_args(&["resize", "a.bmp", "b.bmp", "4by6"]).0.is_err());
+        assert!(run_args(&["diff", "a.bmp", "b.bmp", "--unknown", "1"])
+            .0
+            .is_err());
+        assert!(run_args(&["diff", "a.bmp", "--heatmap"]).0.is_err());
+    }
+
+    #[test]
+    fn sizes_are_parsed() {
+        assert_eq!(Ok((4, 6)), parse_size("4x6"));
+        assert!(parse_size("0x6").is_err());
+        assert!(parse_size("4x").is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use rgba::{blend_straight, mul_div_255};
+use Pixel;
+
+/// The ways a drawn color can be combined with the pixel it is drawn onto.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlendMode {
+    /// Replaces the pixel with the drawn color.
+    Replace,
+    /// Blends the drawn color onto the pixel with the given opacity, from 0 (invisible) to 255
+    /// (the same as `Replace`).
+    Alpha(u8),
+    /// Adds the color channels, saturating at 255.
+    Add,
+    /// Multiplies the color channels, which darkens the pixel.
+    Multiply,
+    /// Multiplies the inverted color channels, which lightens the pixel.
+    Screen,
+    /// Combines the color channels with a bitwise exclusive or. Drawing twice restores the pixel.
+    Xor,
+}
+
+impl BlendMode {
+    /// Returns the result of drawing the color `src` onto the pixel `dst`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{BlendMode, Pixel};
+    ///
+    /// let dst = Pixel::new(100, 200, 0);
+    /// let src = Pixel::new(200, 100, 255);
+    /// assert_eq!(Pixel::new(255, 255, 255), BlendMode::Add.blend(dst, src));
+    /// assert_eq!(Pixel::new(150, 150, 128), BlendMode::Alpha(128).blend(dst, src));
+    /// ```
+    pub fn blend(self, dst: Pixel, src: Pixel) -> Pixel {
+        let f: fn(u8, u8) -> u8 = match self {
+            BlendMode::Replace => return src,
+            BlendMode::Alpha(alpha) => {
+                return Pixel::new(
+                    blend_straight(dst.r, src.r, alpha),
+                    blend_straight(dst.g, src.g, alpha),
+                    blend_straight(dst.b, src.b, alpha),
+                )
+            }
+            BlendMode::Add => |d, s| d.saturating_add(s),
+            BlendMode::Multiply => |d, s| mul_div_255(d, s),
+            BlendMode::Screen => |d, s| 255 - mul_div_255(255 - d, 255 - s),
+            BlendMode::Xor => |d, s| d ^ s,
+        };
+        Pixel::new(f(dst.r, src.r), f(dst.g, src.g), f(dst.b, src.b))
+    }
+}
+
+/// A color together with the `BlendMode` used when it is drawn.
+///
+/// The drawing functions accept either a `Paint` or a `Pixel`, where a `Pixel` is drawn with
+/// `BlendMode::Replace`.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, BlendMode, Image, Paint};
+///
+/// let mut img = Image::new(100, 80);
+/// img.fill(consts::WHITE);
+/// img.fill_rect(10, 10, 20, 20, Paint::new(consts::RED, BlendMode::Multiply));
+/// assert_eq!(consts::RED, img.get_pixel(10, 10));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Paint {
+    pub color: Pixel,
+    pub mode: BlendMode,
+}
+
+impl Paint {
+    /// Creates a new `Paint`.
+    pub fn new(color: Pixel, mode: BlendMode) -> Paint {
+        Paint { color, mode }
+    }
+
+    /// Returns the result of drawing the paint onto the pixel `dst`.
+    #[inline]
+    pub fn apply(&self, dst: Pixel) -> Pixel {
+        self.mode.blend(dst, self.color)
+    }
+}
+
+/// Creates a `Paint` that replaces the pixels it is drawn onto.
+impl From<Pixel> for Paint {
+    fn from(color: Pixel) -> Paint {
+        Paint::new(color, BlendMode::Replace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_modes_combine_the_channels() {
+        let dst = Pixel::new(0, 128, 255);
+        let src = Pixel::new(255, 128, 64);
+
+        assert_eq!(BlendMode::Replace.blend(dst, src), src);
+        assert_eq!(BlendMode::Alpha(0).blend(dst, src), dst);
+        assert_eq!(BlendMode::Alpha(255).blend(dst, src), src);
+        assert_eq!(BlendMode::Add.blend(dst, src), Pixel::new(255, 255, 255));
+        assert_eq!(BlendMode::Multiply.blend(dst, src), Pixel::new(0, 64, 64));
+        assert_eq!(BlendMode::Screen.blend(dst, src), Pixel::new(255, 192, 255));
+        assert_eq!(BlendMode::Xor.blend(dst, src), Pixel::new(255, 0, 191));
+        let xor = BlendMode::Xor.blend(dst, src);
+        assert_eq!(BlendMode::Xor.blend(xor, src), dst);
+    }
+}
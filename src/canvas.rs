@@ -0,0 +1,287 @@
+use rgba::{blend_straight, mul_div_255};
+use transform::clip;
+use {BlendMode, Image, ImageRgba, Pixel, PixelRgba};
+
+/// A stack of layers that are blended together into a single `Image` with `Canvas::flatten`.
+///
+/// The layers are drawn in the order they are pushed, each onto the result of the ones before
+/// it, starting with a canvas filled with the background color.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, BlendMode, Canvas, Image, Layer};
+///
+/// let mut chart = Image::new(80, 60);
+/// chart.fill(consts::BLUE);
+/// let mut highlight = Image::new(20, 20);
+/// highlight.fill(consts::RED);
+///
+/// let mut canvas = Canvas::new(100, 80).with_background(consts::WHITE);
+/// canvas.push(Layer::new(chart).with_position(10, 10));
+/// canvas.push(
+///     Layer::new(highlight)
+///         .with_position(0, 0)
+///         .with_blend_mode(BlendMode::Screen),
+/// );
+///
+/// let img = canvas.flatten();
+/// assert_eq!(consts::WHITE, img.get_pixel(5, 5));
+/// assert_eq!(consts::MAGENTA, img.get_pixel(15, 15));
+/// assert_eq!(consts::BLUE, img.get_pixel(50, 50));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    background: Pixel,
+    layers: Vec<Layer>,
+}
+
+impl Canvas {
+    /// Returns a new empty `Canvas` of `width` times `height` pixels, with a black background.
+    pub fn new(width: u32, height: u32) -> Canvas {
+        Canvas {
+            width,
+            height,
+            background: Pixel::new(0, 0, 0),
+            layers: Vec::new(),
+        }
+    }
+
+    /// Sets the color the canvas is filled with before the layers are drawn.
+    pub fn with_background(mut self, background: Pixel) -> Canvas {
+        self.background = background;
+        self
+    }
+
+    /// Returns the `width` of the canvas.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the canvas.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Adds `layer` on top of the layers pushed before it. An `Image` or an `ImageRgba` can be
+    /// pushed directly, which places it in the upper left corner.
+    pub fn push<L: Into<Layer>>(&mut self, layer: L) {
+        self.layers.push(layer.into());
+    }
+
+    /// Returns the layers of the canvas, from the bottom to the top.
+    #[inline]
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Returns the layers of the canvas mutably, to reorder, edit or remove them before the
+    /// canvas is flattened.
+    #[inline]
+    pub fn layers_mut(&mut self) -> &mut Vec<Layer> {
+        &mut self.layers
+    }
+
+    /// Returns a new `Image` with every layer drawn onto the background, from the bottom to the
+    /// top. The parts of the layers that fall outside of the canvas are ignored.
+    pub fn flatten(&self) -> Image {
+        let mut img = Image::new(self.width, self.height);
+        img.fill(self.background);
+        for layer in &self.layers {
+            layer.draw(&mut img);
+        }
+        img
+    }
+}
+
+/// An image placed on a `Canvas`, drawn with an opacity and a `BlendMode`.
+///
+/// The layer is either an opaque `Image`, or an `ImageRgba` where the alpha of each pixel is
+/// multiplied with the opacity of the layer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer {
+    source: LayerSource,
+    x: i64,
+    y: i64,
+    opacity: u8,
+    mode: BlendMode,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum LayerSource {
+    Rgb(Image),
+    Rgba(ImageRgba),
+}
+
+impl Layer {
+    /// Returns a new fully opaque `Layer` of `image` in the upper left corner, drawn with
+    /// `BlendMode::Replace`.
+    pub fn new(image: Image) -> Layer {
+        Layer::with_source(LayerSource::Rgb(image))
+    }
+
+    /// Returns a new `Layer` of `image` like `Layer::new`, where the alpha channel of the image
+    /// determines how much each pixel covers the layers below it.
+    pub fn from_rgba(image: ImageRgba) -> Layer {
+        Layer::with_source(LayerSource::Rgba(image))
+    }
+
+    fn with_source(source: LayerSource) -> Layer {
+        Layer {
+            source,
+            x: 0,
+            y: 0,
+            opacity: 255,
+            mode: BlendMode::Replace,
+        }
+    }
+
+    /// Places the upper left corner of the layer at `x` and `y` on the canvas.
+    pub fn with_position(mut self, x: i64, y: i64) -> Layer {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Sets the opacity of the layer, from 0 (invisible) to 255 (fully opaque).
+    pub fn with_opacity(mut self, opacity: u8) -> Layer {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Sets the `BlendMode` that combines the layer with the layers below it.
+    pub fn with_blend_mode(mut self, mode: BlendMode) -> Layer {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the position of the upper left corner of the layer on the canvas.
+    #[inline]
+    pub fn position(&self) -> (i64, i64) {
+        (self.x, self.y)
+    }
+
+    /// Returns the `width` of the layer.
+    pub fn get_width(&self) -> u32 {
+        match self.source {
+            LayerSource::Rgb(ref img) => img.get_width(),
+            LayerSource::Rgba(ref img) => img.get_width(),
+        }
+    }
+
+    /// Returns the `height` of the layer.
+    pub fn get_height(&self) -> u32 {
+        match self.source {
+            LayerSource::Rgb(ref img) => img.get_height(),
+            LayerSource::Rgba(ref img) => img.get_height(),
+        }
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> PixelRgba {
+        match self.source {
+            LayerSource::Rgb(ref img) => img.get_pixel(x, y).into(),
+            LayerSource::Rgba(ref img) => img.get_pixel(x, y),
+        }
+    }
+
+    // Blends the mode's result with the pixel below it, by the alpha of the pixel and the
+    // opacity of the layer
+    fn draw(&self, dst: &mut Image) {
+        if self.opacity == 0 {
+            return;
+        }
+        let area = match clip(dst, self.x, self.y, self.get_width(), self.get_height()) {
+            Some(area) => area,
+            None => return,
+        };
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let src = self.get_pixel(area.src_x + x, area.src_y + y);
+                let alpha = mul_div_255(src.a, self.opacity);
+                if alpha == 0 {
+                    continue;
+                }
+                let (dx, dy) = (area.dst_x + x, area.dst_y + y);
+                let below = dst.get_pixel(dx, dy);
+                let blended = self.mode.blend(below, Pixel::new(src.r, src.g, src.b));
+                let px = Pixel::new(
+                    blend_straight(below.r, blended.r, alpha),
+                    blend_straight(below.g, blended.g, alpha),
+                    blend_straight(below.b, blended.b, alpha),
+                );
+                dst.set_pixel(dx, dy, px);
+            }
+        }
+    }
+}
+
+impl From<Image> for Layer {
+    fn from(image: Image) -> Layer {
+        Layer::new(image)
+    }
+}
+
+impl From<ImageRgba> for Layer {
+    fn from(image: ImageRgba) -> Layer {
+        Layer::from_rgba(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn layers_are_drawn_in_order() {
+        let mut red = Image::new(4, 4);
+        red.fill(consts::RED);
+        let mut green = Image::new(4, 4);
+        green.fill(consts::LIME);
+
+        let mut canvas = Canvas::new(6, 6);
+        canvas.push(red.clone());
+        canvas.push(Layer::new(green.clone()).with_position(2, 2));
+        let img = canvas.flatten();
+        assert_eq!(consts::RED, img.get_pixel(1, 1));
+        assert_eq!(consts::LIME, img.get_pixel(3, 3));
+        assert_eq!(consts::BLACK, img.get_pixel(5, 0));
+
+        canvas.layers_mut().reverse();
+        assert_eq!(consts::RED, canvas.flatten().get_pixel(3, 3));
+    }
+
+    #[test]
+    fn opacity_matches_compositing() {
+        let mut icon = ImageRgba::new(2, 2);
+        icon.set_pixel(0, 0, PixelRgba::new(255, 0, 0, 128));
+        icon.set_pixel(1, 1, PixelRgba::new(0, 0, 255, 255));
+
+        let mut canvas = Canvas::new(2, 2);
+        canvas.push(icon.clone());
+        let mut expected = Image::new(2, 2);
+        expected.composite_over(&icon, 0, 0);
+        assert_eq!(expected, canvas.flatten());
+
+        canvas.layers_mut()[0] = Layer::from_rgba(icon).with_opacity(0);
+        assert_eq!(Image::new(2, 2), canvas.flatten());
+    }
+
+    #[test]
+    fn layers_outside_of_the_canvas_are_clipped() {
+        let mut img = Image::new(4, 4);
+        img.fill(consts::WHITE);
+        let mut canvas = Canvas::new(3, 3);
+        canvas.push(Layer::new(img.clone()).with_position(-2, 2));
+        canvas.push(Layer::new(img).with_position(10, 10));
+
+        let flat = canvas.flatten();
+        assert_eq!(consts::WHITE, flat.get_pixel(1, 2));
+        assert_eq!(consts::BLACK, flat.get_pixel(2, 2));
+        assert_eq!(consts::BLACK, flat.get_pixel(1, 1));
+    }
+}
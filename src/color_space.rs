@@ -0,0 +1,237 @@
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use std::io::{self, Read};
+
+use Image;
+
+// The values of the color space type, as four character codes
+const LCS_CALIBRATED_RGB: u32 = 0;
+const LCS_SRGB: u32 = 0x7352_4742;
+const LCS_WINDOWS_COLOR_SPACE: u32 = 0x5769_6E20;
+const PROFILE_LINKED: u32 = 0x4C49_4E4B;
+const PROFILE_EMBEDDED: u32 = 0x4D42_4544;
+
+/// The type of color space of a BMP Version 4 or 5 image.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ColorSpaceType {
+    /// The color space is given by the endpoints and the gamma values.
+    CalibratedRgb,
+    /// The sRGB color space.
+    SRgb,
+    /// The default color space of the system, which is sRGB on Windows.
+    WindowsColorSpace,
+    /// The color space is given by an ICC profile in a separate file.
+    LinkedProfile,
+    /// The color space is given by an ICC profile embedded in the file.
+    EmbeddedProfile,
+    /// A color space type that is not known, with the value from the header.
+    Other(u32),
+}
+
+impl ColorSpaceType {
+    fn from_u32(val: u32) -> ColorSpaceType {
+        match val {
+            LCS_CALIBRATED_RGB => ColorSpaceType::CalibratedRgb,
+            LCS_SRGB => ColorSpaceType::SRgb,
+            LCS_WINDOWS_COLOR_SPACE => ColorSpaceType::WindowsColorSpace,
+            PROFILE_LINKED => ColorSpaceType::LinkedProfile,
+            PROFILE_EMBEDDED => ColorSpaceType::EmbeddedProfile,
+            other => ColorSpaceType::Other(other),
+        }
+    }
+}
+
+/// The rendering intent of a BMP Version 5 image, used when its colors are mapped to a color
+/// space with a smaller gamut.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RenderingIntent {
+    /// Maintains saturation, for business charts and other graphics.
+    Saturation,
+    /// Maintains colorimetric match, for graphic designs and named colors.
+    RelativeColorimetric,
+    /// Maintains contrast, for photographs and natural images.
+    Perceptual,
+    /// Maintains the white point, for proofs and simulations of other devices.
+    AbsoluteColorimetric,
+    /// An intent that is not known, with the value from the header.
+    Other(u32),
+}
+
+impl RenderingIntent {
+    fn from_u32(val: u32) -> RenderingIntent {
+        match val {
+            1 => RenderingIntent::Saturation,
+            2 => RenderingIntent::RelativeColorimetric,
+            4 => RenderingIntent::Perceptual,
+            8 => RenderingIntent::AbsoluteColorimetric,
+            other => RenderingIntent::Other(other),
+        }
+    }
+}
+
+/// A color in the CIE XYZ color space, used for the endpoints of a calibrated color space.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CieXyz {
+    // The coordinates are stored as 2.30 fixed point numbers in the header
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+impl CieXyz {
+    /// Returns the X coordinate.
+    #[inline]
+    pub fn x(&self) -> f64 {
+        fixed_2_30(self.x)
+    }
+
+    /// Returns the Y coordinate.
+    #[inline]
+    pub fn y(&self) -> f64 {
+        fixed_2_30(self.y)
+    }
+
+    /// Returns the Z coordinate.
+    #[inline]
+    pub fn z(&self) -> f64 {
+        fixed_2_30(self.z)
+    }
+
+    fn read<R: Read>(source: &mut R) -> io::Result<CieXyz> {
+        Ok(CieXyz {
+            x: source.read_i32::<LittleEndian>()?,
+            y: source.read_i32::<LittleEndian>()?,
+            z: source.read_i32::<LittleEndian>()?,
+        })
+    }
+}
+
+fn fixed_2_30(val: i32) -> f64 {
+    val as f64 / (1 << 30) as f64
+}
+
+fn fixed_16_16(val: u32) -> f64 {
+    val as f64 / (1 << 16) as f64
+}
+
+/// The color space of a BMP Version 4 or 5 image, read from the header.
+///
+/// The endpoints and the gamma values are only used by calibrated color spaces, and the rendering
+/// intent is only stored in BMP Version 5 images.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{ColorSpaceType, RenderingIntent};
+///
+/// let img = bmp::open("test/bmpsuite-2.5/g/pal8v5.bmp").unwrap();
+/// let color_space = img.color_space().unwrap();
+/// assert_eq!(ColorSpaceType::SRgb, color_space.cs_type());
+/// assert_eq!(Some(RenderingIntent::Perceptual), color_space.intent());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ColorSpace {
+    cs_type: u32,
+    endpoints: [CieXyz; 3],
+    gamma: [u32; 3],
+    intent: Option<u32>,
+}
+
+impl ColorSpace {
+    /// Returns the type of the color space.
+    #[inline]
+    pub fn cs_type(&self) -> ColorSpaceType {
+        ColorSpaceType::from_u32(self.cs_type)
+    }
+
+    /// Returns the endpoints of the red, green and blue channels.
+    #[inline]
+    pub fn endpoints(&self) -> [CieXyz; 3] {
+        self.endpoints
+    }
+
+    /// Returns the gamma values of the red, green and blue channels.
+    #[inline]
+    pub fn gamma(&self) -> [f64; 3] {
+        [
+            fixed_16_16(self.gamma[0]),
+            fixed_16_16(self.gamma[1]),
+            fixed_16_16(self.gamma[2]),
+        ]
+    }
+
+    /// Returns the rendering intent, or `None` for BMP Version 4 images.
+    #[inline]
+    pub fn intent(&self) -> Option<RenderingIntent> {
+        self.intent.map(RenderingIntent::from_u32)
+    }
+
+    // Reads the fields that follow the bit masks of a version 4 header, and the intent of a
+    // version 5 header
+    pub(crate) fn read<R: Read>(source: &mut R, is_v5: bool) -> io::Result<ColorSpace> {
+        let cs_type = source.read_u32::<LittleEndian>()?;
+        let endpoints = [
+            CieXyz::read(source)?,
+            CieXyz::read(source)?,
+            CieXyz::read(source)?,
+        ];
+        let gamma = [
+            source.read_u32::<LittleEndian>()?,
+            source.read_u32::<LittleEndian>()?,
+            source.read_u32::<LittleEndian>()?,
+        ];
+        let intent = if is_v5 {
+            Some(source.read_u32::<LittleEndian>()?)
+        } else {
+            None
+        };
+        Ok(ColorSpace {
+            cs_type,
+            endpoints,
+            gamma,
+            intent,
+        })
+    }
+}
+
+impl Image {
+    /// Returns the color space of the BMP Version 4 or 5 file the image was decoded from, or
+    /// `None` for other images.
+    #[inline]
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        self.source_format.and_then(|format| format.color_space)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v4_images_have_no_intent() {
+        let img = ::open("test/bmpsuite-2.5/g/pal8v4.bmp").unwrap();
+        let color_space = img.color_space().unwrap();
+        assert_eq!(None, color_space.intent());
+    }
+
+    #[test]
+    fn v3_images_have_no_color_space() {
+        let img = ::open("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+        assert_eq!(None, img.color_space());
+    }
+
+    #[test]
+    fn fixed_point_values_are_converted() {
+        let mut bytes = [0; 56];
+        bytes[4..8].copy_from_slice(&(1i32 << 30).to_le_bytes());
+        bytes[40..44].copy_from_slice(&(5u32 << 15).to_le_bytes());
+        let color_space = ColorSpace::read(&mut &bytes[..], true).unwrap();
+
+        assert_eq!(ColorSpaceType::CalibratedRgb, color_space.cs_type());
+        assert_eq!(1.0, color_space.endpoints()[0].x());
+        assert_eq!([2.5, 0.0, 0.0], color_space.gamma());
+        assert_eq!(Some(RenderingIntent::Other(0)), color_space.intent());
+    }
+}
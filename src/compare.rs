@@ -0,0 +1,247 @@
+use draw::lerp;
+use {BmpResult, Image, Pixel};
+
+/// The differences between two images of the same size, returned by `bmp::diff`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diff {
+    /// An image of the absolute differences between the color channels of the two images.
+    /// Pixels that are equal in both images are black.
+    pub image: Image,
+    /// The number of pixels that are different in the two images.
+    pub differing_pixels: usize,
+    /// The largest difference of a color channel between the two images.
+    pub max_difference: u8,
+}
+
+impl Diff {
+    /// Returns `true` if the two images are equal.
+    #[inline]
+    pub fn is_identical(&self) -> bool {
+        self.differing_pixels == 0
+    }
+
+    /// Returns a new `Image` where the differences are shown as a heatmap. Equal pixels are
+    /// black, and the color goes from blue through red to yellow as the largest channel
+    /// difference of each pixel grows, which makes small differences easy to spot.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let a = Image::new(100, 80);
+    /// let mut b = a.clone();
+    /// b.set_pixel(0, 0, consts::WHITE);
+    ///
+    /// let heatmap = bmp::diff(&a, &b).unwrap().heatmap();
+    /// assert_eq!(consts::YELLOW, heatmap.get_pixel(0, 0));
+    /// assert_eq!(consts::BLACK, heatmap.get_pixel(1, 0));
+    /// ```
+    pub fn heatmap(&self) -> Image {
+        let stops = [
+            Pixel::new(0, 0, 255),
+            Pixel::new(255, 0, 0),
+            Pixel::new(255, 255, 0),
+        ];
+        let mut heatmap = Image::new(self.image.get_width(), self.image.get_height());
+        for (x, y) in self.image.coordinates() {
+            let px = self.image.get_pixel(x, y);
+            let d = px.r.max(px.g).max(px.b);
+            if d > 0 {
+                // Spread the differences from 1 to 255 over the two halves of the ramp
+                let t = (d - 1) as f32 / 254.0 * 2.0;
+                let color = if t < 1.0 {
+                    lerp(stops[0], stops[1], t)
+                } else {
+                    lerp(stops[1], stops[2], t - 1.0)
+                };
+                heatmap.set_pixel(x, y, color);
+            }
+        }
+        heatmap
+    }
+}
+
+impl Image {
+    /// Returns `true` if the image is approximately equal to `other`. A pixel differs if one of
+    /// its color channels differs by more than `per_channel_tolerance`, and the images are
+    /// approximately equal if at most `max_differing_fraction` of the pixels differ, between 0
+    /// and 1. Images with different dimensions are never equal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let a = Image::new(10, 10);
+    /// let mut b = a.clone();
+    /// b.fill(Pixel::new(1, 0, 1));
+    /// b.set_pixel(0, 0, consts::WHITE);
+    ///
+    /// assert!(!a.approx_eq(&b, 1, 0.0));
+    /// assert!(a.approx_eq(&b, 1, 0.01));
+    /// ```
+    pub fn approx_eq(
+        &self,
+        other: &Image,
+        per_channel_tolerance: u8,
+        max_differing_fraction: f64,
+    ) -> bool {
+        if self.check_same_dimensions(other).is_err() {
+            return false;
+        }
+        let differs = |a: &Pixel, b: &Pixel| {
+            a.r.abs_diff(b.r) > per_channel_tolerance
+                || a.g.abs_diff(b.g) > per_channel_tolerance
+                || a.b.abs_diff(b.b) > per_channel_tolerance
+        };
+        let differing = self
+            .data
+            .iter()
+            .zip(&other.data)
+            .filter(|&(a, b)| differs(a, b))
+            .count();
+        differing as f64 <= max_differing_fraction * self.data.len() as f64
+    }
+
+    /// Returns a 64-bit FNV-1a hash of the dimensions and the pixels of the image. The pixels are
+    /// hashed from the top row to the bottom row in the red, green, blue order, so the hash only
+    /// depends on the content of the image, and is stable between versions and platforms.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// let hash = img.content_hash();
+    /// assert_eq!(hash, img.clone().content_hash());
+    ///
+    /// img.set_pixel(0, 0, consts::WHITE);
+    /// assert_ne!(hash, img.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let hash_bytes = |hash: u64, bytes: &[u8]| {
+            bytes
+                .iter()
+                .fold(hash, |hash, &b| (hash ^ b as u64).wrapping_mul(PRIME))
+        };
+
+        let mut hash = hash_bytes(OFFSET_BASIS, &self.width.to_le_bytes());
+        hash = hash_bytes(hash, &self.height.to_le_bytes());
+        // The rows are stored from bottom to top internally
+        for row in self.data.chunks(self.width.max(1) as usize).rev() {
+            for px in row {
+                hash = hash_bytes(hash, &[px.r, px.g, px.b]);
+            }
+        }
+        hash
+    }
+}
+
+/// Compares the images `a` and `b` pixel by pixel, and returns the `Diff` of them.
+///
+/// Returns a `BmpError` if the two images do not have the same dimensions.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{Image, Pixel};
+///
+/// let a = Image::new(100, 80);
+/// let mut b = a.clone();
+/// b.set_pixel(10, 10, Pixel::new(3, 0, 2));
+///
+/// let diff = bmp::diff(&a, &b).unwrap();
+/// assert_eq!(1, diff.differing_pixels);
+/// assert_eq!(3, diff.max_difference);
+/// assert_eq!(Pixel::new(3, 0, 2), diff.image.get_pixel(10, 10));
+/// ```
+pub fn diff(a: &Image, b: &Image) -> BmpResult<Diff> {
+    let image = a.zip_map(b, |a, b| {
+        Pixel::new(a.r.abs_diff(b.r), a.g.abs_diff(b.g), a.b.abs_diff(b.b))
+    })?;
+    let black = Pixel::new(0, 0, 0);
+    let differing_pixels = image.data.iter().filter(|&&px| px != black).count();
+    let max_difference = image
+        .data
+        .iter()
+        .map(|px| px.r.max(px.g).max(px.b))
+        .max()
+        .unwrap_or(0);
+    Ok(Diff {
+        image,
+        differing_pixels,
+        max_difference,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {consts, BmpError, BmpErrorKind};
+
+    #[test]
+    fn diff_counts_the_differing_pixels() {
+        let mut a = Image::new(4, 4);
+        a.fill(consts::GRAY);
+        let mut b = a.clone();
+        b.set_pixel(0, 0, consts::WHITE);
+        b.set_pixel(3, 3, Pixel::new(128, 129, 128));
+
+        let diff = diff(&a, &b).unwrap();
+        assert!(!diff.is_identical());
+        assert_eq!(diff.differing_pixels, 2);
+        assert_eq!(diff.max_difference, 127);
+        assert_eq!(diff.image.get_pixel(3, 3), Pixel::new(0, 1, 0));
+
+        let heatmap = diff.heatmap();
+        assert_eq!(heatmap.get_pixel(3, 3), Pixel::new(0, 0, 255));
+        assert_eq!(heatmap.get_pixel(0, 0), Pixel::new(253, 0, 2));
+        assert_eq!(heatmap.get_pixel(1, 1), consts::BLACK);
+
+        assert!(super::diff(&a, &a).unwrap().is_identical());
+        match super::diff(&a, &Image::new(4, 3)) {
+            Err(BmpError {
+                kind: BmpErrorKind::MismatchedDimensions,
+                ..
+            }) => (/* Expected */),
+            other => panic!("Expected mismatched dimensions, but was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn approx_eq_tolerates_small_differences() {
+        let mut a = Image::new(4, 4);
+        a.fill(consts::GRAY);
+        let mut b = a.clone();
+        b.set_pixel(0, 0, Pixel::new(130, 128, 128));
+        b.set_pixel(1, 0, Pixel::new(128, 129, 128));
+
+        assert!(a.approx_eq(&a, 0, 0.0));
+        assert!(!a.approx_eq(&b, 0, 0.1));
+        assert!(a.approx_eq(&b, 0, 0.125));
+        assert!(!a.approx_eq(&b, 1, 0.0));
+        assert!(a.approx_eq(&b, 1, 0.0625));
+        assert!(a.approx_eq(&b, 2, 0.0));
+        assert!(!a.approx_eq(&Image::new(4, 3), 255, 1.0));
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        // The FNV-1a hash of the eight zero bytes of the dimensions
+        assert_eq!(Image::new(0, 0).content_hash(), 0xa8c7_f832_281a_39c5);
+
+        let mut img = Image::new(2, 2);
+        img.set_pixel(1, 0, consts::RED);
+        let mut flipped = Image::new(2, 2);
+        flipped.set_pixel(1, 1, consts::RED);
+        assert_ne!(img.content_hash(), flipped.content_hash());
+        assert_ne!(
+            Image::new(1, 4).content_hash(),
+            Image::new(4, 1).content_hash()
+        );
+    }
+}
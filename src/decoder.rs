@@ -5,13 +5,15 @@ use byteorder::{LittleEndian, ReadBytesExt};
 use std::convert::{From, AsRef};
 use std::error::Error;
 use std::fmt;
-use std::io::{self, Cursor, Read, SeekFrom, Seek};
+use std::mem;
+use std::io::{self, Read, Seek, SeekFrom};
 
 // The BmpHeader always has a size of 14 bytes
 const BMP_HEADER_SIZE: u64 = 14;
 
 // Import structs/functions defined in lib.rs
 use super::*;
+use progress::Monitor;
 use self::BmpErrorKind::*;
 
 /// A result type, either containing an `Image` or a `BmpError`.
@@ -21,14 +23,53 @@ pub type BmpResult<T> = Result<T, BmpError>;
 #[derive(Debug)]
 pub struct BmpError {
     pub kind: BmpErrorKind,
-    pub details: String,
+    pub details: ErrorDetails,
 }
 
 impl BmpError {
-    fn new<T: AsRef<str>>(kind: BmpErrorKind, details: T) -> BmpError {
+    /// Returns the kind of the error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{BmpErrorKind, Image};
+    ///
+    /// let err = Image::from_raw_rgb(&[0; 2], 1, 1).unwrap_err();
+    /// assert_eq!(&BmpErrorKind::WrongBufferSize, err.kind());
+    /// ```
+    #[inline]
+    pub fn kind(&self) -> &BmpErrorKind {
+        &self.kind
+    }
+
+    pub(crate) fn new<T: AsRef<str>>(kind: BmpErrorKind, note: T) -> BmpError {
         BmpError {
-            kind: kind,
-            details: String::from(details.as_ref()),
+            kind,
+            details: ErrorDetails {
+                note: Some(String::from(note.as_ref())),
+                ..ErrorDetails::default()
+            },
+        }
+    }
+
+    pub(crate) fn mismatch<E, F>(
+        kind: BmpErrorKind,
+        field: &'static str,
+        expected: E,
+        found: F,
+    ) -> BmpError
+    where
+        E: fmt::Display,
+        F: fmt::Display,
+    {
+        BmpError {
+            kind,
+            details: ErrorDetails {
+                field: Some(field),
+                expected: Some(expected.to_string()),
+                found: Some(found.to_string()),
+                note: None,
+            },
         }
     }
 }
@@ -36,41 +77,182 @@ impl BmpError {
 impl fmt::Display for BmpError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match self.kind {
-            BmpIoError(ref error) => error.fmt(fmt),
+            BmpIoError(ref error) => write!(fmt, "I/O error: {}", error),
             ref e => {
                 let kind_desc: &str = e.as_ref();
-                write!(fmt, "{}: {}", kind_desc, self.description())
+                write!(fmt, "{}: {}", kind_desc, self.details)
+            }
+        }
+    }
+}
+
+/// The details of a `BmpError`, describing which header field or argument was rejected, and
+/// why.
+///
+/// # Example
+///
+/// ```
+/// let err = bmp::from_reader(&mut &b"GIF89a"[..]).unwrap_err();
+/// assert_eq!(Some("magic"), err.details.field);
+/// assert_eq!(Some("[66, 77]"), err.details.expected.as_deref());
+/// assert_eq!(Some("[71, 73]"), err.details.found.as_deref());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ErrorDetails {
+    /// The name of the header field or argument, e.g. `"bits_per_pixel"`.
+    pub field: Option<&'static str>,
+    /// The expected value or range of values.
+    pub expected: Option<String>,
+    /// The value that was found instead.
+    pub found: Option<String>,
+    /// Additional context that doesn't fit the other fields.
+    pub note: Option<String>,
+}
+
+/// Displays the details as `field: expected <expected>, found <found> (note)`, leaving out the
+/// missing parts.
+impl fmt::Display for ErrorDetails {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(field) = self.field {
+            write!(fmt, "{}: ", field)?;
+        }
+        match (&self.expected, &self.found) {
+            (Some(expected), Some(found)) => write!(fmt, "expected {}, found {}", expected, found)?,
+            (Some(expected), None) => write!(fmt, "expected {}", expected)?,
+            (None, Some(found)) => write!(fmt, "found {}", found)?,
+            (None, None) => (),
+        }
+        match self.note {
+            Some(ref note) if self.expected.is_some() || self.found.is_some() => {
+                write!(fmt, " ({})", note)
             }
+            Some(ref note) => fmt.write_str(note),
+            None => Ok(()),
         }
     }
 }
 
 impl From<io::Error> for BmpError {
     fn from(err: io::Error) -> BmpError {
-        BmpError::new(BmpIoError(err), "Io Error")
+        BmpError {
+            kind: BmpIoError(err),
+            details: ErrorDetails::default(),
+        }
+    }
+}
+
+/// Converts the `BmpError` into an `io::Error`. The wrapped error of a `BmpIoError` is returned
+/// as is, other errors are wrapped in an `io::Error` of the closest `io::ErrorKind`:
+/// `InvalidData` for malformed images, `Unsupported` for valid but unsupported images, and
+/// `InvalidInput` for invalid arguments.
+impl From<BmpError> for io::Error {
+    fn from(err: BmpError) -> io::Error {
+        let kind = match err.kind {
+            BmpIoError(err) => return err,
+            WrongMagicNumbers | UnsupportedHeader => io::ErrorKind::InvalidData,
+            UnsupportedBitsPerPixel | UnsupportedCompressionType | UnsupportedBmpVersion => {
+                io::ErrorKind::Unsupported
+            }
+            WrongBufferSize | MismatchedDimensions | InvalidDimensions | OutOfBounds => {
+                io::ErrorKind::InvalidInput
+            }
+            Cancelled => io::ErrorKind::Interrupted,
+        };
+        io::Error::new(kind, err)
     }
 }
 
+/// The wrapped `io::Error` of a `BmpIoError` is returned as the source of the error.
 impl Error for BmpError {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self.kind {
-            BmpIoError(ref e) => e.description(),
-            _ => &self.details,
+            BmpIoError(ref e) => Some(e),
+            _ => None,
         }
     }
 }
 
+// `BmpError` must stay usable across threads, e.g. in a `Box<dyn Error + Send + Sync>`.
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn is_send_sync<T: Send + Sync + 'static>() {}
+    is_send_sync::<BmpError>();
+}
+
 /// The different kinds of possible BMP errors.
+///
+/// New kinds may be added in the future, so matches on the kind must have a wildcard arm.
+/// Kinds compare as equal if they are the same kind, regardless of the wrapped `io::Error`.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum BmpErrorKind {
     WrongMagicNumbers,
     UnsupportedBitsPerPixel,
     UnsupportedCompressionType,
     UnsupportedBmpVersion,
     UnsupportedHeader,
+    WrongBufferSize,
+    MismatchedDimensions,
+    InvalidDimensions,
+    OutOfBounds,
+    /// The decoding or encoding was cancelled with a `CancellationToken`.
+    Cancelled,
     BmpIoError(io::Error),
 }
 
+impl BmpErrorKind {
+    /// Returns a numeric code for the kind, for reporting errors across FFI boundaries or to log
+    /// aggregation. The codes are stable across versions of the crate: the code of a kind never
+    /// changes, and new kinds get new codes.
+    ///
+    /// | Kind                         | Code |
+    /// |------------------------------|------|
+    /// | `BmpIoError`                 | 1    |
+    /// | `WrongMagicNumbers`          | 2    |
+    /// | `UnsupportedBitsPerPixel`    | 3    |
+    /// | `UnsupportedCompressionType` | 4    |
+    /// | `UnsupportedBmpVersion`      | 5    |
+    /// | `UnsupportedHeader`          | 6    |
+    /// | `WrongBufferSize`            | 7    |
+    /// | `MismatchedDimensions`       | 8    |
+    /// | `OutOfBounds`                | 9    |
+    /// | `InvalidDimensions`          | 10   |
+    /// | `Cancelled`                  | 11   |
+    ///
+    /// The code 0 is never used, so it can signal success.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let err = bmp::Image::from_raw_rgb(&[0; 2], 1, 1).unwrap_err();
+    /// assert_eq!(7, err.kind().code());
+    /// ```
+    pub fn code(&self) -> u32 {
+        match *self {
+            BmpIoError(_) => 1,
+            WrongMagicNumbers => 2,
+            UnsupportedBitsPerPixel => 3,
+            UnsupportedCompressionType => 4,
+            UnsupportedBmpVersion => 5,
+            UnsupportedHeader => 6,
+            WrongBufferSize => 7,
+            MismatchedDimensions => 8,
+            OutOfBounds => 9,
+            InvalidDimensions => 10,
+            Cancelled => 11,
+        }
+    }
+}
+
+impl PartialEq for BmpErrorKind {
+    fn eq(&self, other: &BmpErrorKind) -> bool {
+        mem::discriminant(self) == mem::discriminant(other)
+    }
+}
+
+impl Eq for BmpErrorKind {}
+
 impl AsRef<str> for BmpErrorKind {
     fn as_ref(&self) -> &str {
         match *self {
@@ -78,64 +260,689 @@ impl AsRef<str> for BmpErrorKind {
             UnsupportedBitsPerPixel => "Unsupported bits per pixel",
             UnsupportedCompressionType => "Unsupported compression type",
             UnsupportedBmpVersion => "Unsupported BMP version",
+            WrongBufferSize => "Wrong buffer size",
+            MismatchedDimensions => "Mismatched dimensions",
+            InvalidDimensions => "Invalid dimensions",
+            OutOfBounds => "Out of bounds",
+            Cancelled => "Cancelled",
             _ => "BMP Error",
         }
     }
 }
 
-pub fn decode_image(bmp_data: &mut Cursor<Vec<u8>>) -> BmpResult<Image> {
-    read_bmp_id(bmp_data)?;
-    let header = read_bmp_header(bmp_data)?;
-    let dib_header = read_bmp_dib_header(bmp_data)?;
+/// Decodes an `Image` from `source`, reading it front to back without seeking.
+pub fn decode_image<R: Read>(source: R) -> BmpResult<Image> {
+    Decoder::new().decode(source)
+}
 
-    let color_palette = read_color_palette(bmp_data, &dib_header)?;
+/// A reusable BMP decoder, with options for how images are decoded.
+///
+/// The decoder keeps its row and palette buffers between images, and `decode_into` reuses the
+/// pixel buffer of the destination image, so decoding many images of the same size doesn't
+/// allocate once the buffers have grown large enough.
+///
+/// The options are set with the `with_` methods. By default, the decoder is lenient, has the
+/// default limits, keeps the color palette, and decodes the whole image at full size, like
+/// `bmp::open`.
+///
+/// # Example
+///
+/// ```
+/// use std::fs::File;
+///
+/// let mut decoder = bmp::Decoder::new();
+/// let mut img = bmp::Image::new(0, 0);
+/// for _ in 0..3 {
+///     let mut f = File::open("test/rgbw.bmp").unwrap();
+///     decoder.decode_into(&mut f, &mut img).unwrap();
+///     assert_eq!(bmp::consts::RED, img.get_pixel(0, 0));
+/// }
+/// ```
+///
+/// Decoding a thumbnail of the upper half of an image:
+///
+/// ```
+/// use std::fs::File;
+///
+/// let mut decoder = bmp::Decoder::new()
+///     .with_strict(true)
+///     .with_region(0, 0, 320, 120)
+///     .with_downscale(4);
+/// let f = File::open("test/bmptestsuite-0.9/valid/24bpp-320x240.bmp").unwrap();
+/// let img = decoder.decode(f).unwrap();
+/// assert_eq!((80, 30), (img.get_width(), img.get_height()));
+/// ```
+#[derive(Debug)]
+pub struct Decoder {
+    row: Vec<u8>,
+    palette: Vec<Pixel>,
+    pixels: Vec<Pixel>,
+    strict: bool,
+    limits: Limits,
+    keep_indexed: bool,
+    region: Option<(u32, u32, u32, u32)>,
+    downscale: u32,
+    monitor: Monitor,
+}
 
-    let width = dib_header.width.abs() as u32;
-    let height = dib_header.height.abs() as u32;
-    let padding = width % 4;
+impl Default for Decoder {
+    fn default() -> Decoder {
+        Decoder {
+            row: Vec::new(),
+            palette: Vec::new(),
+            pixels: Vec::new(),
+            strict: false,
+            limits: Limits::default(),
+            keep_indexed: true,
+            region: None,
+            downscale: 1,
+            monitor: Monitor::default(),
+        }
+    }
+}
+
+impl Decoder {
+    /// Returns a new `Decoder` with empty buffers and the default options.
+    pub fn new() -> Decoder {
+        Decoder::default()
+    }
 
-    let data = match color_palette {
-        Some(ref palette) => {
-            read_indexes(
-                bmp_data.get_mut(),
-                &palette,
-                width as usize,
-                height as usize,
+    /// Sets whether the decoder is strict. A strict decoder returns a `BmpError` for the
+    /// inconsistencies that are otherwise tolerated: a `data_size` that doesn't match the
+    /// dimensions, more colors in the palette than the bits per pixel can index, palette indexes
+    /// outside of the palette, and a missing padding at the end of the last row.
+    pub fn with_strict(mut self, strict: bool) -> Decoder {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the `limits` for the dimensions of the images, which are checked before the pixels
+    /// are read. The default `Limits` are checked unless other limits are set, and
+    /// `Limits::none()` opts out of them for trusted images.
+    pub fn with_limits(mut self, limits: Limits) -> Decoder {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets whether images with a color palette keep the palette after they are decoded. The
+    /// palette is returned by `Image::color_palette`.
+    pub fn with_keep_indexed(mut self, keep_indexed: bool) -> Decoder {
+        self.keep_indexed = keep_indexed;
+        self
+    }
+
+    /// Only decodes the `width` by `height` rectangle at `x` and `y`, where (0, 0) is the upper
+    /// left corner of the image. The pixels outside of the region are skipped, and the rows below
+    /// the region are not read.
+    ///
+    /// Decoding returns a `BmpError` if the region is outside of the image.
+    pub fn with_region(mut self, x: u32, y: u32, width: u32, height: u32) -> Decoder {
+        self.region = Some((x, y, width, height));
+        self
+    }
+
+    /// Downscales the images by an integer `factor` while they are decoded, by only keeping
+    /// every `factor`th pixel of every `factor`th row. The dimensions are rounded up.
+    ///
+    /// Panics if `factor` is zero.
+    pub fn with_downscale(mut self, factor: u32) -> Decoder {
+        assert!(factor > 0, "The downscale factor must be at least 1");
+        self.downscale = factor;
+        self
+    }
+
+    /// Calls `progress` with the number of bytes read so far and the total number of bytes of the
+    /// image after every row, to show the progress of decoding large images.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let reports = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&reports);
+    /// let mut decoder = bmp::Decoder::new().with_progress(move |done, total| {
+    ///     sink.lock().unwrap().push((done, total));
+    /// });
+    ///
+    /// let f = std::fs::File::open("test/rgbw.bmp").unwrap();
+    /// decoder.decode(f).unwrap();
+    /// assert_eq!(vec![(62, 70), (70, 70)], *reports.lock().unwrap());
+    /// ```
+    pub fn with_progress<F: FnMut(u64, u64) + Send + 'static>(mut self, progress: F) -> Decoder {
+        self.monitor.set_progress(progress);
+        self
+    }
+
+    /// Checks `token` before every row, and stops decoding with a `BmpError` of the kind
+    /// `Cancelled` once the token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Decoder {
+        self.monitor.set_cancellation(token);
+        self
+    }
+
+    // Checks the dimensions against the limits. The pixels are allocated before they are read, so
+    // the limits keep a header of a few bytes from allocating gigabytes.
+    pub(crate) fn check_limits(&self, width: u32, height: u32) -> BmpResult<()> {
+        self.limits.check_max(width, height)
+    }
+
+    // Checks the dimensions against the limits, and the header for the tolerated inconsistencies
+    // if the decoder is strict
+    fn check_header(&self, dh: &BmpDibHeader) -> BmpResult<()> {
+        let (width, height) = (dh.width.unsigned_abs(), dh.height.unsigned_abs());
+        self.check_limits(width, height)?;
+        if !self.strict {
+            return Ok(());
+        }
+        let data_size = row_stride(dh.bits_per_pixel, width) * height as u64;
+        if dh.data_size != 0 && dh.data_size as u64 != data_size {
+            return Err(BmpError::mismatch(
+                UnsupportedHeader,
+                "data_size",
+                data_size,
+                dh.data_size,
+            ));
+        }
+        if dh.bits_per_pixel <= 8 && dh.num_colors > 1 << dh.bits_per_pixel {
+            return Err(BmpError::mismatch(
+                UnsupportedHeader,
+                "num_colors",
+                format!("at most {}", 1 << dh.bits_per_pixel),
+                dh.num_colors,
+            ));
+        }
+        Ok(())
+    }
+
+    // Returns the part of the image that is decoded
+    fn window(&self, width: u32, height: u32) -> BmpResult<Window> {
+        let (x, y, w, h) = self.region.unwrap_or((0, 0, width, height));
+        if x as u64 + w as u64 > width as u64 || y as u64 + h as u64 > height as u64 {
+            return Err(BmpError::mismatch(
+                OutOfBounds,
+                "region",
+                format!("within the {}x{} image", width, height),
+                format!("{}x{} at ({}, {})", w, h, x, y),
+            ));
+        }
+        Ok(Window {
+            x,
+            y,
+            width: w,
+            height: h,
+            step: self.downscale,
+        })
+    }
+
+    /// Decodes a new `Image` from `source`.
+    pub fn decode<R: Read>(&mut self, source: R) -> BmpResult<Image> {
+        let mut image = Image::new(0, 0);
+        self.decode_into(source, &mut image)?;
+        Ok(image)
+    }
+
+    /// Decodes an image from `source` into `image`, replacing its dimensions and pixels while
+    /// reusing its pixel buffer.
+    ///
+    /// If the decoding fails, `image` is left as an empty 0x0 image.
+    pub fn decode_into<R: Read>(&mut self, source: R, image: &mut Image) -> BmpResult<()> {
+        let result = self.read_image(&mut Reader::new(source), image);
+        if result.is_err() {
+            image.width = 0;
+            image.height = 0;
+            image.padding = 0;
+            image.data.clear();
+            image.color_palette = None;
+            image.source_format = None;
+        }
+        result
+    }
+
+    /// Decodes a new `Image` from `source` like `decode`, but seeks past the rows below the region
+    /// instead of reading them. The rows are stored from the bottom to the top, so decoding a
+    /// region near the top of a large file then only reads the headers and the rows of the
+    /// region.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs::File;
+    ///
+    /// let f = File::open("test/bmptestsuite-0.9/valid/24bpp-320x240.bmp").unwrap();
+    /// let img = bmp::Decoder::new()
+    ///     .with_region(0, 0, 320, 8)
+    ///     .decode_seekable(f)
+    ///     .unwrap();
+    /// assert_eq!((320, 8), (img.get_width(), img.get_height()));
+    /// ```
+    pub fn decode_seekable<R: Read + Seek>(&mut self, source: R) -> BmpResult<Image> {
+        let mut image = Image::new(0, 0);
+        self.read_image(&mut Reader::seekable(source), &mut image)?;
+        Ok(image)
+    }
+
+    /// Decodes an image with a color palette from `source`, keeping the palette index of every
+    /// pixel instead of expanding them to colors. This uses a single byte per pixel, which
+    /// makes it well suited for label masks and other indexed data.
+    ///
+    /// Returns a `BmpError` with the kind `UnsupportedBitsPerPixel` if the image has no palette.
+    /// The region and downscale options of the decoder don't apply to indexed images.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::fs::File;
+    ///
+    /// let mut f = File::open("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+    /// let img = bmp::Decoder::new().decode_indexed(&mut f).unwrap();
+    /// assert_eq!(1, img.indices().len());
+    /// assert_eq!(6, img.palette().colors().len());
+    /// ```
+    pub fn decode_indexed<R: Read>(&mut self, source: R) -> BmpResult<IndexedImage> {
+        let bmp_data = &mut Reader::new(source);
+        read_bmp_id(bmp_data)?;
+        let header = read_bmp_header(bmp_data)?;
+        let dib_header = read_bmp_dib_header(bmp_data)?;
+        self.check_header(&dib_header)?;
+
+        if !read_color_palette(bmp_data, &dib_header, &mut self.palette)? {
+            return Err(BmpError::mismatch(
+                UnsupportedBitsPerPixel,
+                "bits_per_pixel",
+                "1, 4, or 8 for an indexed image",
                 dib_header.bits_per_pixel,
-                header.pixel_offset as usize,
-            )?
+            ));
+        }
+        if self.palette.len() > 256 {
+            return Err(BmpError::mismatch(
+                UnsupportedHeader,
+                "num_colors",
+                "at most 256",
+                self.palette.len(),
+            ));
         }
-        None => read_pixels(bmp_data, width, height, header.pixel_offset, padding as i64)?,
-    };
 
-    let image = Image {
-        header,
-        dib_header: BmpDibHeader::new(width as i32, height as i32),
-        color_palette,
-        width,
-        height,
-        padding,
-        data,
-    };
+        let width = dib_header.width.unsigned_abs() as usize;
+        let height = dib_header.height.unsigned_abs() as usize;
+        let bpp = dib_header.bits_per_pixel;
+        // Number of bytes with pixels in each row, without the padding
+        let bytes_per_row = (bpp as u64 * width as u64).div_ceil(8) as usize;
+
+        bmp_data.skip_to(header.pixel_offset as u64)?;
+        self.row.resize(row_stride(bpp, width as u32) as usize, 0);
+        let mut indices = vec![0; pixel_count(width, height)?];
+        // The rows are stored from bottom to top in the file
+        let total = pixel_data_end(&header, bpp, width as u32, height as u32);
+        for (i, row) in indices.chunks_mut(width.max(1)).rev().enumerate() {
+            let is_last = i + 1 == height;
+            self.monitor.check()?;
+            read_row(bmp_data, &mut self.row, bytes_per_row, is_last && !self.strict)?;
+            self.monitor.report(bmp_data.position, total);
+            for (index, i) in row.iter_mut().zip(bit_index(&self.row, bpp as usize, width)) {
+                if i >= self.palette.len() {
+                    return Err(BmpError::mismatch(
+                        OutOfBounds,
+                        "palette_index",
+                        format!("less than {}", self.palette.len()),
+                        i,
+                    ));
+                }
+                *index = i as u8;
+            }
+        }
+
+        let palette = Palette::new(self.palette.clone());
+        Ok(IndexedImage::from_indices(
+            width as u32,
+            height as u32,
+            palette,
+            indices,
+        ))
+    }
+
+    /// Reads the headers of the image from `source`, and returns a `RowDecoder` that decodes its
+    /// rows one at a time. The strict and limits options apply, the other options don't.
+    pub fn decode_rows<R: Read>(&self, source: R) -> BmpResult<RowDecoder<R>> {
+        let mut bmp_data = Reader::new(source);
+        read_bmp_id(&mut bmp_data)?;
+        let header = read_bmp_header(&mut bmp_data)?;
+        let dib_header = read_bmp_dib_header(&mut bmp_data)?;
+        self.check_header(&dib_header)?;
+
+        let mut palette = Vec::new();
+        let has_palette = read_color_palette(&mut bmp_data, &dib_header, &mut palette)?;
+        bmp_data.skip_to(header.pixel_offset as u64)?;
+
+        let width = dib_header.width.unsigned_abs();
+        let bpp = dib_header.bits_per_pixel;
+        Ok(RowDecoder {
+            bmp_data,
+            palette: if has_palette { Some(palette) } else { None },
+            strict: self.strict,
+            row: vec![0; row_stride(bpp, width) as usize],
+            bytes_per_row: (bpp as u64 * width as u64).div_ceil(8) as usize,
+            pixels: Vec::with_capacity(width as usize),
+            rows_read: 0,
+            dib_header,
+        })
+    }
 
-    Ok(image)
+    fn read_image<R: Read>(
+        &mut self,
+        bmp_data: &mut Reader<R>,
+        image: &mut Image,
+    ) -> BmpResult<()> {
+        read_bmp_id(bmp_data)?;
+        let header = read_bmp_header(bmp_data)?;
+        let dib_header = read_bmp_dib_header(bmp_data)?;
+        let color_space = read_color_space(bmp_data, &dib_header)?;
+
+        self.check_header(&dib_header)?;
+        let has_palette = read_color_palette(bmp_data, &dib_header, &mut self.palette)?;
+
+        let width = dib_header.width.unsigned_abs();
+        let height = dib_header.height.unsigned_abs();
+        let bpp = dib_header.bits_per_pixel;
+        let window = self.window(width, height)?;
+        let (out_width, out_height) = (window.out_width(), window.out_height());
+        // Whole rows can be converted directly into the image, without selecting the columns
+        let whole_rows = window.step == 1 && window.width == width;
+        // Number of bytes with pixels in each row, without the padding
+        let bytes_per_row = (bpp as u64 * width as u64).div_ceil(8) as usize;
+
+        // Grow the pixel buffer once, to exactly the size of the image
+        image.data.clear();
+        image.data.reserve_exact(pixel_count(out_width as usize, out_height as usize)?);
+        bmp_data.skip_to(header.pixel_offset as u64)?;
+        self.row.resize(row_stride(bpp, width) as usize, 0);
+        let total = pixel_data_end(&header, bpp, width, height);
+        // The rows below the region are skipped without converting them
+        let rows_below = height - window.y - window.height;
+        bmp_data.advance(rows_below as u64 * self.row.len() as u64)?;
+        // The rows are stored from bottom to top in the file
+        for (i, y) in (0..height).rev().enumerate().skip(rows_below as usize) {
+            if y < window.y {
+                break;
+            }
+            let is_last = i + 1 == height as usize;
+            self.monitor.check()?;
+            read_row(bmp_data, &mut self.row, bytes_per_row, is_last && !self.strict)?;
+            self.monitor.report(bmp_data.position, total);
+            if !window.contains_row(y) {
+                continue;
+            }
+            let data = if whole_rows {
+                &mut image.data
+            } else {
+                self.pixels.clear();
+                &mut self.pixels
+            };
+            if has_palette {
+                let (palette, strict) = (&self.palette, self.strict);
+                indexes_to_pixels(&self.row, bpp, width as usize, palette, strict, data)?;
+            } else {
+                shuffle::bgr_to_pixels(&self.row[..bytes_per_row], data);
+            }
+            if !whole_rows {
+                let columns = &self.pixels[window.x as usize..(window.x + window.width) as usize];
+                image.data.extend(columns.iter().step_by(window.step as usize));
+            }
+        }
+
+        if has_palette && self.keep_indexed {
+            let palette = image.color_palette.get_or_insert_with(Vec::new);
+            palette.clear();
+            palette.reserve_exact(self.palette.len());
+            palette.extend_from_slice(&self.palette);
+        } else {
+            image.color_palette = None;
+        }
+
+        image.header = header;
+        image.dib_header = BmpDibHeader::new(out_width as i32, out_height as i32);
+        image.dib_header.hres = dib_header.hres;
+        image.dib_header.vres = dib_header.vres;
+        image.source_format = BmpVersion::from_dib_header(&dib_header).map(|version| {
+            SourceFormat {
+                version,
+                bits_per_pixel: dib_header.bits_per_pixel,
+                compression: CompressionType::from_u32(dib_header.compress_type),
+                color_space,
+            }
+        });
+        image.width = out_width;
+        image.height = out_height;
+        image.padding = out_width % 4;
+        Ok(())
+    }
+}
+
+/// Decodes the rows of an image one at a time, in the order they are stored in the file, so
+/// images of any size can be processed in constant memory.
+///
+/// It is created with `Decoder::decode_rows`, or with `RowDecoder::new` for the default options.
+///
+/// # Example
+///
+/// ```
+/// use std::fs::File;
+///
+/// let f = File::open("test/rgbw.bmp").unwrap();
+/// let mut rows = bmp::RowDecoder::new(f).unwrap();
+/// // The rows are stored from the bottom to the top
+/// let (y, row) = rows.next_row().unwrap().unwrap();
+/// assert_eq!(1, y);
+/// assert_eq!(bmp::consts::BLUE, row[0]);
+/// ```
+#[derive(Debug)]
+pub struct RowDecoder<R> {
+    bmp_data: Reader<R>,
+    dib_header: BmpDibHeader,
+    palette: Option<Vec<Pixel>>,
+    strict: bool,
+    row: Vec<u8>,
+    bytes_per_row: usize,
+    pixels: Vec<Pixel>,
+    rows_read: u32,
+}
+
+impl<R: Read> RowDecoder<R> {
+    /// Reads the headers of the image from `source`, and returns a `RowDecoder` for its rows.
+    pub fn new(source: R) -> BmpResult<RowDecoder<R>> {
+        Decoder::new().decode_rows(source)
+    }
+
+    /// Returns the `width` of the image.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.dib_header.width.unsigned_abs()
+    }
+
+    /// Returns the `height` of the image.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.dib_header.height.unsigned_abs()
+    }
+
+    /// Returns the horizontal and vertical resolution of the image in dots per inch.
+    #[inline]
+    pub fn dpi(&self) -> (f32, f32) {
+        (
+            ppm_to_dpi(self.dib_header.hres),
+            ppm_to_dpi(self.dib_header.vres),
+        )
+    }
+
+    pub(crate) fn resolution(&self) -> (i32, i32) {
+        (self.dib_header.hres, self.dib_header.vres)
+    }
+
+    /// Decodes the next row, and returns its `y` coordinate together with its pixels, or `None`
+    /// once every row is read. The rows are returned from the bottom to the top of the image.
+    pub fn next_row(&mut self) -> BmpResult<Option<(u32, &[Pixel])>> {
+        let height = self.get_height();
+        if self.rows_read == height {
+            return Ok(None);
+        }
+        self.rows_read += 1;
+        let is_last = self.rows_read == height;
+        let bytes_per_row = self.bytes_per_row;
+        read_row(&mut self.bmp_data, &mut self.row, bytes_per_row, is_last && !self.strict)?;
+
+        self.pixels.clear();
+        let bpp = self.dib_header.bits_per_pixel;
+        let width = self.get_width() as usize;
+        match self.palette {
+            Some(ref palette) => {
+                indexes_to_pixels(&self.row, bpp, width, palette, self.strict, &mut self.pixels)?
+            }
+            None => shuffle::bgr_to_pixels(&self.row[..bytes_per_row], &mut self.pixels),
+        }
+        Ok(Some((height - self.rows_read, &self.pixels)))
+    }
+}
+
+// Returns the offset of the end of the pixel data, which is the total number of bytes of an image
+// that are read when it is decoded
+fn pixel_data_end(header: &BmpHeader, bpp: u16, width: u32, height: u32) -> u64 {
+    header.pixel_offset as u64 + row_stride(bpp, width) * height as u64
+}
+
+// Returns the number of pixels in an image, or an `InvalidDimensions` error if they can't be
+// addressed on this platform
+fn pixel_count(width: usize, height: usize) -> BmpResult<usize> {
+    width.checked_mul(height).ok_or_else(|| {
+        BmpError::mismatch(
+            InvalidDimensions,
+            "dimensions",
+            format!("at most {} pixels", usize::MAX),
+            format!("{}x{}", width, height),
+        )
+    })
+}
+
+// The part of an image that is decoded: a rectangle of which every `step`th row and column is kept
+struct Window {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    step: u32,
+}
+
+impl Window {
+    fn out_width(&self) -> u32 {
+        self.width.div_ceil(self.step)
+    }
+
+    fn out_height(&self) -> u32 {
+        self.height.div_ceil(self.step)
+    }
+
+    fn contains_row(&self, y: u32) -> bool {
+        y >= self.y && y - self.y < self.height && (y - self.y).is_multiple_of(self.step)
+    }
+}
+
+// A reader that keeps track of how far into the stream it is, so the decoder can skip ahead to
+// the offsets given in the headers without seeking
+#[derive(Debug)]
+struct Reader<R> {
+    inner: R,
+    position: u64,
+    // Seeks `count` bytes ahead, if the source supports seeking
+    seek: Option<fn(&mut R, u64) -> io::Result<()>>,
+}
+
+impl<R> Reader<R> {
+    fn new(inner: R) -> Reader<R> {
+        Reader {
+            inner,
+            position: 0,
+            seek: None,
+        }
+    }
+}
+
+impl<R: Seek> Reader<R> {
+    fn seekable(inner: R) -> Reader<R> {
+        Reader {
+            seek: Some(|inner, count| {
+                inner.seek(SeekFrom::Current(count as i64))?;
+                Ok(())
+            }),
+            ..Reader::new(inner)
+        }
+    }
+}
+
+impl<R: Read> Reader<R> {
+    // Moves `count` bytes ahead, by seeking if the source supports it
+    fn advance(&mut self, count: u64) -> io::Result<()> {
+        match self.seek {
+            Some(seek) if count > 0 => {
+                seek(&mut self.inner, count)?;
+                self.position += count;
+            }
+            _ => {
+                self.skip(count)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Skips `count` bytes, stopping early at the end of the stream. Returns the number of bytes
+    // that were skipped.
+    fn skip(&mut self, count: u64) -> io::Result<u64> {
+        let skipped = io::copy(&mut (&mut self.inner).take(count), &mut io::sink())?;
+        self.position += skipped;
+        Ok(skipped)
+    }
+
+    fn skip_to(&mut self, offset: u64) -> BmpResult<()> {
+        if offset < self.position {
+            return Err(BmpError::mismatch(
+                UnsupportedHeader,
+                "offset",
+                format!("at least {}", self.position),
+                offset,
+            ));
+        }
+        let count = offset - self.position;
+        if count > 0 {
+            debug!("Skipping {} unknown bytes up to offset {}", count, offset);
+        }
+        self.skip(count)?;
+        Ok(())
+    }
 }
 
-fn read_bmp_id(bmp_data: &mut Cursor<Vec<u8>>) -> BmpResult<()> {
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+fn read_bmp_id<R: Read>(bmp_data: &mut Reader<R>) -> BmpResult<()> {
     let mut bm = [0, 0];
-    bmp_data.read(&mut bm)?;
+    bmp_data.read_exact(&mut bm)?;
 
     if bm == b"BM"[..] {
         Ok(())
     } else {
-        Err(BmpError::new(
+        Err(BmpError::mismatch(
             WrongMagicNumbers,
-            format!("Expected [66, 77], but was {:?}", bm),
+            "magic",
+            "[66, 77]",
+            format!("{:?}", bm),
         ))
     }
 }
 
-fn read_bmp_header(bmp_data: &mut Cursor<Vec<u8>>) -> BmpResult<BmpHeader> {
+fn read_bmp_header<R: Read>(bmp_data: &mut Reader<R>) -> BmpResult<BmpHeader> {
     let header = BmpHeader {
         file_size: bmp_data.read_u32::<LittleEndian>()?,
         creator1: bmp_data.read_u16::<LittleEndian>()?,
@@ -146,7 +953,54 @@ fn read_bmp_header(bmp_data: &mut Cursor<Vec<u8>>) -> BmpResult<BmpHeader> {
     Ok(header)
 }
 
-fn read_bmp_dib_header(bmp_data: &mut Cursor<Vec<u8>>) -> BmpResult<BmpDibHeader> {
+/// Reads the headers of the BMP image from `source` into a `BmpMetadata`, without reading the
+/// pixels and without checking if the image can be decoded.
+pub(crate) fn decode_metadata<R: Read>(source: R) -> BmpResult<BmpMetadata> {
+    let bmp_data = &mut Reader::new(source);
+    read_bmp_id(bmp_data)?;
+    let header = read_bmp_header(bmp_data)?;
+    let dib_header = read_raw_dib_header(bmp_data)?;
+    let color_space = read_color_space(bmp_data, &dib_header)?;
+
+    match BmpVersion::from_dib_header(&dib_header) {
+        // The fields of the version 2 header have a different layout
+        Some(BmpVersion::Two) => Err(BmpError::mismatch(
+            UnsupportedBmpVersion,
+            "version",
+            "BMP Version 3, 4, or 5",
+            BmpVersion::Two.as_ref(),
+        )),
+        Some(version) => Ok(BmpMetadata::from_headers(
+            &header,
+            &dib_header,
+            version,
+            color_space,
+        )),
+        None => Err(BmpError::mismatch(
+            UnsupportedHeader,
+            "header_size",
+            "40, 108, or 124",
+            dib_header.header_size,
+        )),
+    }
+}
+
+/// Reads the headers and the color palette of an image that can be decoded, up to the pixels.
+/// Returns the palette if the image has one.
+pub(crate) fn read_headers<R: Read>(
+    source: R,
+) -> BmpResult<(BmpHeader, BmpDibHeader, Option<Vec<Pixel>>)> {
+    let bmp_data = &mut Reader::new(source);
+    read_bmp_id(bmp_data)?;
+    let header = read_bmp_header(bmp_data)?;
+    let dib_header = read_bmp_dib_header(bmp_data)?;
+    let mut palette = Vec::new();
+    let has_palette = read_color_palette(bmp_data, &dib_header, &mut palette)?;
+    Ok((header, dib_header, if has_palette { Some(palette) } else { None }))
+}
+
+// Reads the fields of the DIB header as they are, without checking if the image is supported
+fn read_raw_dib_header<R: Read>(bmp_data: &mut Reader<R>) -> BmpResult<BmpDibHeader> {
     let dib_header = BmpDibHeader {
         header_size: bmp_data.read_u32::<LittleEndian>()?,
         width: bmp_data.read_i32::<LittleEndian>()?,
@@ -161,6 +1015,27 @@ fn read_bmp_dib_header(bmp_data: &mut Cursor<Vec<u8>>) -> BmpResult<BmpDibHeader
         num_imp_colors: bmp_data.read_u32::<LittleEndian>()?,
     };
 
+    Ok(dib_header)
+}
+
+// Reads the color space of version 4 and 5 headers, which follows the fields of the version 3
+// header and the four bit masks
+fn read_color_space<R: Read>(
+    bmp_data: &mut Reader<R>,
+    dh: &BmpDibHeader,
+) -> BmpResult<Option<ColorSpace>> {
+    match dh.header_size {
+        108 | 124 => {
+            bmp_data.skip_to(BMP_HEADER_SIZE + 40 + 16)?;
+            Ok(Some(ColorSpace::read(bmp_data, dh.header_size == 124)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn read_bmp_dib_header<R: Read>(bmp_data: &mut Reader<R>) -> BmpResult<BmpDibHeader> {
+    let dib_header = read_raw_dib_header(bmp_data)?;
+
     match BmpVersion::from_dib_header(&dib_header) {
         // V3 is the only version that is "fully" supported (decompressed images are the exception)
         // We will also attempt to decode v4 and v5, but we ignore all the additional data in the header.
@@ -170,127 +1045,176 @@ fn read_bmp_dib_header(bmp_data: &mut Cursor<Vec<u8>>) -> BmpResult<BmpDibHeader
         Some(BmpVersion::Four) |
         Some(BmpVersion::Five) => (),
         // Otherwise, report the errors
-        Some(other) => return Err(BmpError::new(UnsupportedBmpVersion, other)),
+        Some(other) => {
+            return Err(BmpError::mismatch(
+                UnsupportedBmpVersion,
+                "version",
+                "BMP Version 3, 4, or 5",
+                other.as_ref(),
+            ))
+        }
         None => {
-            return Err(BmpError::new(
+            return Err(BmpError::mismatch(
                 UnsupportedHeader,
-                format!(
-                    "Only simple BMP images of version 3, 4, and 5 are currently supported. \
-                Connot decode the image for the following header: {:?}",
-                    dib_header
-                ),
+                "header_size",
+                "40, 108, or 124",
+                dib_header.header_size,
             ))
         }
     }
 
+    if let Some(version) = BmpVersion::from_dib_header(&dib_header) {
+        debug!(
+            "Decoding a {}x{} image of {} with {} bits per pixel",
+            dib_header.width,
+            dib_header.height,
+            version.as_ref(),
+            dib_header.bits_per_pixel
+        );
+    }
+    if dib_header.height < 0 {
+        warn!("The rows are stored from top to bottom, but are decoded from bottom to top");
+    }
+
     match dib_header.bits_per_pixel {
         // Currently supported
         1 | 4 | 8 | 24 => (),
         other => {
-            return Err(BmpError::new(
+            return Err(BmpError::mismatch(
                 UnsupportedBitsPerPixel,
-                format!(
-                    "Only 1, 4, 8, and 24 bits per pixel are currently supported, was: {}",
-                    other
-                ),
+                "bits_per_pixel",
+                "1, 4, 8, or 24",
+                other,
             ))
         }
     }
 
     match CompressionType::from_u32(dib_header.compress_type) {
         CompressionType::Uncompressed => (),
-        other => return Err(BmpError::new(UnsupportedCompressionType, other)),
+        other => {
+            return Err(BmpError::mismatch(
+                UnsupportedCompressionType,
+                "compress_type",
+                CompressionType::Uncompressed.as_ref(),
+                other.as_ref(),
+            ))
+        }
     }
 
+    // Reject dimensions that overflow the sizes in the headers before anything is allocated
+    checked_data_size(
+        dib_header.bits_per_pixel,
+        dib_header.width.unsigned_abs(),
+        dib_header.height.unsigned_abs(),
+    )?;
+
     Ok(dib_header)
 }
 
-fn read_color_palette(
-    bmp_data: &mut Cursor<Vec<u8>>,
+fn read_color_palette<R: Read>(
+    bmp_data: &mut Reader<R>,
     dh: &BmpDibHeader,
-) -> BmpResult<Option<Vec<Pixel>>> {
+    color_palette: &mut Vec<Pixel>,
+) -> BmpResult<bool> {
     let num_entries = match dh.bits_per_pixel {
+        // The pixels of deeper images are colors, so a color table of `num_colors` is only a hint
+        // for displays with fewer colors. It is skipped along with the gap before the pixels.
+        bpp if bpp > 8 => return Ok(false),
         // We have a color_palette if the num_colors in the dib header is not zero
         _ if dh.num_colors != 0 => dh.num_colors as usize,
         // Or if there are 8 or less bits per pixel
         bpp @ 1 | bpp @ 4 | bpp @ 8 => 1 << bpp,
-        _ => return Ok(None),
+        _ => return Ok(false),
     };
+    debug!("Reading a color palette of {} colors", num_entries);
+    if dh.bits_per_pixel <= 8 && num_entries > 1 << dh.bits_per_pixel {
+        warn!(
+            "The color palette has {} colors, but only {} can be used with {} bits per pixel",
+            num_entries,
+            1 << dh.bits_per_pixel,
+            dh.bits_per_pixel
+        );
+    }
 
-    let num_bytes = match BmpVersion::from_dib_header(&dh) {
+    let num_bytes = match BmpVersion::from_dib_header(dh) {
         // Three bytes for v2. Though, this is currently not supported
-        Some(BmpVersion::Two) => return Err(BmpError::new(UnsupportedBmpVersion, BmpVersion::Two)),
+        Some(BmpVersion::Two) => {
+            return Err(BmpError::mismatch(
+                UnsupportedBmpVersion,
+                "version",
+                "BMP Version 3, 4, or 5",
+                BmpVersion::Two.as_ref(),
+            ))
+        }
         // Each entry in the color_palette is four bytes for v3, v4, and v5
         _ => 4,
     };
 
-    bmp_data.seek(SeekFrom::Start(
-        BMP_HEADER_SIZE + dh.header_size as u64,
-    ))?;
+    bmp_data.skip_to(BMP_HEADER_SIZE + dh.header_size as u64)?;
 
-    let mut px = &mut [0; 4][0..num_bytes as usize];
-    let mut color_palette = Vec::with_capacity(num_entries);
+    let px = &mut [0; 4][0..num_bytes as usize];
+    color_palette.clear();
+    // Only the colors that can be indexed are reserved up front, in case `num_colors` is bogus
+    color_palette.reserve_exact(num_entries.min(256));
     for _ in 0..num_entries {
-        bmp_data.read(&mut px)?;
+        bmp_data.read_exact(px)?;
         color_palette.push(px!(px[2], px[1], px[0]));
     }
 
-    Ok(Some(color_palette))
+    Ok(true)
 }
 
-fn read_indexes(
-    bmp_data: &mut Vec<u8>,
-    palette: &Vec<Pixel>,
-    width: usize,
-    height: usize,
+// Appends the colors of the palette indexes in `row` to `data`. Indexes outside of the palette
+// are decoded as black, unless `strict` is set.
+fn indexes_to_pixels(
+    row: &[u8],
     bpp: u16,
-    offset: usize,
-) -> BmpResult<Vec<Pixel>> {
-    let mut data = Vec::with_capacity(height * width);
-    // Number of bytes to read from each row, varies based on bits_per_pixel
-    let bytes_per_row = (width as f64 / (8.0 / bpp as f64)).ceil() as usize;
-    for y in 0..height {
-        let padding = match bytes_per_row % 4 {
-            0 => 0,
-            other => 4 - other,
-        };
-        let start = offset + (bytes_per_row + padding) * y;
-        let bytes = &bmp_data[start..start + bytes_per_row];
-
-        for i in bit_index(&bytes, bpp as usize, width as usize) {
-            data.push(palette[i]);
+    width: usize,
+    palette: &[Pixel],
+    strict: bool,
+    data: &mut Vec<Pixel>,
+) -> BmpResult<()> {
+    for i in bit_index(row, bpp as usize, width) {
+        match palette.get(i) {
+            Some(&px) => data.push(px),
+            None if !strict => data.push(px!(0, 0, 0)),
+            None => {
+                return Err(BmpError::mismatch(
+                    OutOfBounds,
+                    "palette_index",
+                    format!("less than {}", palette.len()),
+                    i,
+                ))
+            }
         }
     }
-    Ok(data)
+    Ok(())
 }
 
-fn read_pixels(
-    bmp_data: &mut Cursor<Vec<u8>>,
-    width: u32,
-    height: u32,
-    offset: u32,
-    padding: i64,
-) -> BmpResult<Vec<Pixel>> {
-    let mut data = Vec::with_capacity((height * width) as usize);
-    // seek until data
-    bmp_data.seek(SeekFrom::Start(offset as u64))?;
-    // read pixels until padding
-    let mut px = [0; 3];
-    for _ in 0..height {
-        for _ in 0..width {
-            bmp_data.read(&mut px)?;
-            data.push(px!(px[2], px[1], px[0]));
+// Reads a row of `row.len()` bytes including the padding with a single read. The padding of the
+// last row is optional, since some encoders leave it out at the end of the file.
+fn read_row<R: Read>(
+    bmp_data: &mut Reader<R>,
+    row: &mut [u8],
+    bytes_per_row: usize,
+    is_last: bool,
+) -> io::Result<()> {
+    if is_last {
+        bmp_data.read_exact(&mut row[..bytes_per_row])?;
+        let padding = (row.len() - bytes_per_row) as u64;
+        if bmp_data.skip(padding)? < padding {
+            warn!("The padding of the last row is missing");
         }
-        // seek padding
-        bmp_data.seek(SeekFrom::Current(padding))?;
+        Ok(())
+    } else {
+        bmp_data.read_exact(row)
     }
-    Ok(data)
 }
 
 const BITS: usize = 8;
 
 #[derive(Debug)]
-struct BitIndex<'a> {
+pub(crate) struct BitIndex<'a> {
     size: usize,
     nbits: usize,
     bits_left: usize,
@@ -299,13 +1223,13 @@ struct BitIndex<'a> {
     index: usize,
 }
 
-fn bit_index<'a>(bytes: &'a [u8], nbits: usize, size: usize) -> BitIndex {
+pub(crate) fn bit_index<'a>(bytes: &'a [u8], nbits: usize, size: usize) -> BitIndex<'a> {
     let bits_left = BITS - nbits;
     BitIndex {
         size,
         nbits,
         bits_left,
-        mask: (!0 as u8 >> bits_left),
+        mask: (!0u8 >> bits_left),
         bytes,
         index: 0,
     }
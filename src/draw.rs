@@ -0,0 +1,1146 @@
+use {FillRule, Image, Paint, Path, Pixel};
+
+/// A quadratic or cubic Bézier curve, given by its control points.
+///
+/// The points are `(x, y)` positions in the image, and can be between or outside of the pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bezier {
+    /// A curve from the first to the last point, pulled towards the middle point.
+    Quadratic((f32, f32), (f32, f32), (f32, f32)),
+    /// A curve from the first to the last point, pulled towards the two middle points.
+    Cubic((f32, f32), (f32, f32), (f32, f32), (f32, f32)),
+}
+
+/// The shape at the ends of a stroked line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineCap {
+    /// The line ends exactly at the end points.
+    Butt,
+    /// The line is extended by a half circle around the end points.
+    Round,
+    /// The line is extended by half the width beyond the end points.
+    Square,
+}
+
+/// The direction in which the colors of a gradient change.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GradientDirection {
+    /// From the left edge to the right edge.
+    Horizontal,
+    /// From the top edge to the bottom edge.
+    Vertical,
+    /// From the upper left corner to the lower right corner.
+    Diagonal,
+}
+
+// Interpolates linearly between the channels of `from` and `to`, where `t` is between 0 and 1
+pub(crate) fn lerp(from: Pixel, to: Pixel, t: f32) -> Pixel {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Pixel::new(
+        channel(from.r, to.r),
+        channel(from.g, to.g),
+        channel(from.b, to.b),
+    )
+}
+
+/// The pattern of dashes and gaps along a stroked line.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, Image, LineStyle, Stroke};
+///
+/// let mut img = Image::new(100, 80);
+/// let stroke = Stroke::new(1.0).with_style(LineStyle::dashed(4.0, 2.0));
+/// img.draw_line_stroked((0.0, 10.5), (100.0, 10.5), &stroke, consts::RED);
+/// assert_eq!(consts::RED, img.get_pixel(3, 10));
+/// assert_eq!(consts::BLACK, img.get_pixel(4, 10));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LineStyle {
+    /// The alternating lengths of the dashes and gaps in pixels, starting with a dash. A pattern
+    /// with an odd number of lengths is repeated twice, and an empty pattern draws a solid line.
+    /// The dashes of a pattern shorter than half a pixel can't be told apart, so it draws a solid
+    /// line as well.
+    pub dashes: Vec<f32>,
+    /// The distance into the pattern where the line starts.
+    pub offset: f32,
+}
+
+// The shortest pattern in pixels that is split into dashes
+const MIN_PATTERN_LENGTH: f64 = 0.5;
+
+impl LineStyle {
+    /// Creates a `LineStyle` for solid lines.
+    pub fn solid() -> LineStyle {
+        LineStyle::default()
+    }
+
+    /// Creates a `LineStyle` of dashes that are `on` pixels long, separated by gaps of `off`
+    /// pixels.
+    ///
+    /// Panics if `on` or `off` is not positive.
+    pub fn dashed(on: f32, off: f32) -> LineStyle {
+        assert!(on > 0.0, "The dash length must be positive, but was {}", on);
+        assert!(off > 0.0, "The gap must be positive, but was {}", off);
+        LineStyle {
+            dashes: vec![on, off],
+            offset: 0.0,
+        }
+    }
+
+    /// Creates a `LineStyle` of dots that are `spacing` pixels apart.
+    ///
+    /// The dots have no length, so they are drawn by the caps of the line. Use it together with
+    /// `LineCap::Round` or `LineCap::Square`.
+    ///
+    /// Panics if `spacing` is not positive.
+    pub fn dotted(spacing: f32) -> LineStyle {
+        assert!(
+            spacing > 0.0,
+            "The spacing must be positive, but was {}",
+            spacing
+        );
+        LineStyle {
+            dashes: vec![0.0, spacing],
+            offset: 0.0,
+        }
+    }
+
+    /// Returns the `LineStyle` with the pattern starting `offset` pixels into it. Animating the
+    /// offset makes the dashes move along the line.
+    pub fn with_offset(mut self, offset: f32) -> LineStyle {
+        self.offset = offset;
+        self
+    }
+
+    // Splits the line through `points` into the dashes of the pattern. Only the parts of the line
+    // within `bounds` (left, top, right, bottom) are split, as the rest can't cover any pixels.
+    pub(crate) fn split(
+        &self,
+        points: &[(f32, f32)],
+        bounds: (f32, f32, f32, f32),
+    ) -> Vec<Vec<(f32, f32)>> {
+        let mut pattern: Vec<f64> = self.dashes.iter().map(|&l| f64::from(l)).collect();
+        if pattern.len() % 2 == 1 {
+            pattern.extend_from_slice(&pattern.clone());
+        }
+        // A pattern with negative lengths would never advance, and the dashes of a pattern much
+        // shorter than a pixel can't be told apart, so both are drawn as a solid line
+        let total: f64 = pattern.iter().sum();
+        let negative = pattern.iter().any(|&l| l < 0.0);
+        if points.is_empty() || negative || !total.is_finite() || total < MIN_PATTERN_LENGTH {
+            return vec![points.to_vec()];
+        }
+        // The distance along the pattern where each dash or gap ends
+        let ends: Vec<f64> = pattern
+            .iter()
+            .scan(0.0, |end, &l| {
+                *end += l;
+                Some(*end)
+            })
+            .collect();
+        let offset = f64::from(self.offset).rem_euclid(total);
+
+        let mut dashes = Vec::new();
+        let mut dash = vec![];
+        // The current dash or gap, as the repetition of the pattern and the index within it. The
+        // boundaries are computed from it instead of being summed up, so they keep advancing on
+        // long lines.
+        let (mut cycle, mut i) = (0.0, 0);
+        let mut inside = false;
+        let mut start = 0.0;
+        for w in points.windows(2) {
+            let (a, b) = (w[0], w[1]);
+            let (ax, ay) = (f64::from(a.0), f64::from(a.1));
+            let (dx, dy) = (f64::from(b.0) - ax, f64::from(b.1) - ay);
+            let length = (dx * dx + dy * dy).sqrt();
+            if length == 0.0 {
+                continue;
+            }
+            let at = |d: f64| {
+                let t = (d - start) / length;
+                ((ax + dx * t) as f32, (ay + dy * t) as f32)
+            };
+            let (t0, t1) = match clip_segment(a, b, bounds) {
+                Some(range) => range,
+                None => {
+                    if inside && dash.iter().any(|&p| p != dash[0]) {
+                        dashes.push(dash);
+                    }
+                    dash = vec![];
+                    inside = false;
+                    start += length;
+                    continue;
+                }
+            };
+            let (from, to) = (start + t0 * length, start + t1 * length);
+            if !inside || t0 > 0.0 {
+                if inside && dash.iter().any(|&p| p != dash[0]) {
+                    dashes.push(dash);
+                }
+                // Find the position of the start of the visible part in the pattern
+                let position = from + offset;
+                cycle = (position / total).floor();
+                let skip = position - cycle * total;
+                i = (0..pattern.len())
+                    .find(|&j| skip < ends[j] || (skip == ends[j] && pattern[j] == 0.0))
+                    .unwrap_or(0);
+                if skip >= total {
+                    cycle += 1.0;
+                }
+                dash = if i % 2 == 0 { vec![at(from)] } else { vec![] };
+            }
+            // Every dash or gap ending within the visible part of the segment
+            loop {
+                let end = cycle * total + ends[i] - offset;
+                if end > to {
+                    break;
+                }
+                let p = at(end);
+                if i % 2 == 0 {
+                    dash.push(p);
+                    dashes.push(dash);
+                    dash = vec![];
+                } else {
+                    dash = vec![p];
+                }
+                i += 1;
+                if i == pattern.len() {
+                    i = 0;
+                    cycle += 1.0;
+                }
+            }
+            let p = at(to);
+            if i % 2 == 0 && dash.last() != Some(&p) {
+                dash.push(p);
+            }
+            inside = t1 == 1.0;
+            if !inside && dash.iter().any(|&p| p != dash[0]) {
+                dashes.push(dash);
+                dash = vec![];
+            }
+            start += length;
+        }
+        // A dash starting at the end of the line would have no length
+        if inside && dash.iter().any(|&p| p != dash[0]) {
+            dashes.push(dash);
+        }
+        dashes
+    }
+}
+
+// Returns the part of the segment from `a` to `b` within `bounds` (left, top, right, bottom), as
+// the range of its parameter
+fn clip_segment(a: (f32, f32), b: (f32, f32), bounds: (f32, f32, f32, f32)) -> Option<(f64, f64)> {
+    let (ax, ay) = (f64::from(a.0), f64::from(a.1));
+    let (dx, dy) = (f64::from(b.0) - ax, f64::from(b.1) - ay);
+    let (left, top) = (f64::from(bounds.0), f64::from(bounds.1));
+    let (right, bottom) = (f64::from(bounds.2), f64::from(bounds.3));
+    let (mut t0, mut t1) = (0.0, 1.0);
+    for &(p, q) in &[
+        (-dx, ax - left),
+        (dx, right - ax),
+        (-dy, ay - top),
+        (dy, bottom - ay),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            t0 = f64::max(t0, q / p);
+        } else {
+            t1 = f64::min(t1, q / p);
+        }
+    }
+    if t0 <= t1 {
+        Some((t0, t1))
+    } else {
+        None
+    }
+}
+
+/// The parameters used when drawing stroked lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stroke {
+    /// The width of the line in pixels.
+    pub width: f32,
+    /// The shape at the ends of the line.
+    pub cap: LineCap,
+    /// The pattern of dashes along the line.
+    pub style: LineStyle,
+}
+
+impl Stroke {
+    /// Creates a new solid `Stroke` of `width` pixels with butt caps.
+    pub fn new(width: f32) -> Stroke {
+        Stroke {
+            width,
+            cap: LineCap::Butt,
+            style: LineStyle::solid(),
+        }
+    }
+
+    /// Returns the `Stroke` with the caps set to `cap`. With a dashed `LineStyle`, the caps are
+    /// drawn at the ends of every dash.
+    pub fn with_cap(mut self, cap: LineCap) -> Stroke {
+        self.cap = cap;
+        self
+    }
+
+    /// Returns the `Stroke` with the pattern of dashes set to `style`.
+    pub fn with_style(mut self, style: LineStyle) -> Stroke {
+        self.style = style;
+        self
+    }
+
+    // Whether the point `p` is inside the stroked line from `a` to `b`
+    pub(crate) fn covers(&self, p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> bool {
+        let half = self.width / 2.0;
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let (px, py) = (p.0 - a.0, p.1 - a.1);
+        if length == 0.0 {
+            return match self.cap {
+                LineCap::Butt => false,
+                LineCap::Round => px * px + py * py <= half * half,
+                LineCap::Square => px.abs() <= half && py.abs() <= half,
+            };
+        }
+
+        // The position along the line, and the distance from it
+        let along = (px * dx + py * dy) / length;
+        let across = (px * dy - py * dx).abs() / length;
+        match self.cap {
+            LineCap::Butt => along >= 0.0 && along <= length && across <= half,
+            LineCap::Square => along >= -half && along <= length + half && across <= half,
+            LineCap::Round => {
+                let nearest = along.clamp(0.0, length);
+                let (nx, ny) = (px - dx * nearest / length, py - dy * nearest / length);
+                nx * nx + ny * ny <= half * half
+            }
+        }
+    }
+}
+
+// The maximum distance in pixels between a flattened curve and the real curve
+const FLATNESS: f32 = 0.25;
+// Bounds the subdivision for degenerate curves
+const MAX_DEPTH: u32 = 16;
+// Bounds the number of lines of an arc with a huge radius
+const MAX_ARC_STEPS: usize = 1 << 16;
+
+fn midpoint(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+}
+
+// The distance from `p` to the line through `a` and `b`
+fn distance_to_line(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        ((p.0 - a.0).powi(2) + (p.1 - a.1).powi(2)).sqrt()
+    } else {
+        ((p.0 - a.0) * dy - (p.1 - a.1) * dx).abs() / length
+    }
+}
+
+impl Bezier {
+    // Appends the points of the flattened curve to `points`, excluding the first point
+    pub(crate) fn flatten_into(&self, points: &mut Vec<(f32, f32)>) {
+        match *self {
+            Bezier::Quadratic(p0, p1, p2) => flatten_quadratic(p0, p1, p2, 0, points),
+            Bezier::Cubic(p0, p1, p2, p3) => flatten_cubic(p0, p1, p2, p3, 0, points),
+        }
+    }
+}
+
+fn flatten_quadratic(
+    p0: (f32, f32),
+    p1: (f32, f32),
+    p2: (f32, f32),
+    depth: u32,
+    points: &mut Vec<(f32, f32)>,
+) {
+    if depth >= MAX_DEPTH || distance_to_line(p1, p0, p2) <= FLATNESS {
+        points.push(p2);
+        return;
+    }
+    // Split the curve in two halves with de Casteljau's algorithm
+    let (a, b) = (midpoint(p0, p1), midpoint(p1, p2));
+    let m = midpoint(a, b);
+    flatten_quadratic(p0, a, m, depth + 1, points);
+    flatten_quadratic(m, b, p2, depth + 1, points);
+}
+
+fn flatten_cubic(
+    p0: (f32, f32),
+    p1: (f32, f32),
+    p2: (f32, f32),
+    p3: (f32, f32),
+    depth: u32,
+    points: &mut Vec<(f32, f32)>,
+) {
+    let flatness = distance_to_line(p1, p0, p3).max(distance_to_line(p2, p0, p3));
+    if depth >= MAX_DEPTH || flatness <= FLATNESS {
+        points.push(p3);
+        return;
+    }
+    let (a, b, c) = (midpoint(p0, p1), midpoint(p1, p2), midpoint(p2, p3));
+    let (d, e) = (midpoint(a, b), midpoint(b, c));
+    let m = midpoint(d, e);
+    flatten_cubic(p0, a, d, m, depth + 1, points);
+    flatten_cubic(m, e, c, p3, depth + 1, points);
+}
+
+// The angle in degrees covered by an arc going clockwise from `start_angle` to `end_angle`
+fn sweep(start_angle: f32, end_angle: f32) -> f32 {
+    let sweep = end_angle - start_angle;
+    if sweep.abs() >= 360.0 {
+        360.0
+    } else {
+        sweep.rem_euclid(360.0)
+    }
+}
+
+// The points of an arc flattened to lines, including both end points
+fn arc_points(center: (f32, f32), radius: f32, start_angle: f32, sweep: f32) -> Vec<(f32, f32)> {
+    // The angle between the points, where the lines are never more than FLATNESS from the arc.
+    // It is computed in f64 since it vanishes in f32 for large radii, and the number of points is
+    // bounded for the radii where it vanishes even then.
+    let radius = f64::from(radius);
+    let flatness = f64::from(FLATNESS);
+    let step = if radius > flatness {
+        2.0 * (1.0 - flatness / radius).acos().to_degrees()
+    } else {
+        f64::from(sweep)
+    };
+    let steps = ((f64::from(sweep) / step).ceil() as usize).clamp(1, MAX_ARC_STEPS);
+    (0..=steps)
+        .map(|i| {
+            let angle = f64::from(start_angle) + f64::from(sweep) * i as f64 / steps as f64;
+            let angle = angle.to_radians();
+            (
+                (f64::from(center.0) + radius * angle.cos()) as f32,
+                (f64::from(center.1) + radius * angle.sin()) as f32,
+            )
+        })
+        .collect()
+}
+
+// The second coordinate of the point where the line from `a` to `b` crosses the first coordinate
+// `at`, rounded to the closest integer. The product can only overflow for lines with a slope
+// that has no small fraction and points far beyond the image, where a float is close enough.
+fn crossing(a: (i64, i64), b: (i64, i64), at: i64) -> i64 {
+    let (d0, d1) = (b.0 as i128 - a.0 as i128, b.1 as i128 - a.1 as i128);
+    let (d0, d1) = if d0 < 0 { (-d0, -d1) } else { (d0, d1) };
+    let (mut gcd, mut rest) = (d0, d1.abs());
+    while rest != 0 {
+        let r = gcd % rest;
+        gcd = rest;
+        rest = r;
+    }
+    let (d0, d1) = (d0 / gcd, d1 / gcd);
+    match d1.checked_mul(2 * (at as i128 - a.0 as i128)) {
+        Some(n) => (a.1 as i128 + (n + d0).div_euclid(2 * d0)) as i64,
+        None => {
+            let t = (at as f64 - a.0 as f64) / (b.0 as f64 - a.0 as f64);
+            (a.1 as f64 + (b.1 as f64 - a.1 as f64) * t).round() as i64
+        }
+    }
+}
+
+impl Image {
+    #[inline]
+    fn plot(&mut self, x: i64, y: i64, paint: Paint) {
+        if x >= 0 && y >= 0 && x < self.width as i64 && y < self.height as i64 {
+            self.paint_pixel(x as u32, y as u32, paint);
+        }
+    }
+
+    /// Draws a one pixel wide line from (`x0`, `y0`) to (`x1`, `y1`) in the color `val`.
+    /// The parts of the line that fall outside of the image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.draw_line(-10, -10, 10, 10, bmp::consts::RED);
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(5, 5));
+    /// ```
+    pub fn draw_line<P: Into<Paint>>(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, val: P) {
+        self.draw_line_segment(x0, y0, x1, y1, val.into(), true);
+    }
+
+    // Bresenham's line algorithm, optionally skipping the last point so connected segments only
+    // draw their shared points once
+    fn draw_line_segment(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, val: Paint, last: bool) {
+        let ((x0, y0), end) = match self.clip_line((x0, y0), (x1, y1)) {
+            Some(clipped) => clipped,
+            None => return,
+        };
+        // The end of a clipped line is not shared with the next segment
+        let last = last || end != (x1, y1);
+        let (x1, y1) = end;
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+        loop {
+            let is_last = x == x1 && y == y1;
+            if !is_last || last {
+                self.plot(x, y, val);
+            }
+            if is_last {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    // Clips the line from `a` to `b` to the image with the Cohen-Sutherland algorithm, so that
+    // Bresenham's algorithm never steps through the pixels outside of the image. Returns the
+    // clipped end points, or `None` if the line misses the image.
+    fn clip_line(&self, mut a: (i64, i64), mut b: (i64, i64)) -> Option<((i64, i64), (i64, i64))> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let (right, bottom) = (self.width as i64 - 1, self.height as i64 - 1);
+        const LEFT: u8 = 1;
+        const RIGHT: u8 = 2;
+        const TOP: u8 = 4;
+        const BOTTOM: u8 = 8;
+        let outcode = |(x, y): (i64, i64)| {
+            let horizontal = if x < 0 {
+                LEFT
+            } else if x > right {
+                RIGHT
+            } else {
+                0
+            };
+            let vertical = if y < 0 {
+                TOP
+            } else if y > bottom {
+                BOTTOM
+            } else {
+                0
+            };
+            horizontal | vertical
+        };
+        // Every point is moved at most twice, once for each axis
+        for _ in 0..4 {
+            let (code_a, code_b) = (outcode(a), outcode(b));
+            if code_a | code_b == 0 {
+                return Some((a, b));
+            }
+            if code_a & code_b != 0 {
+                return None;
+            }
+            let code = if code_a != 0 { code_a } else { code_b };
+            let p = if code & (LEFT | RIGHT) != 0 {
+                let x = if code & LEFT != 0 { 0 } else { right };
+                (x, crossing((a.0, a.1), (b.0, b.1), x))
+            } else {
+                let y = if code & TOP != 0 { 0 } else { bottom };
+                (crossing((a.1, a.0), (b.1, b.0), y), y)
+            };
+            if code_a != 0 {
+                a = p;
+            } else {
+                b = p;
+            }
+        }
+        // Rounding can leave a point just outside of a corner
+        let clamp = |(x, y): (i64, i64)| (x.max(0).min(right), y.max(0).min(bottom));
+        Some((clamp(a), clamp(b)))
+    }
+
+    // Draws one pixel wide lines between consecutive points
+    fn draw_polyline(&mut self, points: &[(f32, f32)], val: Paint) {
+        let rounded: Vec<_> = points
+            .iter()
+            .map(|p| (p.0.round() as i64, p.1.round() as i64))
+            .collect();
+        for (i, w) in rounded.windows(2).enumerate() {
+            let last = i + 2 == rounded.len();
+            self.draw_line_segment(w[0].0, w[0].1, w[1].0, w[1].1, val, last);
+        }
+    }
+
+    /// Draws a one pixel wide Bézier `curve` in the color `val`. The curve is approximated by
+    /// straight lines that are never more than a quarter of a pixel away from it.
+    /// The parts of the curve that fall outside of the image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Bezier, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.draw_bezier(Bezier::Quadratic((0.0, 79.0), (50.0, -79.0), (99.0, 79.0)), consts::RED);
+    /// assert_eq!(consts::RED, img.get_pixel(50, 0));
+    /// ```
+    pub fn draw_bezier<P: Into<Paint>>(&mut self, curve: Bezier, val: P) {
+        let first = match curve {
+            Bezier::Quadratic(p0, ..) | Bezier::Cubic(p0, ..) => p0,
+        };
+        let mut points = vec![first];
+        curve.flatten_into(&mut points);
+        self.draw_polyline(&points, val.into());
+    }
+
+    /// Draws a line from `from` to `to` with the width and caps given by `stroke`, in the color
+    /// `val`. A pixel is drawn if its center is inside the line.
+    /// The parts of the line that fall outside of the image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, LineCap, Stroke};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// let stroke = Stroke::new(5.0).with_cap(LineCap::Round);
+    /// img.draw_line_stroked((10.0, 10.0), (90.0, 10.0), &stroke, consts::RED);
+    /// assert_eq!(consts::RED, img.get_pixel(50, 12));
+    /// assert_eq!(consts::BLACK, img.get_pixel(50, 13));
+    /// ```
+    pub fn draw_line_stroked<P: Into<Paint>>(
+        &mut self,
+        from: (f32, f32),
+        to: (f32, f32),
+        stroke: &Stroke,
+        val: P,
+    ) {
+        self.stroke_path(&Path::new().move_to(from).line_to(to), stroke, val);
+    }
+
+    /// Fills the rectangle with the upper left corner at `x` and `y`, and the size of `width` and
+    /// `height`, with a linear gradient going from the color `from` to the color `to` in the
+    /// given `direction`. The first and last pixels of the rectangle get the exact colors, and
+    /// the gradient is drawn with the blend mode of `from`. The parts of the rectangle that fall
+    /// outside of the image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, GradientDirection, Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// let direction = GradientDirection::Vertical;
+    /// img.fill_rect_gradient(0, 0, 100, 3, consts::BLACK, consts::WHITE, direction);
+    /// assert_eq!(consts::BLACK, img.get_pixel(50, 0));
+    /// assert_eq!(Pixel::new(128, 128, 128), img.get_pixel(50, 1));
+    /// assert_eq!(consts::WHITE, img.get_pixel(50, 2));
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_rect_gradient<P: Into<Paint>>(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        from: P,
+        to: P,
+        direction: GradientDirection,
+    ) {
+        let (from, to) = (from.into(), to.into());
+        // The number of steps from the first to the last pixel of the gradient, which can exceed
+        // a u32 for the diagonal of a huge rectangle
+        let (width, height) = (width as u64, height as u64);
+        let steps = match direction {
+            GradientDirection::Horizontal => width.saturating_sub(1),
+            GradientDirection::Vertical => height.saturating_sub(1),
+            GradientDirection::Diagonal => (width + height).saturating_sub(2),
+        }
+        .max(1) as f64;
+
+        // Only the part of the rectangle within the image is filled
+        let x_end = (x as u64 + width).min(self.width as u64) as u32;
+        let y_end = (y as u64 + height).min(self.height as u64) as u32;
+        for py in y.min(y_end)..y_end {
+            for px in x.min(x_end)..x_end {
+                let (dx, dy) = ((px - x) as u64, (py - y) as u64);
+                let step = match direction {
+                    GradientDirection::Horizontal => dx,
+                    GradientDirection::Vertical => dy,
+                    GradientDirection::Diagonal => dx + dy,
+                };
+                let color = lerp(from.color, to.color, (step as f64 / steps) as f32);
+                self.paint_pixel(px, py, Paint::new(color, from.mode));
+            }
+        }
+    }
+
+    /// Draws the outline of the rectangle with the upper left corner at `x` and `y`, and the size
+    /// of `width` and `height`, in the color `val`. The outline is one pixel wide and drawn
+    /// inside the rectangle. The parts of the rectangle that fall outside of the image are
+    /// ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.draw_rect(10, 10, 20, 20, bmp::consts::RED);
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(29, 29));
+    /// assert_eq!(bmp::consts::BLACK, img.get_pixel(11, 11));
+    /// ```
+    pub fn draw_rect<P: Into<Paint>>(&mut self, x: u32, y: u32, width: u32, height: u32, val: P) {
+        self.draw_rect_with_width(x, y, width, height, 1, val);
+    }
+
+    /// Draws the outline of the rectangle like `draw_rect`, with an outline that is
+    /// `stroke_width` pixels wide. The outline is drawn inside the rectangle.
+    pub fn draw_rect_with_width<P: Into<Paint>>(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        stroke_width: u32,
+        val: P,
+    ) {
+        let val = val.into();
+        // The edges are drawn without overlapping, so every pixel is only blended once
+        let top = stroke_width.min(height);
+        let bottom = stroke_width.min(height - top);
+        let left = stroke_width.min(width);
+        let right = stroke_width.min(width - left);
+        let inner_y = y.saturating_add(top);
+        let inner_height = height - top - bottom;
+
+        // The top and bottom edges span the full width
+        self.fill_rect(x, y, width, top, val);
+        self.fill_rect(x, y.saturating_add(height - bottom), width, bottom, val);
+        // The left and right edges fit between them
+        self.fill_rect(x, inner_y, left, inner_height, val);
+        self.fill_rect(
+            x.saturating_add(width - right),
+            inner_y,
+            right,
+            inner_height,
+            val,
+        );
+    }
+
+    /// Draws the outline of the rectangle with the upper left corner at `x` and `y`, and the size
+    /// of `width` and `height`, with the width, caps and dashes given by `stroke`, in the color
+    /// `val`. Like in `draw_rect`, the outline is drawn inside the rectangle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, LineStyle, Stroke};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// let marquee = Stroke::new(1.0).with_style(LineStyle::dashed(3.0, 3.0));
+    /// img.stroke_rect(10, 10, 20, 20, &marquee, consts::WHITE);
+    /// assert_eq!(consts::WHITE, img.get_pixel(12, 10));
+    /// assert_eq!(consts::BLACK, img.get_pixel(14, 10));
+    /// ```
+    pub fn stroke_rect<P: Into<Paint>>(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        stroke: &Stroke,
+        val: P,
+    ) {
+        // Move the center of the outline inside, so its outer edge follows the rectangle
+        let inset = (stroke.width / 2.0)
+            .min(width as f32 / 2.0)
+            .min(height as f32 / 2.0);
+        let (x0, y0) = (x as f32 + inset, y as f32 + inset);
+        let (x1, y1) = (
+            x as f32 + width as f32 - inset,
+            y as f32 + height as f32 - inset,
+        );
+        let path = Path::new()
+            .move_to((x0, y0))
+            .line_to((x1, y0))
+            .line_to((x1, y1))
+            .line_to((x0, y1))
+            .close();
+        self.stroke_path(&path, stroke, val);
+    }
+
+    /// Draws a one pixel wide arc of the circle around `center` with `radius`, in the color `val`.
+    ///
+    /// The angles are in degrees, measured clockwise from the positive x-axis, and the arc goes
+    /// clockwise from `start_angle` to `end_angle`. The angles wrap around, so an arc from 350 to
+    /// 10 degrees covers 20 degrees. If the angles are 360 degrees or more apart, the full circle
+    /// is drawn. The parts of the arc that fall outside of the image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.draw_arc((50.0, 40.0), 30.0, 180.0, 360.0, bmp::consts::RED);
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(50, 10));
+    /// assert_eq!(bmp::consts::BLACK, img.get_pixel(50, 70));
+    /// ```
+    pub fn draw_arc<P: Into<Paint>>(
+        &mut self,
+        center: (f32, f32),
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        val: P,
+    ) {
+        let sweep = sweep(start_angle, end_angle);
+        let points = arc_points(center, radius, start_angle, sweep);
+        self.draw_polyline(&points, val.into());
+    }
+
+    /// Fills the slice of the circle around `center` with `radius` between `start_angle` and
+    /// `end_angle`, in the color `val`. The angles are handled like in `draw_arc`.
+    /// A pixel is drawn if its center is inside the slice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// // A slice around the positive x-axis
+    /// img.fill_pie((50.0, 40.0), 30.0, 315.0, 45.0, bmp::consts::RED);
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(70, 40));
+    /// assert_eq!(bmp::consts::BLACK, img.get_pixel(30, 40));
+    /// ```
+    pub fn fill_pie<P: Into<Paint>>(
+        &mut self,
+        center: (f32, f32),
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        val: P,
+    ) {
+        let sweep = sweep(start_angle, end_angle);
+        if sweep == 0.0 {
+            return;
+        }
+        let path = arc_points(center, radius, start_angle, sweep)
+            .into_iter()
+            .fold(Path::new().move_to(center), |path, p| path.line_to(p))
+            .close();
+        self.fill_path(&path, FillRule::NonZero, val);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sweep, Bezier, GradientDirection, LineCap, LineStyle, Stroke};
+    use {consts, BlendMode, Image, Paint, Pixel};
+
+    fn painted(img: &Image) -> Vec<(u32, u32)> {
+        img.coordinates()
+            .filter(|&(x, y)| img.get_pixel(x, y) != consts::BLACK)
+            .collect()
+    }
+
+    #[test]
+    fn draw_line_draws_every_pixel_once() {
+        let mut img = Image::new(5, 5);
+        img.draw_line(0, 4, 4, 2, consts::WHITE);
+        assert_eq!(painted(&img), vec![(3, 2), (4, 2), (1, 3), (2, 3), (0, 4)]);
+
+        let mut img = Image::new(5, 5);
+        img.draw_line(2, -3, 2, 9, consts::WHITE);
+        assert_eq!(painted(&img), vec![(2, 0), (2, 1), (2, 2), (2, 3), (2, 4)]);
+
+        let mut img = Image::new(5, 5);
+        img.draw_line(1, 1, 1, 1, consts::WHITE);
+        assert_eq!(painted(&img), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn draw_line_clips_lines_far_outside_of_the_image() {
+        let far = i64::MAX / 2;
+        let mut img = Image::new(5, 5);
+        img.draw_line(-far, -far, far, far, consts::WHITE);
+        assert_eq!(painted(&img), vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+
+        let mut img = Image::new(5, 5);
+        img.draw_line(i64::MIN, 2, i64::MAX, 2, consts::WHITE);
+        assert_eq!(painted(&img).len(), 5);
+
+        let mut img = Image::new(5, 5);
+        img.draw_line(i64::MAX, i64::MAX, i64::MIN, i64::MIN, consts::WHITE);
+        assert_eq!(painted(&img), vec![(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+
+        let mut img = Image::new(5, 5);
+        img.draw_line(-far, far, far, far, consts::WHITE);
+        assert!(painted(&img).is_empty());
+    }
+
+    #[test]
+    fn bezier_curves_pass_through_the_end_points() {
+        let mut img = Image::new(20, 20);
+        img.draw_bezier(
+            Bezier::Cubic((0.0, 0.0), (19.0, 0.0), (0.0, 19.0), (19.0, 19.0)),
+            consts::WHITE,
+        );
+        assert_eq!(img.get_pixel(0, 0), consts::WHITE);
+        assert_eq!(img.get_pixel(19, 19), consts::WHITE);
+        // The curve is symmetric around the center
+        assert_eq!(img.get_pixel(10, 10), consts::WHITE);
+        assert_eq!(img.get_pixel(0, 19), consts::BLACK);
+
+        // A straight curve is a straight line
+        let mut line = Image::new(20, 20);
+        line.draw_line(1, 2, 15, 9, consts::WHITE);
+        let mut curve = Image::new(20, 20);
+        curve.draw_bezier(
+            Bezier::Quadratic((1.0, 2.0), (8.0, 5.5), (15.0, 9.0)),
+            consts::WHITE,
+        );
+        assert_eq!(painted(&curve), painted(&line));
+    }
+
+    #[test]
+    fn stroked_lines_have_the_given_width_and_caps() {
+        let draw = |cap| {
+            let mut img = Image::new(12, 7);
+            let stroke = Stroke::new(3.0).with_cap(cap);
+            img.draw_line_stroked((3.0, 3.5), (9.0, 3.5), &stroke, consts::WHITE);
+            painted(&img)
+        };
+
+        let butt = draw(LineCap::Butt);
+        assert_eq!(butt.len(), 6 * 3);
+        assert!(butt
+            .iter()
+            .all(|&(x, y)| (3..9).contains(&x) && (2..5).contains(&y)));
+
+        let square = draw(LineCap::Square);
+        assert_eq!(square.len(), 10 * 3);
+        assert!(square.contains(&(1, 2)) && square.contains(&(10, 4)));
+
+        let round = draw(LineCap::Round);
+        assert!(round.contains(&(1, 3)) && !round.contains(&(1, 2)));
+        assert!(round.len() > butt.len() && round.len() < square.len());
+
+        let mut img = Image::new(5, 5);
+        let stroke = Stroke::new(2.0).with_cap(LineCap::Round);
+        img.draw_line_stroked((2.5, 2.5), (2.5, 2.5), &stroke, consts::WHITE);
+        assert_eq!(painted(&img), vec![(2, 1), (1, 2), (2, 2), (3, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn draw_rect_draws_the_outline_inside_the_rectangle() {
+        let mut img = Image::new(5, 5);
+        img.draw_rect(1, 1, 3, 3, consts::WHITE);
+        assert_eq!(
+            painted(&img),
+            vec![(1, 1), (2, 1), (3, 1), (1, 2), (3, 2), (1, 3), (2, 3), (3, 3)]
+        );
+
+        let mut img = Image::new(5, 5);
+        img.draw_rect_with_width(0, 0, 5, 5, 2, consts::WHITE);
+        assert_eq!(painted(&img).len(), 24);
+        assert_eq!(img.get_pixel(2, 2), consts::BLACK);
+
+        let mut img = Image::new(5, 5);
+        img.draw_rect_with_width(3, 3, 4, 4, 1, consts::WHITE);
+        assert_eq!(painted(&img), vec![(3, 3), (4, 3), (3, 4)]);
+    }
+
+    #[test]
+    fn blended_drawing_paints_every_pixel_once() {
+        let xor = Paint::new(consts::WHITE, BlendMode::Xor);
+
+        // The edges of a thick outline overlap when the rectangle is small
+        let mut img = Image::new(5, 5);
+        img.draw_rect_with_width(0, 0, 5, 3, 2, xor);
+        assert_eq!(painted(&img).len(), 15);
+
+        let mut img = Image::new(5, 5);
+        img.draw_line_stroked((0.5, 2.5), (4.5, 2.5), &Stroke::new(3.0), xor);
+        img.draw_line_stroked((0.5, 2.5), (4.5, 2.5), &Stroke::new(3.0), xor);
+        assert!(painted(&img).is_empty());
+    }
+
+    #[test]
+    fn arcs_wrap_around() {
+        assert_eq!(sweep(350.0, 10.0), 20.0);
+        assert_eq!(sweep(10.0, 350.0), 340.0);
+        assert_eq!(sweep(-90.0, 90.0), 180.0);
+        assert_eq!(sweep(0.0, 720.0), 360.0);
+        assert_eq!(sweep(45.0, 45.0), 0.0);
+
+        let mut img = Image::new(21, 21);
+        img.draw_arc((10.0, 10.0), 8.0, 0.0, 360.0, consts::WHITE);
+        for (x, y) in painted(&img) {
+            let d = ((x as f32 - 10.0).powi(2) + (y as f32 - 10.0).powi(2)).sqrt();
+            assert!((d - 8.0).abs() < 1.0);
+        }
+        assert_eq!(img.get_pixel(18, 10), consts::WHITE);
+        assert_eq!(img.get_pixel(10, 2), consts::WHITE);
+
+        let mut img = Image::new(21, 21);
+        img.fill_pie((10.5, 10.5), 8.0, 300.0, 60.0, consts::WHITE);
+        assert_eq!(img.get_pixel(17, 10), consts::WHITE);
+        assert_eq!(img.get_pixel(3, 10), consts::BLACK);
+        assert_eq!(img.get_pixel(10, 3), consts::BLACK);
+    }
+
+    #[test]
+    fn arcs_with_huge_radii_are_drawn() {
+        // The top of the circle runs along the row 8 of the image
+        let center = (5.0, 100_000_008.0);
+        let mut img = Image::new(10, 10);
+        img.draw_arc(center, 1e8, 0.0, 360.0, consts::WHITE);
+        let painted = painted(&img);
+        assert_eq!(painted.len(), 10);
+        assert!(painted.iter().all(|&(_, y)| y == 8));
+
+        let mut img = Image::new(10, 10);
+        img.fill_pie(center, 1e8, 0.0, 360.0, consts::WHITE);
+        assert_eq!(img.get_pixel(5, 9), consts::WHITE);
+        assert_eq!(img.get_pixel(5, 6), consts::BLACK);
+    }
+
+    #[test]
+    fn line_styles_split_lines_into_dashes() {
+        let points = [(0.0, 0.0), (10.0, 0.0), (10.0, 4.0)];
+        let bounds = (-10.0, -10.0, 20.0, 20.0);
+        assert_eq!(
+            LineStyle::solid().split(&points, bounds),
+            vec![points.to_vec()]
+        );
+        assert_eq!(
+            LineStyle::dashed(6.0, 2.0).split(&points, bounds),
+            vec![
+                vec![(0.0, 0.0), (6.0, 0.0)],
+                vec![(8.0, 0.0), (10.0, 0.0), (10.0, 4.0)]
+            ]
+        );
+        assert_eq!(
+            LineStyle::dashed(3.0, 3.0)
+                .with_offset(4.0)
+                .split(&points, bounds),
+            vec![
+                vec![(2.0, 0.0), (5.0, 0.0)],
+                vec![(8.0, 0.0), (10.0, 0.0), (10.0, 1.0)]
+            ]
+        );
+        assert_eq!(LineStyle::dotted(5.0).split(&points, bounds).len(), 3);
+
+        let mut img = Image::new(10, 3);
+        let stroke = Stroke::new(1.0)
+            .with_cap(LineCap::Square)
+            .with_style(LineStyle::dotted(3.0));
+        img.draw_line_stroked((0.5, 1.5), (9.5, 1.5), &stroke, consts::WHITE);
+        assert_eq!(painted(&img), vec![(0, 1), (3, 1), (6, 1), (9, 1)]);
+    }
+
+    #[test]
+    fn line_styles_with_negative_lengths_are_solid() {
+        let points = [(0.0, 0.0), (10.0, 0.0)];
+        let bounds = (-10.0, -10.0, 20.0, 20.0);
+        let style = LineStyle {
+            dashes: vec![4.0, -2.0],
+            offset: 0.0,
+        };
+        assert_eq!(style.split(&points, bounds), vec![points.to_vec()]);
+    }
+
+    #[test]
+    fn line_styles_only_split_the_lines_within_the_bounds() {
+        let points = [(-20.0, 0.0), (10.0, 0.0), (10.0, 20.0)];
+        assert_eq!(
+            LineStyle::dashed(3.0, 3.0).split(&points, (-2.0, -2.0, 12.0, 5.0)),
+            vec![
+                vec![(-2.0, 0.0), (1.0, 0.0)],
+                vec![(4.0, 0.0), (7.0, 0.0)],
+                vec![(10.0, 0.0), (10.0, 3.0)]
+            ]
+        );
+    }
+
+    #[test]
+    fn long_dashed_lines_are_drawn() {
+        let dashed = Stroke::new(1.0).with_style(LineStyle::dashed(4.0, 4.0));
+        let mut img = Image::new(100, 10);
+        img.draw_line_stroked((-1e8, 5.5), (1e8, 5.5), &dashed, consts::WHITE);
+        assert_eq!(painted(&img).len(), 52);
+
+        let dashed = Stroke::new(1.0).with_style(LineStyle::dashed(1.0, 1.0));
+        let mut img = Image::new(10, 10);
+        img.stroke_rect(0, 0, u32::MAX, u32::MAX, &dashed, consts::WHITE);
+        assert_eq!(img.get_pixel(0, 0), consts::WHITE);
+        assert_eq!(img.get_pixel(5, 5), consts::BLACK);
+    }
+
+    #[test]
+    fn tiny_dashes_are_drawn_solid() {
+        let tiny = Stroke::new(1.0).with_style(LineStyle::dashed(1e-6, 1e-6));
+        let mut img = Image::new(10, 3);
+        img.draw_line_stroked((0.0, 1.5), (10.0, 1.5), &tiny, consts::WHITE);
+        assert_eq!(painted(&img).len(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "The gap must be positive, but was -2")]
+    fn dashed_rejects_negative_lengths() {
+        LineStyle::dashed(4.0, -2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "The spacing must be positive, but was 0")]
+    fn dotted_rejects_zero_spacing() {
+        LineStyle::dotted(0.0);
+    }
+
+    #[test]
+    fn gradients_interpolate_between_the_colors() {
+        let (from, to) = (Pixel::new(0, 100, 200), Pixel::new(200, 100, 0));
+
+        let mut img = Image::new(5, 5);
+        img.fill_rect_gradient(0, 0, 5, 5, from, to, GradientDirection::Horizontal);
+        for y in 0..5 {
+            assert_eq!(img.get_pixel(0, y), from);
+            assert_eq!(img.get_pixel(1, y), Pixel::new(50, 100, 150));
+            assert_eq!(img.get_pixel(4, y), to);
+        }
+
+        let mut img = Image::new(5, 5);
+        img.fill_rect_gradient(1, 1, 3, 3, from, to, GradientDirection::Diagonal);
+        assert_eq!(img.get_pixel(1, 1), from);
+        assert_eq!(img.get_pixel(3, 1), Pixel::new(100, 100, 100));
+        assert_eq!(img.get_pixel(1, 3), Pixel::new(100, 100, 100));
+        assert_eq!(img.get_pixel(3, 3), to);
+        assert_eq!(img.get_pixel(0, 0), consts::BLACK);
+
+        // Clipped gradients keep the colors of the full rectangle
+        let mut img = Image::new(5, 5);
+        img.fill_rect_gradient(0, 2, 5, 5, from, to, GradientDirection::Vertical);
+        assert_eq!(img.get_pixel(0, 4), Pixel::new(100, 100, 100));
+
+        let mut img = Image::new(5, 5);
+        let (max, diagonal) = (u32::MAX, GradientDirection::Diagonal);
+        img.fill_rect_gradient(0, 0, max, max, from, to, diagonal);
+        assert_eq!(img.get_pixel(0, 0), from);
+        assert_eq!(img.get_pixel(4, 4), from);
+    }
+
+    #[test]
+    fn gradients_are_blended() {
+        let mut img = Image::new(3, 1);
+        img.fill(Pixel::new(10, 10, 10));
+        let (from, to) = (
+            Paint::new(consts::BLACK, BlendMode::Add),
+            Paint::new(Pixel::new(100, 100, 100), BlendMode::Add),
+        );
+        img.fill_rect_gradient(0, 0, 3, 1, from, to, GradientDirection::Horizontal);
+        assert_eq!(img.get_pixel(0, 0), Pixel::new(10, 10, 10));
+        assert_eq!(img.get_pixel(1, 0), Pixel::new(60, 60, 60));
+        assert_eq!(img.get_pixel(2, 0), Pixel::new(110, 110, 110));
+    }
+}
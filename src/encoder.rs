@@ -2,26 +2,74 @@ extern crate byteorder;
 use byteorder::{LittleEndian, WriteBytesExt};
 
 use std::io::{self, Write};
+use std::mem;
 
-use Image;
+use {checked_data_size, encoded_size, row_stride, shuffle, BmpDibHeader, BmpHeader, Image};
+use palette::IndexedImage;
+use progress::Monitor;
+use {BmpError, BmpErrorKind, BmpResult, CancellationToken, Pixel, HEADER_SIZE};
 
 const B: u8 = 66;
 const M: u8 = 77;
 
 pub fn encode_image(bmp_image: &Image) -> io::Result<Vec<u8>> {
-    let mut bmp_data = Vec::with_capacity(bmp_image.header.file_size as usize);
+    // Images that don't fit in the 32-bit sizes of the headers are rejected up front
+    let data_size = checked_data_size(24, bmp_image.width, bmp_image.height)?;
+    let size = encoded_size(24, bmp_image.width, bmp_image.height);
+    let mut bmp_data = Vec::with_capacity(size as usize);
 
-    write_header(&mut bmp_data, bmp_image)?;
+    write_header(
+        &mut bmp_data,
+        &bmp_image.header,
+        &bmp_image.dib_header,
+        24,
+        data_size,
+    )?;
     write_data(&mut bmp_data, bmp_image)?;
     Ok(bmp_data)
 }
 
-fn write_header(bmp_data: &mut Vec<u8>, img: &Image) -> io::Result<()> {
-    let header = &img.header;
-    let dib_header = &img.dib_header;
-    let (header_size, data_size) = file_size!(24, img.width, img.height);
+pub fn encode_indexed_image(img: &IndexedImage) -> io::Result<Vec<u8>> {
+    let bpp = img.bits_per_pixel();
+    let (width, height) = (img.get_width(), img.get_height());
+    let data_size = checked_data_size(bpp, width, height)?;
+    let size = encoded_size(bpp, width, height);
+    let mut bmp_data = Vec::with_capacity(size as usize);
 
-    io::Write::write(bmp_data, &[B, M])?;
+    let header = BmpHeader::new(HEADER_SIZE, data_size);
+    let dib_header = BmpDibHeader::new(width as i32, height as i32);
+    write_header(&mut bmp_data, &header, &dib_header, bpp, data_size)?;
+    // The palette always has an entry for every index, the unused ones are black
+    let colors = img.palette().colors();
+    for i in 0..1usize << bpp {
+        let px = colors.get(i).cloned().unwrap_or(Pixel::new(0, 0, 0));
+        bmp_data.write_all(&[px.b, px.g, px.r, 0])?;
+    }
+    // The indices are packed from the most significant bits of each byte, the padding is left
+    // zeroed
+    let mut row = vec![0; row_stride(bpp, width) as usize];
+    for indices in img.indices().chunks(width.max(1) as usize).rev() {
+        row.iter_mut().for_each(|b| *b = 0);
+        for (x, &index) in indices.iter().enumerate() {
+            let bit = x * bpp as usize;
+            row[bit / 8] |= index << (8 - bpp as usize - bit % 8);
+        }
+        bmp_data.write_all(&row)?;
+    }
+    Ok(bmp_data)
+}
+
+fn write_header<W: Write>(
+    bmp_data: &mut W,
+    header: &BmpHeader,
+    dib_header: &BmpDibHeader,
+    bpp: u16,
+    data_size: u32,
+) -> io::Result<()> {
+    // The color palette follows the headers of images with 8 or less bits per pixel
+    let header_size = encoded_size(bpp, 0, 0) as u32;
+
+    bmp_data.write_all(&[B, M])?;
 
     bmp_data.write_u32::<LittleEndian>(header_size + data_size)?;
     bmp_data.write_u16::<LittleEndian>(header.creator1)?;
@@ -32,7 +80,7 @@ fn write_header(bmp_data: &mut Vec<u8>, img: &Image) -> io::Result<()> {
     bmp_data.write_i32::<LittleEndian>(dib_header.width)?;
     bmp_data.write_i32::<LittleEndian>(dib_header.height)?;
     bmp_data.write_u16::<LittleEndian>(1)?; // num_planes
-    bmp_data.write_u16::<LittleEndian>(24)?; // bits_per_pixel
+    bmp_data.write_u16::<LittleEndian>(bpp)?; // bits_per_pixel
     bmp_data.write_u32::<LittleEndian>(0)?; // compress_type
     bmp_data.write_u32::<LittleEndian>(data_size)?;
     bmp_data.write_i32::<LittleEndian>(dib_header.hres)?;
@@ -42,15 +90,252 @@ fn write_header(bmp_data: &mut Vec<u8>, img: &Image) -> io::Result<()> {
     Ok(())
 }
 
+#[cfg(not(feature = "rayon"))]
 fn write_data(bmp_data: &mut Vec<u8>, img: &Image) -> io::Result<()> {
-    let padding = &[0; 4][0..img.padding as usize];
-    for y in 0..img.height {
-        for x in 0..img.width {
-            let index = (y * img.width + x) as usize;
-            let px = &img.data[index];
-            Write::write(bmp_data, &[px.b, px.g, px.r])?;
-        }
-        Write::write(bmp_data, padding)?;
+    // Every row is converted into the row buffer, the padding is left zeroed
+    let mut row = vec![0; row_stride(24, img.width) as usize];
+    for pixels in img.data.chunks(img.width.max(1) as usize) {
+        shuffle::pixels_to_bgr(pixels, &mut row);
+        bmp_data.write_all(&row)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "rayon")]
+fn write_data(bmp_data: &mut Vec<u8>, img: &Image) -> io::Result<()> {
+    use rayon::prelude::*;
+
+    if img.width == 0 || img.height == 0 {
+        return Ok(());
     }
+    // Every row is converted into its own slice of the output, the padding is left zeroed
+    let stride = row_stride(24, img.width) as usize;
+    let start = bmp_data.len();
+    bmp_data.resize(start + stride * img.height as usize, 0);
+    bmp_data[start..]
+        .par_chunks_mut(stride)
+        .zip(img.data.par_chunks(img.width as usize))
+        .for_each(|(out, row)| shuffle::pixels_to_bgr(row, out));
     Ok(())
 }
+
+/// Encodes an image one row at a time, in the order the rows are stored in the file, so images
+/// of any size can be written in constant memory. The image is encoded like `Image::to_writer`,
+/// with 24 bits per pixel.
+///
+/// The headers are written together with the first row, and `finish` checks that every row was
+/// written.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, RowEncoder};
+///
+/// let mut encoder = RowEncoder::new(Vec::new(), 2, 2).unwrap();
+/// // The rows are written from the bottom to the top
+/// encoder.write_row(&[consts::RED, consts::LIME]).unwrap();
+/// encoder.write_row(&[consts::BLUE, consts::WHITE]).unwrap();
+/// let bytes = encoder.finish().unwrap();
+///
+/// let img = bmp::from_reader(&mut &bytes[..]).unwrap();
+/// assert_eq!(consts::BLUE, img.get_pixel(0, 0));
+/// assert_eq!(consts::LIME, img.get_pixel(1, 1));
+/// ```
+#[derive(Debug)]
+pub struct RowEncoder<W: Write> {
+    destination: W,
+    dib_header: BmpDibHeader,
+    data_size: u32,
+    row: Vec<u8>,
+    rows_written: u32,
+    monitor: Monitor,
+}
+
+impl<W: Write> RowEncoder<W> {
+    /// Returns a new `RowEncoder` that writes an image of `width` times `height` pixels to
+    /// `destination`.
+    ///
+    /// Returns a `BmpError` if the encoded image doesn't fit in the 32-bit sizes of the headers.
+    pub fn new(destination: W, width: u32, height: u32) -> BmpResult<RowEncoder<W>> {
+        let data_size = checked_data_size(24, width, height)?;
+        Ok(RowEncoder {
+            destination,
+            dib_header: BmpDibHeader::new(width as i32, height as i32),
+            data_size,
+            row: vec![0; row_stride(24, width) as usize],
+            rows_written: 0,
+            monitor: Monitor::default(),
+        })
+    }
+
+    /// Sets the horizontal and vertical resolution of the image in dots per inch.
+    pub fn with_dpi(mut self, x: f32, y: f32) -> RowEncoder<W> {
+        self.dib_header.hres = ::dpi_to_ppm(x);
+        self.dib_header.vres = ::dpi_to_ppm(y);
+        self
+    }
+
+    /// Calls `progress` with the number of bytes written so far and the total number of bytes of
+    /// the image after every row.
+    pub fn with_progress<F>(mut self, progress: F) -> RowEncoder<W>
+    where
+        F: FnMut(u64, u64) + Send + 'static,
+    {
+        self.monitor.set_progress(progress);
+        self
+    }
+
+    /// Checks `token` before every row, and returns a `BmpError` of the kind `Cancelled` from
+    /// `write_row` once the token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> RowEncoder<W> {
+        self.monitor.set_cancellation(token);
+        self
+    }
+
+    // Copies the resolution as it is stored in a header, to keep it exact
+    pub(crate) fn with_resolution(mut self, hres: i32, vres: i32) -> RowEncoder<W> {
+        self.dib_header.hres = hres;
+        self.dib_header.vres = vres;
+        self
+    }
+
+    fn get_width(&self) -> u32 {
+        self.dib_header.width as u32
+    }
+
+    fn get_height(&self) -> u32 {
+        self.dib_header.height as u32
+    }
+
+    fn write_headers_once(&mut self) -> io::Result<()> {
+        if self.rows_written > 0 {
+            return Ok(());
+        }
+        let header = BmpHeader::new(HEADER_SIZE, self.data_size);
+        write_header(
+            &mut self.destination,
+            &header,
+            &self.dib_header,
+            24,
+            self.data_size,
+        )
+    }
+
+    /// Writes the next row of `pixels`. The rows are written from the bottom to the top of the
+    /// image.
+    ///
+    /// Returns a `BmpError` if the row doesn't have `width` pixels, or if every row has already
+    /// been written.
+    pub fn write_row(&mut self, pixels: &[Pixel]) -> BmpResult<()> {
+        if pixels.len() != self.get_width() as usize {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::WrongBufferSize,
+                "row",
+                format!("{} pixels", self.get_width()),
+                pixels.len(),
+            ));
+        }
+        if self.rows_written == self.get_height() {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::OutOfBounds,
+                "rows",
+                format!("at most {}", self.get_height()),
+                self.rows_written as u64 + 1,
+            ));
+        }
+        self.monitor.check()?;
+        self.write_headers_once()?;
+        shuffle::pixels_to_bgr(pixels, &mut self.row);
+        self.destination.write_all(&self.row)?;
+        self.rows_written += 1;
+
+        let stride = self.row.len() as u64;
+        let done = HEADER_SIZE as u64 + self.rows_written as u64 * stride;
+        self.monitor.report(done, HEADER_SIZE as u64 + self.data_size as u64);
+        Ok(())
+    }
+
+    /// Flushes the encoded image, and returns the destination.
+    ///
+    /// Returns a `BmpError` if fewer than `height` rows were written.
+    pub fn finish(mut self) -> BmpResult<W> {
+        self.write_headers_once()?;
+        if self.rows_written < self.get_height() {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::WrongBufferSize,
+                "rows",
+                self.get_height(),
+                self.rows_written,
+            ));
+        }
+        self.destination.flush()?;
+        Ok(self.destination)
+    }
+}
+
+/// A BMP encoder, with options for reporting the progress and cancelling the encoding of large
+/// images. The images are encoded like `Image::to_writer`, one row at a time.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{CancellationToken, Encoder, Image};
+///
+/// let token = CancellationToken::new();
+/// let canceller = token.clone();
+/// let mut encoder = Encoder::new()
+///     .with_cancellation(token)
+///     .with_progress(move |done, total| {
+///         // Give up halfway through
+///         if done * 2 >= total {
+///             canceller.cancel();
+///         }
+///     });
+///
+/// let img = Image::new(100, 80);
+/// assert!(encoder.encode(&img, Vec::new()).is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct Encoder {
+    monitor: Monitor,
+}
+
+impl Encoder {
+    /// Returns a new `Encoder` that reports no progress and cannot be cancelled.
+    pub fn new() -> Encoder {
+        Encoder::default()
+    }
+
+    /// Calls `progress` with the number of bytes written so far and the total number of bytes of
+    /// the image after every row.
+    pub fn with_progress<F: FnMut(u64, u64) + Send + 'static>(mut self, progress: F) -> Encoder {
+        self.monitor.set_progress(progress);
+        self
+    }
+
+    /// Checks `token` before every row, and stops encoding with a `BmpError` of the kind
+    /// `Cancelled` once the token is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Encoder {
+        self.monitor.set_cancellation(token);
+        self
+    }
+
+    /// Encodes `image` to `destination`, and returns the destination.
+    ///
+    /// Returns a `BmpError` if the image is too large to be encoded, if it cannot be written, or
+    /// if the encoding is cancelled. The destination may then contain a partial image.
+    pub fn encode<W: Write>(&mut self, image: &Image, destination: W) -> BmpResult<W> {
+        let (hres, vres) = (image.dib_header.hres, image.dib_header.vres);
+        let mut encoder =
+            RowEncoder::new(destination, image.width, image.height)?.with_resolution(hres, vres);
+        // The encoder borrows the progress callback for the duration of a single image
+        mem::swap(&mut self.monitor, &mut encoder.monitor);
+        let width = image.width as usize;
+        // The pixels are stored from bottom to top, in the same order as the rows of the file
+        let result = (0..image.height as usize)
+            .try_for_each(|y| encoder.write_row(&image.data[y * width..(y + 1) * width]));
+        mem::swap(&mut self.monitor, &mut encoder.monitor);
+        result?;
+        encoder.finish()
+    }
+}
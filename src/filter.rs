@@ -0,0 +1,186 @@
+use {Image, Pixel};
+
+// Blurs `len` pixels that are `step` apart in `src`, starting at `start`, into `dst`. The window
+// of each pixel is kept as a running sum, and the edges are extended by repeating the outermost
+// pixels.
+fn blur_line(src: &[Pixel], dst: &mut [Pixel], start: usize, step: usize, len: usize, r: usize) {
+    let at = |i: usize| src[start + i.min(len - 1) * step];
+    let window = (2 * r + 1) as u64;
+
+    // The window of the first pixel, from -r to r
+    let mut sum = [0u64; 3];
+    for i in 0..=r {
+        let px = at(i);
+        let n = if i == 0 { r as u64 + 1 } else { 1 };
+        sum[0] += px.r as u64 * n;
+        sum[1] += px.g as u64 * n;
+        sum[2] += px.b as u64 * n;
+    }
+
+    for i in 0..len {
+        dst[start + i * step] = Pixel::new(
+            ((sum[0] + window / 2) / window) as u8,
+            ((sum[1] + window / 2) / window) as u8,
+            ((sum[2] + window / 2) / window) as u8,
+        );
+        // Slide the window one pixel forward
+        let (enter, leave) = (at(i + r + 1), at(i.saturating_sub(r)));
+        sum[0] = sum[0] + enter.r as u64 - leave.r as u64;
+        sum[1] = sum[1] + enter.g as u64 - leave.g as u64;
+        sum[2] = sum[2] + enter.b as u64 - leave.b as u64;
+    }
+}
+
+impl Image {
+    /// Returns a new `Image` where every pixel is the average of the square of pixels within
+    /// `radius` of it. The pixels along the edges are repeated outside of the image.
+    ///
+    /// The averages are computed with running sums, so the cost does not depend on the radius.
+    /// A radius larger than the image is reduced to the width or height of the image, whichever
+    /// is larger.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let mut img = Image::new(3, 3);
+    /// img.set_pixel(1, 1, consts::WHITE);
+    /// let blurred = img.box_blur(1);
+    /// assert_eq!(Pixel::new(28, 28, 28), blurred.get_pixel(0, 0));
+    /// ```
+    pub fn box_blur(&self, radius: u32) -> Image {
+        let (width, height) = (self.width as usize, self.height as usize);
+        if radius == 0 || width == 0 || height == 0 {
+            return self.clone();
+        }
+        // Summing the windows of huge radii would take long and could overflow
+        let r = radius.min(self.width.max(self.height)) as usize;
+
+        // Blur the rows first, then the columns
+        let mut rows = self.data.clone();
+        for y in 0..height {
+            blur_line(&self.data, &mut rows, y * width, 1, width, r);
+        }
+        let mut data = rows.clone();
+        for x in 0..width {
+            blur_line(&rows, &mut data, x, width, height, r);
+        }
+        Image::from_data(self.width, self.height, data)
+    }
+
+    /// Returns a new `Image` with the edges enhanced, like `unsharp_mask` with a radius of 1, an
+    /// amount of 1 and no threshold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(100, 80);
+    /// let sharpened = img.sharpen();
+    /// assert_eq!(img, sharpened);
+    /// ```
+    pub fn sharpen(&self) -> Image {
+        self.unsharp_mask(1, 1.0, 0)
+    }
+
+    /// Returns a new `Image` with the edges enhanced by adding the difference between the image
+    /// and a blurred copy of it. The copy is blurred with `box_blur(radius)`, and the difference
+    /// is scaled by `amount`. Channels that differ by `threshold` or less from the blurred copy
+    /// are unchanged, so flat areas and noise are not amplified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(3, 1);
+    /// img.set_pixel(0, 0, consts::GRAY);
+    /// img.set_pixel(2, 0, consts::WHITE);
+    /// let sharpened = img.unsharp_mask(1, 1.0, 0);
+    /// // The dark center pixel gets darker next to the bright pixels
+    /// assert_eq!(consts::BLACK, sharpened.get_pixel(1, 0));
+    /// ```
+    pub fn unsharp_mask(&self, radius: u32, amount: f32, threshold: u8) -> Image {
+        let blurred = self.box_blur(radius);
+        let channel = |v: u8, b: u8| {
+            let diff = v as f32 - b as f32;
+            if diff.abs() <= threshold as f32 {
+                v
+            } else {
+                (v as f32 + diff * amount).round().clamp(0.0, 255.0) as u8
+            }
+        };
+        let data = self
+            .data
+            .iter()
+            .zip(&blurred.data)
+            .map(|(px, b)| Pixel::new(channel(px.r, b.r), channel(px.g, b.g), channel(px.b, b.b)))
+            .collect();
+        Image::from_data(self.width, self.height, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    // Averages the square around every pixel directly
+    fn naive_box_blur(img: &Image, radius: i64) -> Image {
+        let mut blurred = Image::new(img.get_width(), img.get_height());
+        let n = ((2 * radius + 1) * (2 * radius + 1)) as u32;
+        for (x, y) in img.coordinates() {
+            let mut sum = [0u32; 3];
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let nx = (x as i64 + dx).clamp(0, img.get_width() as i64 - 1) as u32;
+                    let ny = (y as i64 + dy).clamp(0, img.get_height() as i64 - 1) as u32;
+                    let px = img.get_pixel(nx, ny);
+                    sum[0] += px.r as u32;
+                    sum[1] += px.g as u32;
+                    sum[2] += px.b as u32;
+                }
+            }
+            let avg = |s: u32| ((s + n / 2) / n) as u8;
+            blurred.set_pixel(x, y, Pixel::new(avg(sum[0]), avg(sum[1]), avg(sum[2])));
+        }
+        blurred
+    }
+
+    #[test]
+    fn box_blur_averages_the_neighborhood() {
+        let mut img = Image::new(7, 5);
+        img.fill(consts::WHITE);
+        img.fill_rect(2, 1, 3, 2, consts::NAVY);
+        img.set_pixel(6, 4, consts::ORANGE);
+
+        // Rounding once per pass makes the result differ from a single average by at most one
+        for radius in 1..5 {
+            let blurred = img.box_blur(radius);
+            let expected = naive_box_blur(&img, radius as i64);
+            for (a, b) in blurred.data.iter().zip(expected.data.iter()) {
+                assert!((a.r as i32 - b.r as i32).abs() <= 1);
+                assert!((a.g as i32 - b.g as i32).abs() <= 1);
+                assert!((a.b as i32 - b.b as i32).abs() <= 1);
+            }
+        }
+        assert_eq!(img.box_blur(0), img);
+        assert_eq!(img.box_blur(u32::MAX), img.box_blur(7));
+    }
+
+    #[test]
+    fn unsharp_mask_respects_the_threshold() {
+        let mut img = Image::new(5, 1);
+        img.fill(Pixel::new(100, 100, 100));
+        img.set_pixel(2, 0, Pixel::new(130, 106, 100));
+
+        let sharpened = img.unsharp_mask(1, 1.0, 0);
+        assert_eq!(sharpened.get_pixel(2, 0), Pixel::new(150, 110, 100));
+        assert_eq!(sharpened.get_pixel(1, 0), Pixel::new(90, 98, 100));
+
+        // Only the red channel of the center pixel differs by more than the threshold
+        let sharpened = img.unsharp_mask(1, 1.0, 5);
+        assert_eq!(sharpened.get_pixel(2, 0), Pixel::new(150, 106, 100));
+        assert_eq!(sharpened.get_pixel(1, 0), Pixel::new(90, 100, 100));
+    }
+}
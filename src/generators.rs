@@ -0,0 +1,204 @@
+use draw::lerp;
+use {Image, Pixel};
+
+// Mixes the bits of `x` with the finalizer of SplitMix64
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+// A random value from 0 up to 255 for the position `x` and `y`, which only depends on the
+// position and the seed
+fn random(x: i64, y: i64, seed: u64) -> u8 {
+    let h = mix(seed ^ mix(x as u64 ^ mix(y as u64).rotate_left(32)));
+    (h >> 56) as u8
+}
+
+/// Returns a new `Image` of `width` and `height` filled with white noise, where every pixel is
+/// a random shade of gray.
+///
+/// The noise is the same for the same `seed`, and every pixel only depends on its position, so
+/// a smaller image shows the upper left corner of a larger one.
+///
+/// # Example
+///
+/// ```
+/// use bmp::generators;
+///
+/// let a = generators::noise(100, 80, 42);
+/// let b = generators::noise(100, 80, 42);
+/// assert_eq!(a, b);
+/// assert_ne!(a, generators::noise(100, 80, 43));
+/// ```
+pub fn noise(width: u32, height: u32, seed: u64) -> Image {
+    let mut img = Image::new(width, height);
+    for (x, y) in img.coordinates() {
+        let v = random(x as i64, y as i64, seed);
+        img.set_pixel(x, y, Pixel::new(v, v, v));
+    }
+    img
+}
+
+/// Returns a new `Image` of `width` and `height` filled with value noise, which is smooth noise
+/// in shades of gray. Random values are placed on a grid with `cell_size` pixels between the
+/// points, and the pixels between them are smoothly interpolated. A larger `cell_size` gives
+/// larger features.
+///
+/// Like `noise`, the result only depends on the `seed` and the position of the pixels.
+///
+/// Panics if `cell_size` is 0.
+///
+/// # Example
+///
+/// ```
+/// use bmp::generators;
+///
+/// let img = generators::value_noise(100, 80, 16, 42);
+/// // Neighboring pixels have similar values
+/// let (a, b) = (img.get_pixel(10, 10), img.get_pixel(11, 10));
+/// assert!((a.r as i32 - b.r as i32).abs() < 32);
+/// ```
+pub fn value_noise(width: u32, height: u32, cell_size: u32, seed: u64) -> Image {
+    assert!(cell_size > 0, "The cells must be at least 1 pixel");
+    let smoothstep = |t: f32| t * t * (3.0 - 2.0 * t);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let mut img = Image::new(width, height);
+    for (x, y) in img.coordinates() {
+        let (cx, cy) = ((x / cell_size) as i64, (y / cell_size) as i64);
+        let tx = smoothstep((x % cell_size) as f32 / cell_size as f32);
+        let ty = smoothstep((y % cell_size) as f32 / cell_size as f32);
+
+        // Interpolate between the values at the corners of the cell
+        let corner = |dx: i64, dy: i64| random(cx + dx, cy + dy, seed) as f32;
+        let top = lerp(corner(0, 0), corner(1, 0), tx);
+        let bottom = lerp(corner(0, 1), corner(1, 1), tx);
+        let v = lerp(top, bottom, ty).round() as u8;
+        img.set_pixel(x, y, Pixel::new(v, v, v));
+    }
+    img
+}
+
+/// Returns a new `Image` of `width` and `height` with a linear gradient from the color `from`
+/// to the color `to`. The colors change in the direction of `angle`, in degrees clockwise from
+/// the positive x-axis, so 0 goes from left to right and 90 from top to bottom. The corners of
+/// the image where the gradient starts and ends get the exact colors.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, generators, Pixel};
+///
+/// let img = generators::linear_gradient(256, 256, consts::BLACK, consts::WHITE, 90.0);
+/// assert_eq!(consts::BLACK, img.get_pixel(100, 0));
+/// assert_eq!(Pixel::new(128, 128, 128), img.get_pixel(100, 128));
+/// assert_eq!(consts::WHITE, img.get_pixel(100, 255));
+/// ```
+pub fn linear_gradient(width: u32, height: u32, from: Pixel, to: Pixel, angle: f32) -> Image {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (cx, cy) = (
+        width.saturating_sub(1) as f32 / 2.0,
+        height.saturating_sub(1) as f32 / 2.0,
+    );
+    // The length of the image in the direction of the gradient
+    let extent = 2.0 * (cx * cos.abs() + cy * sin.abs());
+
+    let mut img = Image::new(width, height);
+    for (x, y) in img.coordinates() {
+        let along = (x as f32 - cx) * cos + (y as f32 - cy) * sin;
+        let t = if extent > 0.0 {
+            (along / extent + 0.5).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        img.set_pixel(x, y, lerp(from, to, t));
+    }
+    img
+}
+
+/// Returns a new `Image` of `width` and `height` with a radial gradient, going from the color
+/// `inner` at `center` to the color `outer` at `radius` pixels from it. The pixels further away
+/// get the color `outer`.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, generators};
+///
+/// let (inner, outer) = (consts::WHITE, consts::BLACK);
+/// let img = generators::radial_gradient(100, 80, (50.5, 40.5), 40.0, inner, outer);
+/// assert_eq!(consts::WHITE, img.get_pixel(50, 40));
+/// assert_eq!(consts::BLACK, img.get_pixel(0, 0));
+/// ```
+pub fn radial_gradient(
+    width: u32,
+    height: u32,
+    center: (f32, f32),
+    radius: f32,
+    inner: Pixel,
+    outer: Pixel,
+) -> Image {
+    let mut img = Image::new(width, height);
+    for (x, y) in img.coordinates() {
+        // Measure the distance from the center of the pixel
+        let (dx, dy) = (x as f32 + 0.5 - center.0, y as f32 + 0.5 - center.1);
+        let distance = (dx * dx + dy * dy).sqrt();
+        let t = if radius > 0.0 {
+            (distance / radius).min(1.0)
+        } else {
+            1.0
+        };
+        img.set_pixel(x, y, lerp(inner, outer, t));
+    }
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_spread_over_all_values() {
+        let img = noise(64, 64, 7);
+        let mut counts = [0u32; 4];
+        for (x, y) in img.coordinates() {
+            let px = img.get_pixel(x, y);
+            assert!(px.r == px.g && px.g == px.b);
+            counts[px.r as usize / 64] += 1;
+        }
+        // Each quarter of the values should get about a quarter of the pixels
+        assert!(counts.iter().all(|&n| (900..1150).contains(&n)));
+
+        // A smaller image is the corner of a larger one
+        assert_eq!(noise(10, 10, 7), img.crop(0, 0, 10, 10).unwrap());
+    }
+
+    #[test]
+    fn value_noise_matches_the_grid_points() {
+        let img = value_noise(33, 33, 16, 3);
+        for &(x, y) in &[(0u32, 0u32), (16, 0), (32, 16), (32, 32)] {
+            let v = random((x / 16) as i64, (y / 16) as i64, 3);
+            assert_eq!(img.get_pixel(x, y), Pixel::new(v, v, v));
+        }
+    }
+
+    #[test]
+    fn linear_gradients_follow_the_angle() {
+        let (from, to) = (Pixel::new(0, 0, 0), Pixel::new(200, 100, 0));
+
+        let img = linear_gradient(5, 3, from, to, 0.0);
+        for y in 0..3 {
+            assert_eq!(img.get_pixel(0, y), from);
+            assert_eq!(img.get_pixel(2, y), Pixel::new(100, 50, 0));
+            assert_eq!(img.get_pixel(4, y), to);
+        }
+
+        let img = linear_gradient(5, 3, from, to, 270.0);
+        assert_eq!(img.get_pixel(3, 2), from);
+        assert_eq!(img.get_pixel(3, 0), to);
+
+        let img = linear_gradient(1, 1, from, to, 30.0);
+        assert_eq!(img.get_pixel(0, 0), from);
+    }
+}
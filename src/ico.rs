@@ -0,0 +1,313 @@
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use {decoder, row_stride, BmpError, BmpErrorKind, BmpResult, ImageRgba, PixelRgba};
+
+// The sizes of the ICONDIR header, of each ICONDIRENTRY, and of the BITMAPINFOHEADER
+const DIR_SIZE: usize = 6;
+const ENTRY_SIZE: usize = 16;
+const DIB_HEADER_SIZE: u32 = 40;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG";
+
+/// Reads every image in the ICO or CUR file from `source`, in the order of the entries.
+///
+/// The transparency of the images is read from the alpha channel of 32 bpp entries, and from
+/// the AND mask of the other entries, so fully transparent pixels have an alpha of 0. Entries
+/// compressed as PNG are not supported.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{ico, ImageRgba, PixelRgba};
+///
+/// let mut icon = ImageRgba::new(16, 16);
+/// icon.set_pixel(8, 8, PixelRgba::new(255, 0, 0, 255));
+///
+/// let mut bytes = Vec::new();
+/// ico::write(&mut bytes, &[icon.clone(), ImageRgba::new(32, 32)]).unwrap();
+/// let images = ico::read(&mut &bytes[..]).unwrap();
+/// assert_eq!(2, images.len());
+/// assert_eq!(icon, images[0]);
+/// ```
+pub fn read<R: Read>(source: &mut R) -> BmpResult<Vec<ImageRgba>> {
+    let mut bytes = Vec::new();
+    source.read_to_end(&mut bytes)?;
+
+    let mut dir = &bytes[..];
+    let (reserved, kind, count) = (
+        dir.read_u16::<LittleEndian>()?,
+        dir.read_u16::<LittleEndian>()?,
+        dir.read_u16::<LittleEndian>()?,
+    );
+    if reserved != 0 || (kind != 1 && kind != 2) {
+        return Err(BmpError::mismatch(
+            BmpErrorKind::WrongMagicNumbers,
+            "type",
+            "1 for an icon or 2 for a cursor",
+            kind,
+        ));
+    }
+
+    (0..count as usize)
+        .map(|i| {
+            let entry = bytes
+                .get(DIR_SIZE + i * ENTRY_SIZE..DIR_SIZE + (i + 1) * ENTRY_SIZE)
+                .ok_or_else(|| {
+                    BmpError::new(BmpErrorKind::OutOfBounds, "The entries are truncated")
+                })?;
+            let size = LittleEndian::read_u32(&entry[8..12]) as usize;
+            let offset = LittleEndian::read_u32(&entry[12..16]) as usize;
+            let data = offset
+                .checked_add(size)
+                .and_then(|end| bytes.get(offset..end))
+                .ok_or_else(|| {
+                    BmpError::new(
+                        BmpErrorKind::OutOfBounds,
+                        format!("The entry {} is outside of the file", i),
+                    )
+                })?;
+            read_entry(data)
+        })
+        .collect()
+}
+
+/// Reads every image in the ICO or CUR file specified by `path`.
+/// See `read` for details.
+pub fn open<P: AsRef<Path>>(path: P) -> BmpResult<Vec<ImageRgba>> {
+    let mut f = fs::File::open(path)?;
+    read(&mut f)
+}
+
+// Decodes the DIB of a single entry, where the height covers both the image and the AND mask
+fn read_entry(data: &[u8]) -> BmpResult<ImageRgba> {
+    if data.starts_with(PNG_SIGNATURE) {
+        return Err(BmpError::new(
+            BmpErrorKind::UnsupportedCompressionType,
+            "PNG compressed icons are not supported",
+        ));
+    }
+    if data.len() < DIB_HEADER_SIZE as usize {
+        return Err(BmpError::new(
+            BmpErrorKind::OutOfBounds,
+            "The DIB header is truncated",
+        ));
+    }
+    let header_size = LittleEndian::read_u32(&data[0..4]);
+    let width = LittleEndian::read_i32(&data[4..8]).unsigned_abs();
+    let height = LittleEndian::read_i32(&data[8..12]).unsigned_abs() / 2;
+    let bpp = LittleEndian::read_u16(&data[14..16]);
+    let num_colors = LittleEndian::read_u32(&data[32..36]) as u64;
+    let palette_size = match bpp {
+        _ if num_colors != 0 => num_colors * 4,
+        1 | 4 | 8 => 4 << bpp,
+        _ => 0,
+    };
+    let pixels_offset = header_size as u64 + palette_size;
+
+    let mut img = if bpp == 32 {
+        read_bgra(data, pixels_offset as usize, width, height)?
+    } else {
+        // Reuse the BMP decoder, by adding a file header and halving the height in the DIB
+        // header, so that only the image is decoded
+        let mut bmp = Vec::with_capacity(14 + data.len());
+        bmp.extend_from_slice(b"BM");
+        bmp.write_u32::<LittleEndian>(14 + data.len() as u32)?;
+        bmp.write_u32::<LittleEndian>(0)?;
+        bmp.write_u32::<LittleEndian>(14 + pixels_offset as u32)?;
+        bmp.extend_from_slice(data);
+        LittleEndian::write_i32(&mut bmp[22..26], height as i32);
+        ImageRgba::from(&decoder::decode_image(&bmp[..])?)
+    };
+
+    // Without a meaningful alpha channel, the transparency is given by the AND mask
+    let has_alpha = bpp == 32 && img.coordinates().any(|(x, y)| img.get_pixel(x, y).a != 0);
+    if !has_alpha {
+        let mask_offset = pixels_offset + row_stride(bpp, width) * height as u64;
+        apply_mask(&mut img, data.get(mask_offset as usize..).unwrap_or(&[]));
+    }
+    Ok(img)
+}
+
+fn read_bgra(data: &[u8], offset: usize, width: u32, height: u32) -> BmpResult<ImageRgba> {
+    let stride = width as usize * 4;
+    let pixels = data
+        .get(offset..offset + stride * height as usize)
+        .ok_or_else(|| BmpError::new(BmpErrorKind::OutOfBounds, "The pixels are truncated"))?;
+    let mut img = ImageRgba::new(width, height);
+    // The rows are stored from bottom to top
+    for (row, y) in pixels.chunks(stride.max(1)).zip((0..height).rev()) {
+        for (bgra, x) in row.chunks(4).zip(0..) {
+            img.set_pixel(x, y, PixelRgba::new(bgra[2], bgra[1], bgra[0], bgra[3]));
+        }
+    }
+    Ok(img)
+}
+
+// Makes the pixels with a set bit in the AND mask transparent, and the others opaque. A missing
+// mask leaves the image opaque.
+fn apply_mask(img: &mut ImageRgba, mask: &[u8]) {
+    let stride = row_stride(1, img.get_width()) as usize;
+    for (x, y) in img.coordinates() {
+        let row = (img.get_height() - y - 1) as usize;
+        let transparent = mask
+            .get(row * stride + x as usize / 8)
+            .is_some_and(|&bits| bits & (0x80 >> (x % 8)) != 0);
+        let mut px = img.get_pixel(x, y);
+        px.a = if transparent { 0 } else { 255 };
+        img.set_pixel(x, y, px);
+    }
+}
+
+/// Writes the `images` as an ICO file with one entry per image to `destination`.
+///
+/// The images are written as 32 bpp entries, with an AND mask where the fully transparent
+/// pixels are set.
+///
+/// Returns a `BmpError` if there are no images, or if an image is smaller than 1x1 or larger
+/// than 256x256.
+pub fn write<W: Write>(destination: &mut W, images: &[ImageRgba]) -> BmpResult<()> {
+    if images.is_empty() || images.len() > u16::MAX as usize {
+        return Err(BmpError::mismatch(
+            BmpErrorKind::OutOfBounds,
+            "images.len()",
+            "1 to 65535",
+            images.len(),
+        ));
+    }
+    for img in images {
+        let (width, height) = (img.get_width(), img.get_height());
+        if width == 0 || height == 0 || width > 256 || height > 256 {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::OutOfBounds,
+                "dimensions",
+                "1x1 to 256x256",
+                format!("{}x{}", width, height),
+            ));
+        }
+    }
+
+    let entries = images
+        .iter()
+        .map(write_entry)
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut bytes = Vec::new();
+    bytes.write_u16::<LittleEndian>(0)?; // reserved
+    bytes.write_u16::<LittleEndian>(1)?; // icon
+    bytes.write_u16::<LittleEndian>(images.len() as u16)?;
+    let mut offset = DIR_SIZE + ENTRY_SIZE * images.len();
+    for (img, entry) in images.iter().zip(&entries) {
+        // A size of 256 is written as 0
+        bytes.push(img.get_width() as u8);
+        bytes.push(img.get_height() as u8);
+        bytes.push(0); // num_colors
+        bytes.push(0); // reserved
+        bytes.write_u16::<LittleEndian>(1)?; // num_planes
+        bytes.write_u16::<LittleEndian>(32)?; // bits_per_pixel
+        bytes.write_u32::<LittleEndian>(entry.len() as u32)?;
+        bytes.write_u32::<LittleEndian>(offset as u32)?;
+        offset += entry.len();
+    }
+    for entry in entries {
+        bytes.extend_from_slice(&entry);
+    }
+    destination.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Saves the `images` as an ICO file to the path specified by `path`.
+/// See `write` for details.
+pub fn save<P: AsRef<Path>>(path: P, images: &[ImageRgba]) -> BmpResult<()> {
+    let mut f = fs::File::create(path)?;
+    write(&mut f, images)
+}
+
+fn write_entry(img: &ImageRgba) -> io::Result<Vec<u8>> {
+    let (width, height) = (img.get_width(), img.get_height());
+    let mask_stride = row_stride(1, width) as usize;
+    let size = (width * 4 * height) as usize + mask_stride * height as usize;
+
+    let mut data = Vec::with_capacity(DIB_HEADER_SIZE as usize + size);
+    data.write_u32::<LittleEndian>(DIB_HEADER_SIZE)?;
+    data.write_i32::<LittleEndian>(width as i32)?;
+    data.write_i32::<LittleEndian>(height as i32 * 2)?;
+    data.write_u16::<LittleEndian>(1)?; // num_planes
+    data.write_u16::<LittleEndian>(32)?; // bits_per_pixel
+    data.write_u32::<LittleEndian>(0)?; // compress_type
+    data.write_u32::<LittleEndian>(size as u32)?;
+    data.write_i32::<LittleEndian>(0)?; // hres
+    data.write_i32::<LittleEndian>(0)?; // vres
+    data.write_u32::<LittleEndian>(0)?; // num_colors
+    data.write_u32::<LittleEndian>(0)?; // num_imp_colors
+
+    // The rows are stored from bottom to top
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let px = img.get_pixel(x, y);
+            data.extend_from_slice(&[px.b, px.g, px.r, px.a]);
+        }
+    }
+    for y in (0..height).rev() {
+        let mut row = vec![0; mask_stride];
+        for x in 0..width {
+            if img.get_pixel(x, y).a == 0 {
+                row[x as usize / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        data.extend_from_slice(&row);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+    use Image;
+
+    #[test]
+    fn written_icons_keep_their_transparency() {
+        let mut icon = ImageRgba::new(256, 3);
+        icon.set_pixel(255, 2, PixelRgba::new(1, 2, 3, 128));
+        icon.set_pixel(0, 0, PixelRgba::new(4, 5, 6, 255));
+
+        let mut bytes = Vec::new();
+        write(&mut bytes, &[icon.clone()]).unwrap();
+        assert_eq!(bytes[DIR_SIZE], 0);
+        assert_eq!(read(&mut &bytes[..]).unwrap(), vec![icon]);
+
+        match write(&mut bytes, &[ImageRgba::new(257, 1)]) {
+            Err(BmpError {
+                kind: BmpErrorKind::OutOfBounds,
+                ..
+            }) => (/* Expected */),
+            _ => panic!("The image is too large for an icon"),
+        }
+    }
+
+    #[test]
+    fn entries_without_alpha_use_the_and_mask() {
+        // A 24 bpp entry, made from the DIB of an encoded BMP and a mask for a 2x2 image
+        let mut img = Image::new(2, 2);
+        img.set_pixel(1, 0, consts::RED);
+        let mut dib = Vec::new();
+        img.to_writer(&mut dib).unwrap();
+        let mut dib = dib.split_off(14);
+        LittleEndian::write_i32(&mut dib[8..12], 4);
+        // The bottom row is first, and the pixel at (0, 1) is transparent
+        dib.extend_from_slice(&[0b1000_0000, 0, 0, 0, 0, 0, 0, 0]);
+
+        let mut bytes = vec![0, 0, 1, 0, 1, 0, 2, 2, 0, 0, 1, 0, 24, 0];
+        bytes.write_u32::<LittleEndian>(dib.len() as u32).unwrap();
+        bytes.write_u32::<LittleEndian>(22).unwrap();
+        bytes.extend_from_slice(&dib);
+
+        let images = read(&mut &bytes[..]).unwrap();
+        assert_eq!(images[0].get_pixel(1, 0), PixelRgba::new(255, 0, 0, 255));
+        assert_eq!(images[0].get_pixel(0, 1).a, 0);
+        assert_eq!(images[0].get_pixel(1, 1).a, 255);
+    }
+}
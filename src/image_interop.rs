@@ -0,0 +1,61 @@
+use image::{DynamicImage, RgbImage};
+
+use {ChannelOrder, Image};
+
+/// Converts an `RgbImage` from the `image` crate into an `Image`.
+impl From<RgbImage> for Image {
+    fn from(img: RgbImage) -> Image {
+        let (width, height) = img.dimensions();
+        Image::from_raw_rgb(img.as_raw(), width, height)
+            .expect("The buffer of an RgbImage matches its dimensions")
+    }
+}
+
+/// Converts an `Image` into an `RgbImage` from the `image` crate.
+impl From<Image> for RgbImage {
+    fn from(img: Image) -> RgbImage {
+        let (width, height) = (img.get_width(), img.get_height());
+        RgbImage::from_raw(width, height, img.into_raw(ChannelOrder::Rgb))
+            .expect("The raw pixels of an Image match its dimensions")
+    }
+}
+
+/// Converts any `DynamicImage` from the `image` crate into an `Image`. Colors are converted to
+/// 8-bit RGB, and the alpha channel is dropped.
+impl From<DynamicImage> for Image {
+    fn from(img: DynamicImage) -> Image {
+        Image::from(img.into_rgb8())
+    }
+}
+
+/// Converts an `Image` into an 8-bit RGB `DynamicImage` from the `image` crate.
+impl From<Image> for DynamicImage {
+    fn from(img: Image) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from(img))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn conversions_keep_pixels_in_place() {
+        let mut img = Image::new(3, 2);
+        img.set_pixel(2, 0, consts::RED);
+        img.set_pixel(0, 1, consts::BLUE);
+
+        let rgb = RgbImage::from(img.clone());
+        assert_eq!(rgb.get_pixel(2, 0).0, [255, 0, 0]);
+        assert_eq!(rgb.get_pixel(0, 1).0, [0, 0, 255]);
+
+        let dynamic = DynamicImage::from(img.clone());
+        let rgba = dynamic.to_rgba8();
+        assert_eq!(rgba.get_pixel(0, 1).0, [0, 0, 255, 255]);
+        assert_eq!(
+            Image::from(DynamicImage::ImageRgba8(rgba)).as_raw_rgb(),
+            img.as_raw_rgb()
+        );
+    }
+}
@@ -1,5 +1,4 @@
 #![deny(warnings)]
-#![cfg_attr(test, deny(warnings))]
 
 //! A small library for reading and writing BMP images.
 //!
@@ -26,23 +25,122 @@
 //!     for (x, y) in img.coordinates() {
 //!         img.set_pixel(x, y, px!(x, y, 200));
 //!     }
-//!     let _ = img.save("img.bmp");
+//!     let _ = img.save(std::env::temp_dir().join("img.bmp"));
+//! #   let _ = std::fs::remove_file(std::env::temp_dir().join("img.bmp"));
 //! }
 //! ```
 //!
+//! # Features
+//!
+//! Optional functionality is enabled with the following Cargo features:
+//!
+//! - `ttf`: Drawing text with TrueType and OpenType fonts, using `fontdue`.
+//! - `rayon`: Processing the pixels of an image in parallel, using `rayon`.
+//! - `log`: Logging diagnostics about the decoded images, such as their version and the
+//!   inconsistencies that are tolerated, with the `log` crate.
+//! - `mmap`: Decoding images from memory-mapped files with `open_mmap`, using `memmap2`.
+//! - `image`: Conversions between `Image` and the `RgbImage` and `DynamicImage` types of the
+//!   `image` crate.
+//! - `ndarray`: Conversions between `Image` and the `Array3` type of the `ndarray` crate.
+//! - `rgb`: Conversions between `Pixel` and the `RGB8` and `RGBA8` types of the `rgb` crate, also
+//!   for whole slices of pixels without copying.
+//! - `serde`: Serializing and deserializing `Image` and `Pixel` with `serde`.
+//! - `tokio`: Reading and writing images asynchronously with `open_async`, `from_async_reader`
+//!   and `Image::save_async`, using `tokio`.
+//! - `arbitrary`: Generating images of up to 64x64 pixels and pixels for fuzzing, with
+//!   `arbitrary`.
+//! - `proptest`: Strategies that generate images and pixels for property tests, in the
+//!   `strategies` module.
+//! - `wasm-bindgen`: Decoding and encoding images from `Uint8Array`s in the browser, in the
+//!   `wasm` module.
+//! - `cli`: The `bmptool` command line tool, with the subcommands `info`, `convert`, `resize` and
+//!   `diff`.
+//!
+//! The crate compiles to `wasm32-unknown-unknown`. Only the functions that take a path use the
+//! file system, every other function works on readers, writers and buffers in memory.
+//!
 
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
 extern crate byteorder;
-
-use std::convert::AsRef;
+#[cfg(feature = "ttf")]
+extern crate fontdue;
+#[cfg(feature = "image")]
+extern crate image;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "rgb")]
+extern crate rgb;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "wasm-bindgen")]
+extern crate wasm_bindgen;
+
+use std::convert::{AsRef, TryFrom};
 use std::fmt;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io;
-use std::io::{Cursor, Read, Write};
-use std::path::Path;
+use std::io::{Read, Write};
 use std::iter::Iterator;
 
 // Expose decoder's public types, structs, and enums
-pub use decoder::{BmpError, BmpErrorKind, BmpResult};
+pub use adjust::ColorDistance;
+#[cfg(feature = "tokio")]
+pub use async_io::{from_async_reader, open_async};
+pub use blend::{BlendMode, Paint};
+pub use canvas::{Canvas, Layer};
+pub use color_space::{CieXyz, ColorSpace, ColorSpaceType, RenderingIntent};
+pub use compare::{diff, Diff};
+pub use decoder::{BmpError, BmpErrorKind, BmpResult, Decoder, ErrorDetails, RowDecoder};
+pub use draw::{Bezier, GradientDirection, LineCap, LineStyle, Stroke};
+pub use encoder::{Encoder, RowEncoder};
+pub use limits::Limits;
+pub use metadata::{open_metadata, read_metadata, BmpMetadata, SourceFormat};
+pub use palette::{BayerMatrix, IndexedImage, Palette};
+pub use path::{FillRule, Path};
+pub use pipeline::Pipeline;
+pub use progress::CancellationToken;
+pub use quantize::Quantizer;
+pub use random_access::BmpFile;
+pub use resize::Filter;
+pub use rgba::{ImageRgba, PixelRgba};
+pub use shared::SharedImage;
+pub use sprite::pack_sheet;
+pub use tiled::TiledImage;
+pub use transform::Anchor;
+pub use validate::{validate_bytes, validate_file, Finding, Severity, ValidationReport};
+pub use view::{SubImage, SubImageMut, Tiles};
+
+// Without the `log` feature, the diagnostics are type checked but never formatted
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
 
 /// Macro to generate a `Pixel` from `r`, `g` and `b` values.
 #[macro_export]
@@ -52,25 +150,141 @@ macro_rules! px {
     }
 }
 
-macro_rules! file_size {
-    ($bpp:expr, $width:expr, $height:expr) => {{
-        let header_size = 2 + 12 + 40;
-        // find row size in bytes, round up to 4 bytes (padding)
-        let row_size = (($bpp as f32 * $width as f32 + 31.0) / 32.0).floor() as u32 * 4;
-        (header_size as u32, $height as u32 * row_size)
-    }}
+/// Returns the number of bytes used by each row of pixels in an encoded image with `bpp` bits
+/// per pixel and the given `width`, including the padding up to a multiple of four bytes.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(12, bmp::row_stride(24, 3));
+/// assert_eq!(4, bmp::row_stride(1, 32));
+/// ```
+pub const fn row_stride(bpp: u16, width: u32) -> u64 {
+    (bpp as u64 * width as u64).div_ceil(32) * 4
+}
+
+/// Returns the number of bytes in an encoded image with `bpp` bits per pixel and the given `width`
+/// and `height`, as written by this crate: the headers, a color palette for images with 8 or less
+/// bits per pixel, and the rows of pixels.
+///
+/// # Example
+///
+/// ```
+/// const SIZE: u64 = bmp::encoded_size(24, 2, 2);
+/// let mut bytes = Vec::with_capacity(SIZE as usize);
+/// bmp::Image::new(2, 2).to_writer(&mut bytes).unwrap();
+/// assert_eq!(SIZE, bytes.len() as u64);
+/// ```
+pub const fn encoded_size(bpp: u16, width: u32, height: u32) -> u64 {
+    let palette_size = if bpp <= 8 { 4 << bpp } else { 0 };
+    let data_size = row_stride(bpp, width).saturating_mul(height as u64);
+    (HEADER_SIZE as u64 + palette_size).saturating_add(data_size)
+}
+
+// Returns the size of the pixel array of an encoded image, or an `InvalidDimensions` error if the
+// encoded image doesn't fit in the 32-bit sizes of the headers
+fn checked_data_size(bpp: u16, width: u32, height: u32) -> BmpResult<u32> {
+    if encoded_size(bpp, width, height) > u32::MAX as u64 {
+        return Err(BmpError::mismatch(
+            BmpErrorKind::InvalidDimensions,
+            "dimensions",
+            "an encoded image of at most 4 GiB",
+            format!("{}x{}", width, height),
+        ));
+    }
+    Ok((row_stride(bpp, width) * height as u64) as u32)
+}
+
+const METERS_PER_INCH: f64 = 0.0254;
+
+// Converts a resolution in pixels per meter, as stored in the header, to dots per inch
+fn ppm_to_dpi(ppm: i32) -> f32 {
+    (ppm as f64 * METERS_PER_INCH) as f32
+}
+
+fn dpi_to_ppm(dpi: f32) -> i32 {
+    (dpi as f64 / METERS_PER_INCH).round() as i32
 }
 
+// The size of the BMP header and the V3 DIB header
+const HEADER_SIZE: u32 = 2 + 12 + 40;
+
 /// Common color constants accessible by names.
 pub mod consts;
 
+/// Functions that create new images from noise and other patterns.
+pub mod generators;
+
+/// Functions that read and write ICO files, which contain several images of different sizes.
+pub mod ico;
+
+/// Functions that measure how similar two images are.
+pub mod metrics;
+
+/// A decoder that is pushed the bytes of images as they arrive, without a `Read` source.
+pub mod push;
+
+/// Strategies that generate images and pixels for property tests.
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+/// Functions that decode and encode images from JavaScript in the browser.
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+mod adjust;
+mod analyze;
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod blend;
+mod canvas;
+mod color_space;
+mod compare;
 mod decoder;
+mod draw;
 mod encoder;
+mod filter;
+#[cfg(feature = "image")]
+mod image_interop;
+mod limits;
+mod metadata;
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
+mod palette;
+mod path;
+mod pipeline;
+mod preview;
+mod progress;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod quantize;
+mod random_access;
+mod resize;
+mod rgba;
+#[cfg(feature = "rgb")]
+mod rgb_interop;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod shared;
+mod shuffle;
+mod sprite;
+#[cfg(feature = "ttf")]
+mod text;
+mod tiled;
+mod transform;
+mod validate;
+mod view;
 
 /// The pixel data used in the `Image`.
 ///
 /// It has three values for the `red`, `blue` and `green` color channels, respectively.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+///
+/// The layout of a `Pixel` is guaranteed to be three bytes in the order `r`, `g` and `b`, so a
+/// slice of pixels can be viewed as tightly packed RGB bytes without copying.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(C)]
 pub struct Pixel {
     pub r: u8,
     pub g: u8,
@@ -80,7 +294,31 @@ pub struct Pixel {
 impl Pixel {
     /// Creates a new `Pixel`.
     pub fn new(r: u8, g: u8, b: u8) -> Pixel {
-        Pixel { r: r, g: g, b: b }
+        Pixel { r, g, b }
+    }
+
+    /// Returns the pixels in `pixels` as tightly packed 8-bit `r`, `g` and `b` values, without
+    /// copying.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Pixel};
+    ///
+    /// let pixels = [consts::RED, consts::BLUE];
+    /// assert_eq!(&[255, 0, 0, 0, 0, 255], Pixel::slice_as_bytes(&pixels));
+    /// ```
+    pub fn slice_as_bytes(pixels: &[Pixel]) -> &[u8] {
+        // Safety: `Pixel` is `repr(C)` with three `u8` fields, so it has no padding and an
+        // alignment of one
+        unsafe { std::slice::from_raw_parts(pixels.as_ptr() as *const u8, pixels.len() * 3) }
+    }
+
+    /// Returns the pixels in `pixels` as mutable, tightly packed 8-bit `r`, `g` and `b` values,
+    /// without copying.
+    pub fn slice_as_bytes_mut(pixels: &mut [Pixel]) -> &mut [u8] {
+        // Safety: see `slice_as_bytes`, and every byte value is a valid channel
+        unsafe { std::slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, pixels.len() * 3) }
     }
 }
 
@@ -105,8 +343,66 @@ impl fmt::LowerHex for Pixel {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-enum BmpVersion {
+/// The order of the color channels in a buffer of packed pixel bytes.
+///
+/// The alpha channel is always fully opaque when written by the `Image`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChannelOrder {
+    Rgb,
+    Bgr,
+    Rgba,
+    Bgra,
+}
+
+impl ChannelOrder {
+    /// Returns the number of bytes used for each pixel.
+    pub fn channels(&self) -> usize {
+        match *self {
+            ChannelOrder::Rgb | ChannelOrder::Bgr => 3,
+            ChannelOrder::Rgba | ChannelOrder::Bgra => 4,
+        }
+    }
+}
+
+/// The policy used to map coordinates outside of an image to a pixel value when sampling.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Edge {
+    /// Uses the closest pixel on the edge of the image.
+    Clamp,
+    /// Repeats the image, so coordinates wrap around to the opposite edge.
+    Wrap,
+    /// Reflects the image across its edges, so -1 maps to 0 and `width` maps to `width - 1`.
+    Mirror,
+    /// Uses the given pixel value for every coordinate outside of the image.
+    Constant(Pixel),
+}
+
+impl Edge {
+    // Maps `i` to an index in `0..size`, or `None` for a constant
+    fn map(self, i: i64, size: u32) -> Option<u32> {
+        let size = size as i64;
+        let i = match self {
+            _ if i >= 0 && i < size => i,
+            Edge::Clamp => i.clamp(0, size - 1),
+            Edge::Wrap => i.rem_euclid(size),
+            Edge::Mirror => {
+                let i = i.rem_euclid(2 * size);
+                if i < size {
+                    i
+                } else {
+                    2 * size - 1 - i
+                }
+            }
+            Edge::Constant(_) => return None,
+        };
+        Some(i as u32)
+    }
+}
+
+/// The version of a BMP file, given by the size of its DIB header.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum BmpVersion {
     Two,
     Three,
     ThreeNT,
@@ -139,22 +435,31 @@ impl AsRef<str> for BmpVersion {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-enum CompressionType {
+/// The compression of the pixels in a BMP file.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum CompressionType {
     Uncompressed,
     Rle8bit,
     Rle4bit,
     // Only for BMP version 4
     BitfieldsEncoding,
+    Jpeg,
+    Png,
+    /// A compression type that is not known, with the value from the header.
+    Other(u32),
 }
 
 impl CompressionType {
     fn from_u32(val: u32) -> CompressionType {
         match val {
+            0 => CompressionType::Uncompressed,
             1 => CompressionType::Rle8bit,
             2 => CompressionType::Rle4bit,
             3 => CompressionType::BitfieldsEncoding,
-            _ => CompressionType::Uncompressed,
+            4 => CompressionType::Jpeg,
+            5 => CompressionType::Png,
+            other => CompressionType::Other(other),
         }
     }
 }
@@ -166,6 +471,9 @@ impl AsRef<str> for CompressionType {
             CompressionType::Rle4bit => "RLE 4-bit",
             CompressionType::BitfieldsEncoding => "Bitfields Encoding",
             CompressionType::Uncompressed => "Uncompressed",
+            CompressionType::Jpeg => "JPEG",
+            CompressionType::Png => "PNG",
+            CompressionType::Other(_) => "Unknown",
         }
     }
 }
@@ -206,11 +514,14 @@ struct BmpDibHeader {
 
 impl BmpDibHeader {
     fn new(width: i32, height: i32) -> BmpDibHeader {
-        let (_, pixel_array_size) = file_size!(24, width, height);
+        let stride = row_stride(24, width.unsigned_abs());
+        // The size saturates for images that are too large to be encoded
+        let pixel_array_size = u32::try_from(stride * height.unsigned_abs() as u64);
+        let pixel_array_size = pixel_array_size.unwrap_or(u32::MAX);
         BmpDibHeader {
             header_size: 40,
-            width: width,
-            height: height,
+            width,
+            height,
             num_planes: 1,
             bits_per_pixel: 24,
             compress_type: 0,
@@ -232,11 +543,26 @@ impl BmpDibHeader {
 /// where point (0, 0) is defined to be in the upper left corner of the image.
 ///
 /// Currently, only uncompressed BMP images are supported.
+///
+/// # Memory layout
+///
+/// The pixels are guaranteed to be stored in a single contiguous buffer of `Pixel`s, which are
+/// three bytes in the order `r`, `g` and `b`. The rows are stored from the bottom row to the top
+/// row, the same order as in an uncompressed BMP file, and are tightly packed without any
+/// padding. The byte offset of the pixel at `x` and `y` in `Image::as_bytes` is thus:
+///
+/// ```
+/// # let img = bmp::Image::new(7, 5);
+/// # let (x, y) = (3, 1);
+/// let offset = (img.get_height() - y - 1) as usize * img.row_stride_bytes() + x as usize * 3;
+/// # assert_eq!(&img.as_bytes()[offset..offset + 3], &[0, 0, 0]);
+/// ```
 #[derive(Clone, Eq, PartialEq)]
 pub struct Image {
     header: BmpHeader,
     dib_header: BmpDibHeader,
     color_palette: Option<Vec<Pixel>>,
+    source_format: Option<SourceFormat>,
     width: u32,
     height: u32,
     padding: u32,
@@ -253,20 +579,149 @@ impl Image {
     /// let mut img = bmp::Image::new(100, 80);
     /// ```
     pub fn new(width: u32, height: u32) -> Image {
-        let mut data = Vec::with_capacity((width * height) as usize);
-        for _ in 0..width * height {
-            data.push(px!(0, 0, 0));
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .expect("the number of pixels overflows usize");
+        let data = vec![px!(0, 0, 0); len];
+        Image::from_data(width, height, data)
+    }
+
+    /// Returns a new black BMP Image with the `width` and `height` specified, or a `BmpError` if
+    /// the image is empty or exceeds the default `Limits`, instead of aborting when the pixels
+    /// cannot be allocated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// assert!(bmp::Image::try_new(100, 80).is_ok());
+    /// assert!(bmp::Image::try_new(0, 80).is_err());
+    /// assert!(bmp::Image::try_new(u32::MAX, u32::MAX).is_err());
+    /// ```
+    pub fn try_new(width: u32, height: u32) -> BmpResult<Image> {
+        Image::try_new_with_limits(width, height, Limits::default())
+    }
+
+    /// Returns a new black BMP Image with the `width` and `height` specified, or a `BmpError` if
+    /// the image is empty or exceeds the given `limits`.
+    pub fn try_new_with_limits(width: u32, height: u32, limits: Limits) -> BmpResult<Image> {
+        limits.check(width, height)?;
+        Ok(Image::new(width, height))
+    }
+
+    /// Returns a new BMP Image from a buffer of tightly packed 8-bit `r`, `g` and `b` values,
+    /// stored in row-major order from top to bottom.
+    ///
+    /// Returns a `BmpError` if the length of `bytes` is not `width * height * 3`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let bytes = [255, 0, 0, 0, 255, 0];
+    /// let img = bmp::Image::from_raw_rgb(&bytes, 2, 1).unwrap();
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(0, 0));
+    /// assert_eq!(bmp::consts::LIME, img.get_pixel(1, 0));
+    /// ```
+    pub fn from_raw_rgb(bytes: &[u8], width: u32, height: u32) -> BmpResult<Image> {
+        Image::from_raw_channels(bytes, width, height, 3, |c| px!(c[0], c[1], c[2]))
+    }
+
+    /// Returns a new BMP Image from a buffer of tightly packed 8-bit `b`, `g` and `r` values,
+    /// stored in row-major order from top to bottom.
+    ///
+    /// Returns a `BmpError` if the length of `bytes` is not `width * height * 3`.
+    pub fn from_raw_bgr(bytes: &[u8], width: u32, height: u32) -> BmpResult<Image> {
+        Image::from_raw_channels(bytes, width, height, 3, |c| px!(c[2], c[1], c[0]))
+    }
+
+    /// Returns a new BMP Image from a buffer of tightly packed 8-bit `r`, `g`, `b` and `a` values,
+    /// stored in row-major order from top to bottom. The alpha channel is dropped.
+    ///
+    /// Returns a `BmpError` if the length of `bytes` is not `width * height * 4`.
+    pub fn from_raw_rgba(bytes: &[u8], width: u32, height: u32) -> BmpResult<Image> {
+        Image::from_raw_channels(bytes, width, height, 4, |c| px!(c[0], c[1], c[2]))
+    }
+
+    /// Returns a new BMP Image from a framebuffer of `0xAARRGGBB` values, stored in row-major
+    /// order from top to bottom, as used by `minifb` and `softbuffer`. The alpha channel is
+    /// dropped.
+    ///
+    /// Returns a `BmpError` if the length of `pixels` is not `width * height`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::from_argb8888(&[0xFF0000, 0x00FF00], 2, 1).unwrap();
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(0, 0));
+    /// assert_eq!(bmp::consts::LIME, img.get_pixel(1, 0));
+    /// ```
+    pub fn from_argb8888(pixels: &[u32], width: u32, height: u32) -> BmpResult<Image> {
+        if Some(pixels.len()) != (width as usize).checked_mul(height as usize) {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::WrongBufferSize,
+                "pixels.len()",
+                format!("{} for a {}x{} image", width as u64 * height as u64, width, height),
+                pixels.len(),
+            ));
+        }
+        let mut img = Image::new(width, height);
+        for (y, row) in pixels.chunks(width.max(1) as usize).enumerate() {
+            for (px, &argb) in img.row_mut(y as u32).iter_mut().zip(row) {
+                *px = px!(argb >> 16, argb >> 8, argb);
+            }
+        }
+        Ok(img)
+    }
+
+    fn from_raw_channels<F>(
+        bytes: &[u8],
+        width: u32,
+        height: u32,
+        channels: usize,
+        to_pixel: F,
+    ) -> BmpResult<Image>
+    where
+        F: Fn(&[u8]) -> Pixel,
+    {
+        let row_size = width as usize * channels;
+        let expected = row_size.checked_mul(height as usize);
+        if expected != Some(bytes.len()) {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::WrongBufferSize,
+                "bytes.len()",
+                format!(
+                    "{} for a {}x{} image with {} channels",
+                    row_size as u64 * height as u64,
+                    width,
+                    height,
+                    channels
+                ),
+                bytes.len(),
+            ));
         }
+        if bytes.is_empty() {
+            return Ok(Image::new(width, height));
+        }
+
+        // The rows are stored from bottom to top internally
+        let data = bytes
+            .chunks(row_size)
+            .rev()
+            .flat_map(|row| row.chunks(channels).map(&to_pixel))
+            .collect();
+        Ok(Image::from_data(width, height, data))
+    }
 
-        let (header_size, data_size) = file_size!(24, width, height);
+    fn from_data(width: u32, height: u32, data: Vec<Pixel>) -> Image {
+        let data_size = checked_data_size(24, width, height).unwrap_or(u32::MAX);
         Image {
-            header: BmpHeader::new(header_size, data_size),
+            header: BmpHeader::new(HEADER_SIZE, data_size),
             dib_header: BmpDibHeader::new(width as i32, height as i32),
             color_palette: None,
-            width: width,
-            height: height,
+            source_format: None,
+            width,
+            height,
             padding: width % 4,
-            data: data,
+            data,
         }
     }
 
@@ -282,6 +737,45 @@ impl Image {
         self.height
     }
 
+    /// Returns the color palette of the file the image was decoded from, or `None` if the file
+    /// didn't have a palette, or if the image was created in memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::open("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+    /// assert_eq!(6, img.color_palette().unwrap().len());
+    /// ```
+    #[inline]
+    pub fn color_palette(&self) -> Option<&[Pixel]> {
+        self.color_palette.as_deref()
+    }
+
+    /// Returns the horizontal and vertical resolution of the image in dots per inch, converted from
+    /// the pixels per meter stored in the header. New images have a resolution of 1000 pixels per
+    /// meter, which is 25.4 DPI.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.set_dpi(300.0, 150.0);
+    ///
+    /// let (x, y) = img.dpi();
+    /// assert_eq!((300.0, 150.0), (x.round(), y.round()));
+    /// ```
+    pub fn dpi(&self) -> (f32, f32) {
+        (ppm_to_dpi(self.dib_header.hres), ppm_to_dpi(self.dib_header.vres))
+    }
+
+    /// Sets the horizontal and vertical resolution of the image in dots per inch. The resolution
+    /// is stored as whole pixels per meter, so it is rounded, and it is written to the header when
+    /// the image is encoded.
+    pub fn set_dpi(&mut self, x: f32, y: f32) {
+        self.dib_header.hres = dpi_to_ppm(x);
+        self.dib_header.vres = dpi_to_ppm(y);
+    }
+
     /// Set the pixel value at the position of `width` and `height`.
     ///
     /// # Example
@@ -292,7 +786,8 @@ impl Image {
     /// ```
     #[inline]
     pub fn set_pixel(&mut self, x: u32, y: u32, val: Pixel) {
-        self.data[((self.height - y - 1) * self.width + x) as usize] = val;
+        let i = self.index(x, y);
+        self.data[i] = val;
     }
 
     /// Returns the pixel value at the position of `width` and `height`.
@@ -305,137 +800,966 @@ impl Image {
     /// ```
     #[inline]
     pub fn get_pixel(&self, x: u32, y: u32) -> Pixel {
-        self.data[((self.height - y - 1) * self.width + x) as usize]
+        self.data[self.index(x, y)]
     }
 
-    /// Returns a new `ImageIndex` that iterates over the image dimensions in top-bottom order.
+    // The index of the pixel at `x` and `y` in `data`, where the rows are stored from bottom to top
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside of the {}x{} image",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        (self.height - y - 1) as usize * self.width as usize + x as usize
+    }
+
+    /// Draws `paint` onto the pixel at the position of `x` and `y`, blending it with the current
+    /// value according to the `BlendMode` of the paint.
     ///
     /// # Example
     ///
     /// ```
-    /// let mut img = bmp::Image::new(100, 100);
-    /// for (x, y) in img.coordinates() {
-    ///     img.set_pixel(x, y, bmp::consts::BLUE);
-    /// }
+    /// use bmp::{consts, BlendMode, Image, Paint, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.paint_pixel(10, 10, Paint::new(consts::WHITE, BlendMode::Alpha(128)));
+    /// assert_eq!(Pixel::new(128, 128, 128), img.get_pixel(10, 10));
     /// ```
     #[inline]
-    pub fn coordinates(&self) -> ImageIndex {
-        ImageIndex::new(self.width as u32, self.height as u32)
+    pub fn paint_pixel<P: Into<Paint>>(&mut self, x: u32, y: u32, paint: P) {
+        let px = paint.into().apply(self.get_pixel(x, y));
+        self.set_pixel(x, y, px);
     }
 
-    /// Saves the `Image` instance to the path specified by `path`.
-    /// The function will overwrite the contents if a file already exists at the given path.
+    /// Returns the pixels of the row at `y`, from left to right, without copying.
     ///
-    /// The function returns the `io::Result` from the underlying writer.
+    /// Panics if `y` is outside of the image.
     ///
     /// # Example
     ///
     /// ```
-    /// use bmp::Image;
+    /// use bmp::{consts, Image, Pixel};
     ///
-    /// let mut img = Image::new(100, 100);
-    /// let _ = img.save("black.bmp").unwrap_or_else(|e| {
-    ///     panic!("Failed to save: {}", e)
-    /// });
+    /// let mut img = Image::new(3, 2);
+    /// img.row_mut(1)[2] = consts::RED;
+    /// assert_eq!(consts::RED, img.get_pixel(2, 1));
+    /// assert_eq!(&[0, 0, 0, 0, 0, 0, 255, 0, 0], Pixel::slice_as_bytes(img.row(1)));
     /// ```
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
-        let mut bmp_file = fs::File::create(path)?;
-        self.to_writer(&mut bmp_file)
+    #[inline]
+    pub fn row(&self, y: u32) -> &[Pixel] {
+        assert!(y < self.height, "{} is outside of the image", y);
+        // The rows are stored from bottom to top internally
+        let start = (self.height - y - 1) as usize * self.width as usize;
+        &self.data[start..start + self.width as usize]
     }
 
-    /// Writes the `Image` instance to the writer referenced by `destination`.
-    pub fn to_writer<W: Write>(&self, destination: &mut W) -> io::Result<()> {
-        let bmp_data = encoder::encode_image(self)?;
-        destination.write(&bmp_data)?;
-        Ok(())
+    /// Returns the mutable pixels of the row at `y`, from left to right, without copying.
+    ///
+    /// Panics if `y` is outside of the image.
+    #[inline]
+    pub fn row_mut(&mut self, y: u32) -> &mut [Pixel] {
+        assert!(y < self.height, "{} is outside of the image", y);
+        let start = (self.height - y - 1) as usize * self.width as usize;
+        &mut self.data[start..start + self.width as usize]
     }
-}
 
-impl fmt::Debug for Image {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        f.debug_struct("Image")
-            .field("header", &self.header)
-            .field("dib_header", &self.dib_header)
-            .field("color_palette", &self.color_palette)
-            .field("width", &self.width)
-            .field("height", &self.height)
-            .field("padding", &self.padding)
-            .finish()
+    /// Returns the number of bytes between the start of two rows of the image in memory. The rows
+    /// are tightly packed, so this is always three bytes per pixel of the `width`. See the
+    /// memory layout of `Image`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(5, 2);
+    /// assert_eq!(15, img.row_stride_bytes());
+    /// assert_eq!(img.as_bytes().len(), img.row_stride_bytes() * 2);
+    /// ```
+    #[inline]
+    pub fn row_stride_bytes(&self) -> usize {
+        self.width as usize * 3
     }
-}
 
-/// An `Iterator` returning the `x` and `y` coordinates of an image.
-///
-/// It supports iteration over an image in row-major order,
-/// starting from in the upper left corner of the image.
-#[derive(Clone, Copy)]
-pub struct ImageIndex {
-    width: u32,
-    height: u32,
-    x: u32,
-    y: u32,
-}
+    /// Returns the number of bytes that are added after every row to align the rows to four
+    /// bytes when the image is encoded with 24 bits per pixel, as in a BMP file or a Windows DIB.
+    /// The rows in memory have no padding, so this is needed to hand the pixels to APIs that
+    /// expect aligned rows.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(5, 2);
+    /// assert_eq!(1, img.padding_bytes());
+    /// assert_eq!(
+    ///     bmp::row_stride(24, 5),
+    ///     (img.row_stride_bytes() + img.padding_bytes() as usize) as u64
+    /// );
+    /// ```
+    #[inline]
+    pub fn padding_bytes(&self) -> u32 {
+        self.padding
+    }
 
-impl ImageIndex {
-    fn new(width: u32, height: u32) -> ImageIndex {
-        ImageIndex {
-            width,
-            height,
-            x: 0,
-            y: 0,
-        }
+    /// Returns the pixels of the whole image as tightly packed 8-bit `r`, `g` and `b` values,
+    /// without copying. The rows are in the bottom-up order they are stored in, see the memory
+    /// layout of `Image`, and `Image::as_raw_rgb` for a top-down copy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::open("test/rgbw.bmp").unwrap();
+    /// // The bottom row is blue and white
+    /// assert_eq!(&[0, 0, 255, 255, 255, 255], &img.as_bytes()[..6]);
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        Pixel::slice_as_bytes(&self.data)
     }
-}
 
-impl Iterator for ImageIndex {
-    type Item = (u32, u32);
+    /// Set the pixel value at the position of `x` and `y`, if the position is inside the image.
+    /// Returns whether the pixel was set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// assert!(img.try_set_pixel(10, 10, bmp::consts::RED));
+    /// assert!(!img.try_set_pixel(100, 10, bmp::consts::RED));
+    /// ```
+    #[inline]
+    pub fn try_set_pixel(&mut self, x: u32, y: u32, val: Pixel) -> bool {
+        let inside = x < self.width && y < self.height;
+        if inside {
+            self.set_pixel(x, y, val);
+        }
+        inside
+    }
 
-    fn next(&mut self) -> Option<(u32, u32)> {
-        if self.x < self.width && self.y < self.height {
-            let this = Some((self.x, self.y));
-            self.x += 1;
-            if self.x == self.width {
-                self.x = 0;
-                self.y += 1;
-            }
-            this
+    /// Returns the pixel value at the position of `x` and `y`, or `None` if the position is
+    /// outside of the image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(100, 80);
+    /// assert_eq!(Some(bmp::consts::BLACK), img.try_get_pixel(10, 10));
+    /// assert_eq!(None, img.try_get_pixel(10, 80));
+    /// ```
+    #[inline]
+    pub fn try_get_pixel(&self, x: u32, y: u32) -> Option<Pixel> {
+        if x < self.width && y < self.height {
+            Some(self.get_pixel(x, y))
         } else {
             None
         }
     }
-}
-
-/// Utility function to load an `Image` from the file specified by `path`.
-/// It uses the `from_reader` function internally to decode the `Image`.
-/// Returns a `BmpResult`, either containing an `Image` or a `BmpError`.
-///
-/// # Example
-///
-/// ```
-/// let img = bmp::open("test/rgbw.bmp").unwrap_or_else(|e| {
-///    panic!("Failed to open: {}", e);
-/// });
-/// ```
-pub fn open<P: AsRef<Path>>(path: P) -> BmpResult<Image> {
-    let mut f = fs::File::open(path)?;
-    from_reader(&mut f)
-}
-
-/// Attempts to construct a new `Image` from the given reader.
-/// Returns a `BmpResult`, either containing an `Image` or a `BmpError`.
-pub fn from_reader<R: Read>(source: &mut R) -> BmpResult<Image> {
-    let mut bytes = Vec::new();
-    source.read_to_end(&mut bytes)?;
-
-    let mut bmp_data = Cursor::new(bytes);
-    decoder::decode_image(&mut bmp_data)
-}
 
-#[cfg(test)]
+    /// Returns the pixel value at the position of `x` and `y`, where positions outside of the
+    /// image are mapped according to `edge`.
+    ///
+    /// Panics if the image is empty and `edge` is not `Edge::Constant`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Edge, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.set_pixel(0, 0, consts::RED);
+    /// assert_eq!(consts::RED, img.sample(-5, -5, Edge::Clamp));
+    /// assert_eq!(consts::RED, img.sample(100, 80, Edge::Wrap));
+    /// assert_eq!(consts::RED, img.sample(-1, 0, Edge::Mirror));
+    /// assert_eq!(consts::WHITE, img.sample(-1, 0, Edge::Constant(consts::WHITE)));
+    /// ```
+    pub fn sample(&self, x: i64, y: i64, edge: Edge) -> Pixel {
+        match (edge.map(x, self.width), edge.map(y, self.height)) {
+            (Some(x), Some(y)) => self.get_pixel(x, y),
+            _ => match edge {
+                Edge::Constant(val) => val,
+                _ => panic!("Cannot sample an empty image"),
+            },
+        }
+    }
+
+    /// Sets every pixel of the image to `val`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.fill(bmp::consts::WHITE);
+    /// assert_eq!(bmp::consts::WHITE, img.get_pixel(99, 79));
+    /// ```
+    pub fn fill(&mut self, val: Pixel) {
+        for px in &mut self.data {
+            *px = val;
+        }
+    }
+
+    /// Sets every pixel in the rectangle with the upper left corner at `x` and `y`, and the size
+    /// of `width` and `height`, to `val`. The parts of the rectangle that fall outside of the
+    /// image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.fill_rect(90, 70, 20, 20, bmp::consts::RED);
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(99, 79));
+    /// assert_eq!(bmp::consts::BLACK, img.get_pixel(89, 69));
+    /// ```
+    pub fn fill_rect<P: Into<Paint>>(&mut self, x: u32, y: u32, width: u32, height: u32, val: P) {
+        let val = val.into();
+        let x_end = x.saturating_add(width).min(self.width);
+        let y_end = y.saturating_add(height).min(self.height);
+        for y in y.min(y_end)..y_end {
+            for x in x.min(x_end)..x_end {
+                self.paint_pixel(x, y, val);
+            }
+        }
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the image dimensions in top-bottom order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 100);
+    /// for (x, y) in img.coordinates() {
+    ///     img.set_pixel(x, y, bmp::consts::BLUE);
+    /// }
+    /// ```
+    #[inline]
+    pub fn coordinates(&self) -> ImageIndex {
+        ImageIndex::new(self.width, self.height)
+    }
+
+    /// Returns a new `Image` where each pixel is the result of applying `f` to the pixels at the
+    /// same position in `self` and `other`.
+    ///
+    /// Returns a `BmpError` if the two images do not have the same dimensions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let mut a = Image::new(2, 2);
+    /// let mut b = Image::new(2, 2);
+    /// a.set_pixel(0, 0, consts::RED);
+    /// b.set_pixel(0, 0, consts::BLUE);
+    ///
+    /// let sum = a.zip_map(&b, |a, b| Pixel::new(a.r | b.r, a.g | b.g, a.b | b.b)).unwrap();
+    /// assert_eq!(consts::MAGENTA, sum.get_pixel(0, 0));
+    /// ```
+    pub fn zip_map<F>(&self, other: &Image, mut f: F) -> BmpResult<Image>
+    where
+        F: FnMut(Pixel, Pixel) -> Pixel,
+    {
+        self.check_same_dimensions(other)?;
+        let data = self
+            .data
+            .iter()
+            .zip(other.data.iter())
+            .map(|(&a, &b)| f(a, b))
+            .collect();
+        Ok(Image::from_data(self.width, self.height, data))
+    }
+
+    fn check_same_dimensions(&self, other: &Image) -> BmpResult<()> {
+        if self.width == other.width && self.height == other.height {
+            Ok(())
+        } else {
+            Err(BmpError::mismatch(
+                BmpErrorKind::MismatchedDimensions,
+                "dimensions",
+                format!("{}x{}", self.width, self.height),
+                format!("{}x{}", other.width, other.height),
+            ))
+        }
+    }
+
+    /// Returns a new `FileOrderRows` iterator over the rows of the image, from the bottom row to
+    /// the top row. This is the order the rows are stored in an uncompressed BMP file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(100, 80);
+    /// let (y, row) = img.rows_file_order().next().unwrap();
+    /// assert_eq!(79, y);
+    /// assert_eq!(100, row.len());
+    /// ```
+    pub fn rows_file_order(&self) -> FileOrderRows<'_> {
+        FileOrderRows {
+            rows: self.data.chunks(self.width.max(1) as usize),
+            y: self.height,
+        }
+    }
+
+    /// Returns a read-only `SubImage` view of the rectangle with the upper left corner at `x` and
+    /// `y`, and the size of `width` and `height`. No pixels are copied.
+    ///
+    /// Panics if the rectangle is not inside the image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.set_pixel(10, 20, bmp::consts::RED);
+    ///
+    /// let view = img.view(10, 20, 30, 30);
+    /// assert_eq!(bmp::consts::RED, view.get_pixel(0, 0));
+    /// ```
+    pub fn view(&self, x: u32, y: u32, width: u32, height: u32) -> SubImage<'_> {
+        self.assert_contains_rect(x, y, width, height);
+        SubImage::new(self, x, y, width, height)
+    }
+
+    /// Returns a mutable `SubImageMut` view of the rectangle with the upper left corner at `x`
+    /// and `y`, and the size of `width` and `height`. Edits to the view are written directly to
+    /// the image, and never reach outside of the rectangle.
+    ///
+    /// Panics if the rectangle is not inside the image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.view_mut(10, 20, 30, 30).fill(bmp::consts::RED);
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(39, 49));
+    /// assert_eq!(bmp::consts::BLACK, img.get_pixel(40, 50));
+    /// ```
+    pub fn view_mut(&mut self, x: u32, y: u32, width: u32, height: u32) -> SubImageMut<'_> {
+        self.assert_contains_rect(x, y, width, height);
+        SubImageMut::new(self, x, y, width, height)
+    }
+
+    fn contains_rect(&self, x: u32, y: u32, width: u32, height: u32) -> bool {
+        x as u64 + width as u64 <= self.width as u64
+            && y as u64 + height as u64 <= self.height as u64
+    }
+
+    fn assert_contains_rect(&self, x: u32, y: u32, width: u32, height: u32) {
+        assert!(
+            self.contains_rect(x, y, width, height),
+            "The {}x{} rectangle at ({}, {}) is outside of the {}x{} image",
+            width,
+            height,
+            x,
+            y,
+            self.width,
+            self.height
+        );
+    }
+
+    /// Returns a new `Tiles` iterator that covers the image with views of `tile_width` times
+    /// `tile_height` pixels, in row-major order. The tiles along the right and bottom edges are
+    /// clipped to the image.
+    ///
+    /// Panics if `tile_width` or `tile_height` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(100, 80);
+    /// let tiles: Vec<_> = img
+    ///     .tiles(64, 64)
+    ///     .map(|tile| (tile.position(), tile.get_width(), tile.get_height()))
+    ///     .collect();
+    /// assert_eq!(
+    ///     vec![((0, 0), 64, 64), ((64, 0), 36, 64), ((0, 64), 64, 16), ((64, 64), 36, 16)],
+    ///     tiles
+    /// );
+    /// ```
+    pub fn tiles(&self, tile_width: u32, tile_height: u32) -> Tiles<'_> {
+        Tiles::new(self, tile_width, tile_height)
+    }
+
+    /// Returns the pixel data as tightly packed 8-bit `r`, `g` and `b` values,
+    /// stored in row-major order from top to bottom.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(2, 1);
+    /// assert_eq!(vec![0; 6], img.as_raw_rgb());
+    /// ```
+    pub fn as_raw_rgb(&self) -> Vec<u8> {
+        self.as_raw(ChannelOrder::Rgb)
+    }
+
+    /// Returns the pixel data as tightly packed bytes in the channel order specified by `order`,
+    /// stored in row-major order from top to bottom.
+    pub fn as_raw(&self, order: ChannelOrder) -> Vec<u8> {
+        let channels = order.channels();
+        let mut bytes = Vec::with_capacity(self.data.len() * channels);
+        // The rows are stored from bottom to top internally
+        for row in self.data.chunks(self.width.max(1) as usize).rev() {
+            if order == ChannelOrder::Rgb {
+                bytes.extend_from_slice(Pixel::slice_as_bytes(row));
+                continue;
+            }
+            for px in row {
+                let values = match order {
+                    ChannelOrder::Rgb | ChannelOrder::Rgba => [px.r, px.g, px.b, 255],
+                    ChannelOrder::Bgr | ChannelOrder::Bgra => [px.b, px.g, px.r, 255],
+                };
+                bytes.extend_from_slice(&values[..channels]);
+            }
+        }
+        bytes
+    }
+
+    /// Consumes the `Image` and returns the pixel data as tightly packed bytes in the channel
+    /// order specified by `order`, stored in row-major order from top to bottom. The buffer of
+    /// the image is reused for `ChannelOrder::Rgb` and `ChannelOrder::Bgr`, while the orders with
+    /// an alpha channel need a larger buffer.
+    pub fn into_raw(self, order: ChannelOrder) -> Vec<u8> {
+        if order.channels() != 3 {
+            return self.as_raw(order);
+        }
+        let width = self.width.max(1) as usize;
+        let mut data = self.data;
+        // The rows are stored from bottom to top internally
+        let rows = data.len() / width;
+        for y in 0..rows / 2 {
+            let (top, bottom) = data.split_at_mut((rows - 1 - y) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+        if order == ChannelOrder::Bgr {
+            for px in &mut data {
+                std::mem::swap(&mut px.r, &mut px.b);
+            }
+        }
+        // Safety: see `Pixel::slice_as_bytes`. The length and capacity are both scaled by the
+        // size of a `Pixel`, so the allocation is freed with the layout it was made with.
+        let mut data = std::mem::ManuallyDrop::new(data);
+        let (len, capacity) = (data.len() * 3, data.capacity() * 3);
+        unsafe { Vec::from_raw_parts(data.as_mut_ptr() as *mut u8, len, capacity) }
+    }
+
+    /// Returns the pixel data as a framebuffer of fully opaque `0xAARRGGBB` values, stored in
+    /// row-major order from top to bottom, as used by `minifb` and `softbuffer`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(2, 1);
+    /// img.set_pixel(1, 0, bmp::consts::BLUE);
+    /// assert_eq!(vec![0xFF000000, 0xFF0000FF], img.to_argb8888());
+    /// ```
+    pub fn to_argb8888(&self) -> Vec<u32> {
+        let mut pixels = Vec::with_capacity(self.data.len());
+        // The rows are stored from bottom to top internally
+        for row in self.data.chunks(self.width.max(1) as usize).rev() {
+            pixels.extend(row.iter().map(|px| {
+                0xFF00_0000 | (px.r as u32) << 16 | (px.g as u32) << 8 | px.b as u32
+            }));
+        }
+        pixels
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the image dimensions in column-major order,
+    /// from top to bottom within each column, starting in the upper left corner.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(2, 2);
+    /// let coords: Vec<_> = img.coordinates_column_major().collect();
+    /// assert_eq!(coords, vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+    /// ```
+    #[inline]
+    pub fn coordinates_column_major(&self) -> ImageIndex {
+        ImageIndex::with_order(self.width, self.height, IndexOrder::ColumnMajor)
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the image dimensions in row-major order,
+    /// starting in the lower left corner and moving upwards row by row.
+    ///
+    /// This is the order the pixels are stored in an uncompressed BMP file.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(2, 2);
+    /// let coords: Vec<_> = img.coordinates_bottom_up().collect();
+    /// assert_eq!(coords, vec![(0, 1), (1, 1), (0, 0), (1, 0)]);
+    /// ```
+    #[inline]
+    pub fn coordinates_bottom_up(&self) -> ImageIndex {
+        ImageIndex::with_order(self.width, self.height, IndexOrder::BottomUp)
+    }
+
+    /// Returns a new `ImageIndex` that iterates over every `step_x`-th column of every `step_y`-th
+    /// row of the image in row-major order, starting in the upper left corner, to sample an image
+    /// sparsely.
+    ///
+    /// Panics if `step_x` or `step_y` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(5, 3);
+    /// let coords: Vec<_> = img.coordinates_step(2, 2).collect();
+    /// assert_eq!(coords, vec![(0, 0), (2, 0), (4, 0), (0, 2), (2, 2), (4, 2)]);
+    /// ```
+    pub fn coordinates_step(&self, step_x: u32, step_y: u32) -> ImageIndex {
+        assert!(step_x > 0 && step_y > 0, "The steps must be at least 1");
+        let columns = self.width.div_ceil(step_x);
+        let rows = self.height.div_ceil(step_y);
+        ImageIndex::grid(0, 0, columns, rows, step_x, step_y)
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the rectangle with the upper left corner at
+    /// `x` and `y`, and the size of `width` and `height`, in row-major order. The parts of the
+    /// rectangle that fall outside of the image are left out.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(100, 80);
+    /// let coords: Vec<_> = img.coordinates_in_rect(98, 10, 5, 2).collect();
+    /// assert_eq!(coords, vec![(98, 10), (99, 10), (98, 11), (99, 11)]);
+    /// ```
+    pub fn coordinates_in_rect(&self, x: u32, y: u32, width: u32, height: u32) -> ImageIndex {
+        let x_end = x.saturating_add(width).min(self.width);
+        let y_end = y.saturating_add(height).min(self.height);
+        let (x, y) = (x.min(x_end), y.min(y_end));
+        ImageIndex::grid(x, y, x_end - x, y_end - y, 1, 1)
+    }
+
+    /// Saves the `Image` instance to the path specified by `path`.
+    /// The function will overwrite the contents if a file already exists at the given path.
+    ///
+    /// The function returns the `io::Result` from the underlying writer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::Image;
+    ///
+    /// let mut img = Image::new(100, 100);
+    /// let path = std::env::temp_dir().join("black.bmp");
+    /// let _ = img.save(&path).unwrap_or_else(|e| {
+    ///     panic!("Failed to save: {}", e)
+    /// });
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> io::Result<()> {
+        let mut bmp_file = fs::File::create(path)?;
+        self.to_writer(&mut bmp_file)
+    }
+
+    /// Writes the `Image` instance to the writer referenced by `destination`.
+    pub fn to_writer<W: Write>(&self, destination: &mut W) -> io::Result<()> {
+        let bmp_data = encoder::encode_image(self)?;
+        destination.write_all(&bmp_data)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Image {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("Image")
+            .field("header", &self.header)
+            .field("dib_header", &self.dib_header)
+            .field("color_palette", &self.color_palette)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("padding", &self.padding)
+            .finish()
+    }
+}
+
+/// Hashes the dimensions and the pixels of the image, so images can be used as keys in a
+/// `HashMap` or a `HashSet`. The headers are left out, since equal images always have equal
+/// pixels, and hashing them would only slow down the hashing of small images. Use
+/// `Image::content_hash` for a hash that is stable between versions and platforms.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let img = bmp::open("test/rgbw.bmp").unwrap();
+/// let mut cache = HashMap::new();
+/// cache.insert(img.clone(), img.resize(4, 4));
+/// assert_eq!(4, cache[&img].get_width());
+/// ```
+impl Hash for Image {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+        Pixel::slice_as_bytes(&self.data).hash(state);
+    }
+}
+
+/// An `Iterator` returning the `x` and `y` coordinates of an image.
+///
+/// It supports iteration over an image in row-major order,
+/// starting from in the upper left corner of the image.
+/// Column-major and bottom-up orders are available through `Image::coordinates_column_major`
+/// and `Image::coordinates_bottom_up`.
+/// Sparse and region-limited coordinates are available through `Image::coordinates_step` and
+/// `Image::coordinates_in_rect`.
+#[derive(Clone, Copy)]
+pub struct ImageIndex {
+    // The number of columns and rows, and the position in them
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    remaining: u64,
+    order: IndexOrder,
+    // The coordinates of the first column and row, and the distance between them
+    left: u32,
+    top: u32,
+    step_x: u32,
+    step_y: u32,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum IndexOrder {
+    RowMajor,
+    ColumnMajor,
+    BottomUp,
+}
+
+impl ImageIndex {
+    fn new(width: u32, height: u32) -> ImageIndex {
+        ImageIndex::with_order(width, height, IndexOrder::RowMajor)
+    }
+
+    fn with_order(width: u32, height: u32, order: IndexOrder) -> ImageIndex {
+        let y = match order {
+            IndexOrder::BottomUp => height.saturating_sub(1),
+            _ => 0,
+        };
+        ImageIndex {
+            width,
+            height,
+            x: 0,
+            y,
+            remaining: width as u64 * height as u64,
+            order,
+            left: 0,
+            top: 0,
+            step_x: 1,
+            step_y: 1,
+        }
+    }
+
+    // Returns an index in row-major order over `columns` times `rows` coordinates, starting at
+    // `left` and `top` and `step_x` and `step_y` pixels apart
+    fn grid(
+        left: u32,
+        top: u32,
+        columns: u32,
+        rows: u32,
+        step_x: u32,
+        step_y: u32,
+    ) -> ImageIndex {
+        ImageIndex {
+            left,
+            top,
+            step_x,
+            step_y,
+            ..ImageIndex::new(columns, rows)
+        }
+    }
+}
+
+impl Iterator for ImageIndex {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<(u32, u32)> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let this = Some((
+            self.left + self.x * self.step_x,
+            self.top + self.y * self.step_y,
+        ));
+        match self.order {
+            IndexOrder::RowMajor => {
+                self.x += 1;
+                if self.x == self.width {
+                    self.x = 0;
+                    self.y += 1;
+                }
+            }
+            IndexOrder::ColumnMajor => {
+                self.y += 1;
+                if self.y == self.height {
+                    self.y = 0;
+                    self.x += 1;
+                }
+            }
+            IndexOrder::BottomUp => {
+                self.x += 1;
+                if self.x == self.width {
+                    self.x = 0;
+                    self.y = self.y.wrapping_sub(1);
+                }
+            }
+        }
+        this
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// An `Iterator` returning the rows of an image in the bottom-up order they are stored in an
+/// uncompressed BMP file.
+///
+/// Each row is returned together with its `y` coordinate, counted from the top of the image.
+#[derive(Clone, Debug)]
+pub struct FileOrderRows<'a> {
+    rows: std::slice::Chunks<'a, Pixel>,
+    y: u32,
+}
+
+impl<'a> Iterator for FileOrderRows<'a> {
+    type Item = (u32, &'a [Pixel]);
+
+    fn next(&mut self) -> Option<(u32, &'a [Pixel])> {
+        let row = self.rows.next()?;
+        self.y -= 1;
+        Some((self.y, row))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+/// An `Iterator` returning the `x` and `y` coordinates of every pixel of an image together with
+/// its value, in the same row-major order as `Image::coordinates`.
+///
+/// It is returned by iterating over a reference to an image.
+#[derive(Clone, Debug)]
+pub struct Pixels<'a> {
+    rows: std::iter::Rev<std::slice::Chunks<'a, Pixel>>,
+    row: std::iter::Enumerate<std::slice::Iter<'a, Pixel>>,
+    width: u32,
+    // The number of rows that are started, so the current row is at `y - 1`
+    y: u32,
+}
+
+impl<'a> Iterator for Pixels<'a> {
+    type Item = (u32, u32, Pixel);
+
+    fn next(&mut self) -> Option<(u32, u32, Pixel)> {
+        loop {
+            if let Some((x, &px)) = self.row.next() {
+                return Some((x as u32, self.y - 1, px));
+            }
+            self.row = self.rows.next()?.iter().enumerate();
+            self.y += 1;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.row.len() + self.rows.len() * self.width as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for Pixels<'a> {}
+
+/// An `Iterator` returning the `x` and `y` coordinates of every pixel of an image together with a
+/// mutable reference to it, in the same row-major order as `Image::coordinates`.
+///
+/// It is returned by iterating over a mutable reference to an image.
+#[derive(Debug)]
+pub struct PixelsMut<'a> {
+    rows: std::iter::Rev<std::slice::ChunksMut<'a, Pixel>>,
+    row: std::iter::Enumerate<std::slice::IterMut<'a, Pixel>>,
+    width: u32,
+    y: u32,
+}
+
+impl<'a> Iterator for PixelsMut<'a> {
+    type Item = (u32, u32, &'a mut Pixel);
+
+    fn next(&mut self) -> Option<(u32, u32, &'a mut Pixel)> {
+        loop {
+            if let Some((x, px)) = self.row.next() {
+                return Some((x as u32, self.y - 1, px));
+            }
+            self.row = self.rows.next()?.iter_mut().enumerate();
+            self.y += 1;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.row.len() + self.rows.len() * self.width as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for PixelsMut<'a> {}
+
+/// Iterates over the pixels of the image in row-major order, starting in the upper left corner,
+/// without looking up every coordinate with `get_pixel`.
+///
+/// # Example
+///
+/// ```
+/// let img = bmp::open("test/rgbw.bmp").unwrap();
+/// let red = (&img).into_iter().filter(|&(_, _, px)| px == bmp::consts::RED);
+/// assert_eq!(vec![(0, 0, bmp::consts::RED)], red.collect::<Vec<_>>());
+///
+/// for (x, y, px) in &img {
+///     assert_eq!(img.get_pixel(x, y), px);
+/// }
+/// ```
+impl<'a> IntoIterator for &'a Image {
+    type Item = (u32, u32, Pixel);
+    type IntoIter = Pixels<'a>;
+
+    fn into_iter(self) -> Pixels<'a> {
+        Pixels {
+            rows: self.data.chunks(self.width.max(1) as usize).rev(),
+            row: [].iter().enumerate(),
+            width: self.width,
+            y: 0,
+        }
+    }
+}
+
+/// Iterates over the pixels of the image in row-major order, starting in the upper left corner,
+/// with a mutable reference to every pixel so it can be changed in place.
+///
+/// # Example
+///
+/// ```
+/// let mut img = bmp::Image::new(4, 3);
+/// for (x, y, px) in &mut img {
+///     *px = bmp::Pixel::new(x as u8, y as u8, 0);
+/// }
+/// assert_eq!(bmp::Pixel::new(3, 2, 0), img.get_pixel(3, 2));
+/// ```
+impl<'a> IntoIterator for &'a mut Image {
+    type Item = (u32, u32, &'a mut Pixel);
+    type IntoIter = PixelsMut<'a>;
+
+    fn into_iter(self) -> PixelsMut<'a> {
+        PixelsMut {
+            rows: self.data.chunks_mut(self.width.max(1) as usize).rev(),
+            row: [].iter_mut().enumerate(),
+            width: self.width,
+            y: 0,
+        }
+    }
+}
+
+/// Utility function to load an `Image` from the file specified by `path`.
+/// It uses the `from_reader` function internally to decode the `Image`.
+/// Returns a `BmpResult`, either containing an `Image` or a `BmpError`.
+///
+/// # Example
+///
+/// ```
+/// let img = bmp::open("test/rgbw.bmp").unwrap_or_else(|e| {
+///    panic!("Failed to open: {}", e);
+/// });
+/// ```
+pub fn open<P: AsRef<std::path::Path>>(path: P) -> BmpResult<Image> {
+    let mut f = fs::File::open(path)?;
+    from_reader(&mut f)
+}
+
+/// Loads an image with a color palette from the file specified by `path`, as an `IndexedImage`
+/// where every pixel is a palette index instead of a color.
+/// Returns a `BmpResult`, either containing an `IndexedImage` or a `BmpError`.
+///
+/// # Example
+///
+/// ```
+/// let img = bmp::open_indexed("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+/// assert_eq!(bmp::consts::BLUE, img.get_pixel(0, 0));
+/// ```
+pub fn open_indexed<P: AsRef<std::path::Path>>(path: P) -> BmpResult<IndexedImage> {
+    let f = fs::File::open(path)?;
+    Decoder::new().decode_indexed(io::BufReader::new(f))
+}
+
+/// Attempts to construct a new `Image` from the given reader.
+/// The image is decoded while it is read, through a buffer, so the reader doesn't need to support
+/// seeking and the encoded image is never held in memory as a whole.
+/// Returns a `BmpResult`, either containing an `Image` or a `BmpError`.
+pub fn from_reader<R: Read>(source: &mut R) -> BmpResult<Image> {
+    decoder::decode_image(io::BufReader::new(source))
+}
+
+/// Attempts to decode an `Image` from the encoded BMP image in `bytes`.
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// let bytes = std::fs::read("test/rgbw.bmp").unwrap();
+/// let img = bmp::Image::try_from(&bytes[..]).unwrap();
+/// assert_eq!(bmp::consts::RED, img.get_pixel(0, 0));
+/// ```
+impl<'a> TryFrom<&'a [u8]> for Image {
+    type Error = BmpError;
+
+    fn try_from(bytes: &'a [u8]) -> BmpResult<Image> {
+        decoder::decode_image(bytes)
+    }
+}
+
+/// Attempts to decode an `Image` from the BMP file `file`, reading it from the current position.
+impl TryFrom<fs::File> for Image {
+    type Error = BmpError;
+
+    fn try_from(mut file: fs::File) -> BmpResult<Image> {
+        from_reader(&mut file)
+    }
+}
+
+/// Loads an `Image` from the file specified by `path`, by mapping the file into memory and
+/// decoding directly from the mapping instead of reading the whole file first.
+/// Returns a `BmpResult`, either containing an `Image` or a `BmpError`.
+///
+/// The file must not be modified by other processes while it is being decoded.
+///
+/// # Example
+///
+/// ```
+/// let img = bmp::open_mmap("test/rgbw.bmp").unwrap_or_else(|e| {
+///    panic!("Failed to open: {}", e);
+/// });
+/// ```
+#[cfg(feature = "mmap")]
+pub fn open_mmap<P: AsRef<std::path::Path>>(path: P) -> BmpResult<Image> {
+    let f = fs::File::open(path)?;
+    // Safety: the mapping is only read while decoding, and it is dropped before returning
+    let map = unsafe { memmap2::Mmap::map(&f)? };
+
+    decoder::decode_image(&map[..])
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::hash_map::DefaultHasher;
     use std::io::{Read, Seek, SeekFrom};
     use std::mem::size_of;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn size_of_bmp_header_is_54_bytes() {
@@ -446,6 +1770,12 @@ mod tests {
         assert_eq!(40, bmp_bip_header_size);
     }
 
+    // Returns a path in the temporary directory for a file written by a test, unique to the test
+    // process so concurrent test runs don't overwrite each other's files
+    pub(crate) fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("bmp-{}-{}", std::process::id(), name))
+    }
+
     fn verify_test_bmp_image(img: Image) {
         let header = img.header;
         assert_eq!(70, header.file_size);
@@ -484,13 +1814,424 @@ mod tests {
         verify_test_bmp_image(bmp_img);
     }
 
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn can_read_bmp_image_from_memory_mapped_file() {
+        let bmp_img = open_mmap("test/rgbw.bmp").unwrap();
+        verify_test_bmp_image(bmp_img);
+    }
+
+    #[test]
+    fn can_read_bmp_image_from_a_stream_in_small_chunks() {
+        let bytes = fs::read("test/rgbw.bmp").unwrap();
+        // Every read returns at most three bytes
+        let mut chunks = bytes.chunks(3).fold(Box::new(io::empty()) as Box<dyn Read>, |r, c| {
+            Box::new(r.chain(c))
+        });
+        verify_test_bmp_image(from_reader(&mut chunks).unwrap());
+
+        let mut overlapping = bytes.clone();
+        overlapping[10] = 20;
+        match from_reader(&mut &overlapping[..]) {
+            Err(BmpError {
+                kind: BmpErrorKind::UnsupportedHeader,
+                ..
+            }) => (/* Expected */),
+            _ => panic!("The pixel data cannot overlap the headers"),
+        }
+    }
+
+    #[test]
+    fn decoder_reuses_the_pixel_buffer_of_the_image() {
+        let bytes = fs::read("test/rgbw.bmp").unwrap();
+        let mut decoder = Decoder::new();
+        let mut img = Image::new(0, 0);
+
+        decoder.decode_into(&bytes[..], &mut img).unwrap();
+        let buffer = img.data.as_ptr();
+        decoder.decode_into(&bytes[..], &mut img).unwrap();
+        assert_eq!(buffer, img.data.as_ptr());
+        verify_test_bmp_image(img.clone());
+
+        assert!(decoder.decode_into(&bytes[..20], &mut img).is_err());
+        assert_eq!((0, 0), (img.get_width(), img.get_height()));
+        assert!(img.data.is_empty());
+    }
+
+    #[test]
+    fn decoding_a_region_matches_cropping() {
+        for name in &["24bpp-323x240", "8bpp-321x240", "1bpp-335x240"] {
+            let path = format!("test/bmptestsuite-0.9/valid/{}.bmp", name);
+            let full = open(&path).unwrap();
+
+            let f = fs::File::open(&path).unwrap();
+            let img = Decoder::new().with_region(7, 30, 101, 50).decode(f).unwrap();
+            let crop = full.crop(7, 30, 101, 50).unwrap();
+            assert_eq!(crop.as_raw_rgb(), img.as_raw_rgb());
+        }
+    }
+
+    #[test]
+    fn downscaling_keeps_every_nth_pixel() {
+        let path = "test/bmptestsuite-0.9/valid/24bpp-323x240.bmp";
+        let full = open(path).unwrap();
+
+        let f = fs::File::open(path).unwrap();
+        let img = Decoder::new().with_region(1, 2, 100, 99).with_downscale(3).decode(f).unwrap();
+        assert_eq!((34, 33), (img.get_width(), img.get_height()));
+        for (x, y) in img.coordinates() {
+            assert_eq!(full.get_pixel(1 + x * 3, 2 + y * 3), img.get_pixel(x, y));
+        }
+    }
+
+    #[test]
+    fn regions_outside_of_the_image_are_rejected() {
+        let f = fs::File::open("test/rgbw.bmp").unwrap();
+        match Decoder::new().with_region(1, 1, 2, 2).decode(f) {
+            Err(BmpError { kind: BmpErrorKind::OutOfBounds, .. }) => (/* Expected */),
+            other => panic!("Expected an OutOfBounds error, but was {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn strict_decoding_rejects_tolerated_inconsistencies() {
+        let mut bytes = fs::read("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+        let pixel_offset = bytes[10] as usize | (bytes[11] as usize) << 8;
+        // An index outside of the 6 colors in the palette
+        bytes[pixel_offset] = 200;
+
+        let img = Decoder::new().decode(&bytes[..]).unwrap();
+        assert_eq!(consts::BLACK, img.get_pixel(0, 0));
+        assert!(Decoder::new().with_strict(true).decode(&bytes[..]).is_err());
+
+        // The padding of the last row is missing
+        let mut bytes = Vec::new();
+        Image::new(1, 1).to_writer(&mut bytes).unwrap();
+        bytes.pop();
+        assert!(Decoder::new().decode(&bytes[..]).is_ok());
+        assert!(Decoder::new().with_strict(true).decode(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn decoder_limits_are_checked() {
+        let limits = Limits {
+            max_width: 1,
+            ..Limits::default()
+        };
+        let f = fs::File::open("test/rgbw.bmp").unwrap();
+        match Decoder::new().with_limits(limits).decode(f) {
+            Err(BmpError { kind: BmpErrorKind::InvalidDimensions, .. }) => (/* Expected */),
+            other => panic!("Expected an InvalidDimensions error, but was {:?}", other.err()),
+        }
+    }
+
+    // A reader that counts the bytes that are read from it
+    struct CountingReader<R> {
+        inner: R,
+        read: u64,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read += n as u64;
+            Ok(n)
+        }
+    }
+
+    impl<R: Seek> Seek for CountingReader<R> {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn seekable_decoders_only_read_the_rows_of_the_region() {
+        let path = "test/bmptestsuite-0.9/valid/24bpp-320x240.bmp";
+        let mut source = CountingReader {
+            inner: io::Cursor::new(fs::read(path).unwrap()),
+            read: 0,
+        };
+        let img = Decoder::new()
+            .with_region(10, 5, 100, 8)
+            .decode_seekable(&mut source)
+            .unwrap();
+
+        let expected = Decoder::new()
+            .with_region(10, 5, 100, 8)
+            .decode(fs::File::open(path).unwrap())
+            .unwrap();
+        assert_eq!(expected.data, img.data);
+        // The headers and the 8 rows of 960 bytes, up to the end of the upper row
+        assert!(source.read < 54 + 9 * 960, "{} bytes were read", source.read);
+    }
+
+    #[test]
+    fn decoder_can_drop_the_palette() {
+        let f = fs::File::open("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+        let img = Decoder::new().with_keep_indexed(false).decode(f).unwrap();
+        assert_eq!(None, img.color_palette());
+    }
+
+    #[test]
+    fn decoding_can_be_cancelled_between_rows() {
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        let mut decoder = Decoder::new()
+            .with_cancellation(token)
+            .with_progress(move |done, total| {
+                assert!(done < total, "The last row must not be reached");
+                canceller.cancel();
+            });
+
+        let f = fs::File::open("test/bmptestsuite-0.9/valid/24bpp-320x240.bmp").unwrap();
+        let mut img = Image::new(10, 10);
+        match decoder.decode_into(f, &mut img) {
+            Err(BmpError { kind: BmpErrorKind::Cancelled, .. }) => (/* Expected */),
+            other => panic!("Expected a Cancelled error, but was {:?}", other),
+        }
+        assert_eq!((0, 0), (img.get_width(), img.get_height()));
+        assert_eq!(11, BmpErrorKind::Cancelled.code());
+    }
+
+    #[test]
+    fn encoder_progress_reaches_the_encoded_size() {
+        let img = open("test/bmptestsuite-0.9/valid/8bpp-321x240.bmp").unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reports);
+        let bytes = Encoder::new()
+            .with_progress(move |done, total| sink.lock().unwrap().push((done, total)))
+            .encode(&img, Vec::new())
+            .unwrap();
+
+        assert_eq!(encoder::encode_image(&img).unwrap(), bytes);
+        let reports = reports.lock().unwrap();
+        assert_eq!(240, reports.len());
+        assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+        let size = bytes.len() as u64;
+        assert_eq!(Some(&(size, size)), reports.last());
+    }
+
+    #[test]
+    fn indexed_decoding_matches_expanded_decoding() {
+        for name in &["1bpp-321x240", "4bpp-323x240", "8bpp-322x240"] {
+            let path = format!("test/bmptestsuite-0.9/valid/{}.bmp", name);
+            let indexed = open_indexed(&path).unwrap();
+            let img = open(&path).unwrap();
+            assert_eq!(indexed.to_image().as_raw_rgb(), img.as_raw_rgb());
+        }
+
+        match open_indexed("test/rgbw.bmp") {
+            Err(BmpError {
+                kind: BmpErrorKind::UnsupportedBitsPerPixel,
+                ..
+            }) => (/* Expected */),
+            _ => panic!("A 24 bpp image has no palette"),
+        }
+    }
+
+    #[test]
+    fn decoding_allocates_the_pixels_of_the_image_once() {
+        let path = "test/bmptestsuite-0.9/valid/8bpp-321x240.bmp";
+        let mut img = open(path).unwrap();
+        assert_eq!(321 * 240, img.data.len());
+        assert!(img.data.capacity() >= img.data.len());
+
+        // Decoding the image again reuses the buffer without growing it
+        let (capacity, pixels) = (img.data.capacity(), img.data.as_ptr());
+        let f = fs::File::open(path).unwrap();
+        Decoder::new().decode_into(f, &mut img).unwrap();
+        assert_eq!(capacity, img.data.capacity());
+        assert_eq!(pixels, img.data.as_ptr());
+
+        // The encoder writes exactly the size it reserved up front
+        let bytes = encoder::encode_image(&img).unwrap();
+        assert_eq!(encoded_size(24, 321, 240), bytes.len() as u64);
+        assert!(bytes.capacity() >= bytes.len());
+    }
+
+    #[test]
+    fn bmp_errors_convert_to_io_errors() {
+        fn open_io(path: &str) -> io::Result<Image> {
+            Ok(open(path)?)
+        }
+
+        let err = open_io("test/does-not-exist.bmp").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = open_io("test/bmptestsuite-0.9/valid/32bpp-1x1.bmp").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        let err = io::Error::from(Image::from_raw_rgb(&[0; 2], 1, 1).unwrap_err());
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn can_convert_bytes_and_files_into_images() {
+        let bytes = fs::read("test/rgbw.bmp").unwrap();
+        verify_test_bmp_image(Image::try_from(&bytes[..]).unwrap());
+
+        let f = fs::File::open("test/rgbw.bmp").unwrap();
+        verify_test_bmp_image(Image::try_from(f).unwrap());
+        assert!(Image::try_from(&bytes[..30]).is_err());
+    }
+
+    #[test]
+    fn io_errors_are_the_source_of_the_error() {
+        use std::error::Error;
+
+        let err = BmpError::from(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert_eq!("I/O error: missing", err.to_string());
+        assert_eq!("missing", err.source().unwrap().to_string());
+        assert!(BmpError::new(BmpErrorKind::OutOfBounds, "").source().is_none());
+
+        let boxed: Box<dyn Error + Send + Sync> = Box::new(err);
+        assert!(std::thread::spawn(move || boxed.source().is_some()).join().unwrap());
+    }
+
+    #[test]
+    fn errors_have_structured_details() {
+        let img = Image::new(2, 2);
+        let err = img.crop(1, 1, 2, 2).unwrap_err();
+        assert_eq!(Some("rectangle"), err.details.field);
+        assert_eq!(Some("2x2 at (1, 1)"), err.details.found.as_deref());
+        assert_eq!(
+            "Out of bounds: rectangle: expected within the 2x2 image, found 2x2 at (1, 1)",
+            err.to_string()
+        );
+
+        let err = BmpError::new(BmpErrorKind::OutOfBounds, "The entries are truncated");
+        assert_eq!(None, err.details.field);
+        assert_eq!("Out of bounds: The entries are truncated", err.to_string());
+    }
+
+    #[test]
+    fn huge_dimensions_in_the_header_are_rejected() {
+        let mut bytes = Vec::new();
+        Image::new(1, 1).to_writer(&mut bytes).unwrap();
+        bytes[18..22].copy_from_slice(&65535i32.to_le_bytes());
+        bytes[22..26].copy_from_slice(&65535i32.to_le_bytes());
+
+        match Image::try_from(&bytes[..]) {
+            Err(BmpError { kind: BmpErrorKind::InvalidDimensions, .. }) => (/* Expected */),
+            other => panic!("Expected an InvalidDimensions error, but was {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn huge_dimensions_are_rejected_before_the_pixels_are_allocated() {
+        // A 1 bpp image with a width near 2^31 fits the data size, but not the pixel buffer
+        let mut bytes = fs::read("test/bmptestsuite-0.9/valid/1bpp-1x1.bmp").unwrap();
+        bytes[18..22].copy_from_slice(&i32::MAX.to_le_bytes());
+
+        match Decoder::new().decode(&bytes[..]) {
+            Err(BmpError { kind: BmpErrorKind::InvalidDimensions, .. }) => (/* Expected */),
+            other => panic!("Expected an InvalidDimensions error, but was {:?}", other.err()),
+        }
+        assert!(Decoder::new().decode_indexed(&bytes[..]).is_err());
+        assert!(RowDecoder::new(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn color_tables_of_24_bpp_images_are_skipped() {
+        let expected = open("test/bmpsuite-2.5/g/rgb24.bmp").unwrap();
+        for path in &[
+            "test/bmpsuite-2.5/g/rgb24pal.bmp",
+            "test/bmpsuite-2.5/q/rgb24largepal.bmp",
+        ] {
+            // The headers differ in the offset of the pixels
+            assert_eq!(expected.data, open(path).unwrap().data);
+            let f = fs::File::open(path).unwrap();
+            assert_eq!(expected.data, Decoder::new().decode_seekable(f).unwrap().data);
+
+            match open_indexed(path) {
+                Err(BmpError { kind: BmpErrorKind::UnsupportedBitsPerPixel, .. }) => (),
+                other => panic!("Expected no palette, but was {:?}", other.map(|_| ())),
+            }
+
+            let mut rows = RowDecoder::new(fs::File::open(path).unwrap()).unwrap();
+            while let Some((y, row)) = rows.next_row().unwrap() {
+                assert_eq!(expected.row(y), row);
+            }
+
+            let mut file = BmpFile::open(path).unwrap();
+            for (x, y) in expected.coordinates() {
+                assert_eq!(expected.get_pixel(x, y), file.get_pixel(x, y).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_size_saturates() {
+        assert_eq!(u64::MAX, encoded_size(24, u32::MAX, u32::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "(3, 0) is outside of the 3x2 image")]
+    fn get_pixel_does_not_wrap_around_to_the_next_row() {
+        Image::new(3, 2).get_pixel(3, 0);
+    }
+
+    #[test]
+    fn dpi_is_preserved_when_encoding_and_decoding() {
+        let mut img = Image::new(2, 2);
+        assert_eq!(25.4, img.dpi().0);
+        img.set_dpi(72.0, 96.0);
+
+        let mut bytes = Vec::new();
+        img.to_writer(&mut bytes).unwrap();
+        // 72 and 96 DPI are stored as 2835 and 3780 pixels per meter
+        assert_eq!(&2835i32.to_le_bytes(), &bytes[38..42]);
+        assert_eq!(&3780i32.to_le_bytes(), &bytes[42..46]);
+        assert_eq!(img.dpi(), Image::try_from(&bytes[..]).unwrap().dpi());
+    }
+
+    #[test]
+    fn images_with_unknown_compression_types_are_rejected() {
+        let mut bytes = Vec::new();
+        Image::new(2, 2).to_writer(&mut bytes).unwrap();
+        // These were once decoded as if the pixels were uncompressed
+        for &(compression, found) in &[(4u32, "JPEG"), (5, "PNG"), (7, "Unknown")] {
+            bytes[30..34].copy_from_slice(&compression.to_le_bytes());
+            let err = Image::try_from(&bytes[..]).unwrap_err();
+            assert_eq!(&BmpErrorKind::UnsupportedCompressionType, err.kind());
+            assert_eq!(Some(found), err.details.found.as_deref());
+        }
+    }
+
+    #[test]
+    fn error_codes_are_stable() {
+        use BmpErrorKind::*;
+
+        let kinds = [
+            BmpIoError(io::Error::from(io::ErrorKind::Other)),
+            WrongMagicNumbers,
+            UnsupportedBitsPerPixel,
+            UnsupportedCompressionType,
+            UnsupportedBmpVersion,
+            UnsupportedHeader,
+            WrongBufferSize,
+            MismatchedDimensions,
+            OutOfBounds,
+            InvalidDimensions,
+        ];
+        let codes: Vec<u32> = kinds.iter().map(BmpErrorKind::code).collect();
+        assert_eq!((1..=10).collect::<Vec<_>>(), codes);
+    }
+
+    #[test]
+    fn error_kinds_compare_without_the_io_error() {
+        let not_found = BmpError::from(io::Error::from(io::ErrorKind::NotFound));
+        let eof = BmpError::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+        assert_eq!(not_found.kind(), eof.kind());
+        assert_ne!(not_found.kind(), &BmpErrorKind::OutOfBounds);
+    }
+
     #[test]
     fn can_read_image_data() {
         let mut f = fs::File::open("test/rgbw.bmp").unwrap();
         f.seek(SeekFrom::Start(54)).unwrap();
 
         let mut px = [0; 3];
-        f.read(&mut px).unwrap();
+        f.read_exact(&mut px).unwrap();
 
         assert_eq!(
             Pixel {
@@ -519,8 +2260,10 @@ mod tests {
         assert_eq!(img.data.len(), 1);
         assert_eq!(img.get_pixel(0, 0), consts::BLACK);
 
-        let _ = img.save("test/1bb-1x1.bmp");
-        let img = open("test/1bb-1x1.bmp").unwrap();
+        let path = temp_path("1bb-1x1.bmp");
+        img.save(&path).unwrap();
+        let img = open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
         assert_eq!(img.data.len(), 1);
         assert_eq!(img.get_pixel(0, 0), consts::BLACK);
     }
@@ -531,8 +2274,10 @@ mod tests {
         assert_eq!(img.data.len(), 1);
         assert_eq!(img.get_pixel(0, 0), consts::BLUE);
 
-        let _ = img.save("test/4bb-1x1.bmp");
-        let img = open("test/4bb-1x1.bmp").unwrap();
+        let path = temp_path("4bb-1x1.bmp");
+        img.save(&path).unwrap();
+        let img = open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
         assert_eq!(img.data.len(), 1);
         assert_eq!(img.get_pixel(0, 0), consts::BLUE);
     }
@@ -543,8 +2288,10 @@ mod tests {
         assert_eq!(img.data.len(), 1);
         assert_eq!(img.get_pixel(0, 0), consts::BLUE);
 
-        let _ = img.save("test/8bb-1x1.bmp");
-        let img = open("test/8bb-1x1.bmp").unwrap();
+        let path = temp_path("8bb-1x1.bmp");
+        img.save(&path).unwrap();
+        let img = open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
         assert_eq!(img.data.len(), 1);
         assert_eq!(img.get_pixel(0, 0), consts::BLUE);
     }
@@ -552,19 +2299,28 @@ mod tests {
     #[test]
     fn read_write_bmp_v3_image() {
         let bmp_img = open("test/bmptestsuite-0.9/valid/24bpp-320x240.bmp").unwrap();
-        bmp_img.save("test/24bpp-320x240.bmp").unwrap();
+        let path = temp_path("24bpp-320x240.bmp");
+        bmp_img.save(&path).unwrap();
+        assert_eq!(bmp_img.data, open(&path).unwrap().data);
+        fs::remove_file(&path).unwrap();
     }
 
     #[test]
     fn read_write_bmp_v4_image() {
         let bmp_img = open("test/bmpsuite-2.5/g/pal8v4.bmp").unwrap();
-        bmp_img.save("test/pal8v4-test.bmp").unwrap();
+        let path = temp_path("pal8v4-test.bmp");
+        bmp_img.save(&path).unwrap();
+        assert_eq!(bmp_img.data, open(&path).unwrap().data);
+        fs::remove_file(&path).unwrap();
     }
 
     #[test]
     fn read_write_bmp_v5_image() {
         let bmp_img = open("test/bmpsuite-2.5/g/pal8v5.bmp").unwrap();
-        bmp_img.save("test/pal8v5-test.bmp").unwrap();
+        let path = temp_path("pal8v5-test.bmp");
+        bmp_img.save(&path).unwrap();
+        assert_eq!(bmp_img.data, open(&path).unwrap().data);
+        fs::remove_file(&path).unwrap();
     }
 
     #[test]
@@ -601,9 +2357,11 @@ mod tests {
         bmp.set_pixel(1, 0, consts::LIME);
         bmp.set_pixel(0, 1, consts::BLUE);
         bmp.set_pixel(1, 1, consts::WHITE);
-        let _ = bmp.save("test/rgbw_test.bmp");
+        let path = temp_path("rgbw_test.bmp");
+        bmp.save(&path).unwrap();
 
-        let bmp_img = open("test/rgbw_test.bmp").unwrap();
+        let bmp_img = open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
         assert_eq!(bmp_img.get_pixel(0, 0), consts::RED);
         assert_eq!(bmp_img.get_pixel(1, 0), consts::LIME);
         assert_eq!(bmp_img.get_pixel(0, 1), consts::BLUE);
@@ -634,6 +2392,315 @@ mod tests {
         assert_eq!(coords.next(), Some((1, 2)));
     }
 
+    #[test]
+    fn can_create_image_from_raw_bytes() {
+        let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let bgr = [0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255];
+        let rgba = [255, 0, 0, 9, 0, 255, 0, 9, 0, 0, 255, 9, 255, 255, 255, 9];
+
+        for img in &[
+            Image::from_raw_rgb(&rgb, 2, 2).unwrap(),
+            Image::from_raw_bgr(&bgr, 2, 2).unwrap(),
+            Image::from_raw_rgba(&rgba, 2, 2).unwrap(),
+        ] {
+            assert_eq!(img.get_pixel(0, 0), consts::RED);
+            assert_eq!(img.get_pixel(1, 0), consts::LIME);
+            assert_eq!(img.get_pixel(0, 1), consts::BLUE);
+            assert_eq!(img.get_pixel(1, 1), consts::WHITE);
+        }
+    }
+
+    #[test]
+    fn error_when_creating_image_from_raw_bytes_of_wrong_size() {
+        match Image::from_raw_rgb(&[0; 11], 2, 2) {
+            Err(BmpError { kind: BmpErrorKind::WrongBufferSize, .. }) => (/* Expected */),
+            _ => panic!("The buffer is too short"),
+        }
+        assert_eq!(Image::from_raw_rgba(&[], 0, 5).unwrap().get_height(), 5);
+    }
+
+    #[test]
+    fn raw_bytes_are_in_top_bottom_order() {
+        let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let img = Image::from_raw_rgb(&rgb, 2, 2).unwrap();
+
+        assert_eq!(img.as_raw_rgb(), rgb.to_vec());
+        assert_eq!(&img.as_raw(ChannelOrder::Bgra)[..8], &[0, 0, 255, 255, 0, 255, 0, 255]);
+        for &order in &[ChannelOrder::Rgb, ChannelOrder::Bgr, ChannelOrder::Rgba] {
+            assert_eq!(img.clone().into_raw(order), img.as_raw(order));
+        }
+
+        // The bytes are the buffer of the image
+        let rgb: Vec<u8> = (0..15).collect();
+        let img = Image::from_raw_rgb(&rgb, 1, 5).unwrap();
+        let buffer = img.data.as_ptr() as *const u8;
+        let raw = img.into_raw(ChannelOrder::Rgb);
+        assert_eq!((raw.as_ptr(), raw), (buffer, rgb));
+    }
+
+    #[test]
+    fn framebuffers_are_in_top_bottom_order() {
+        let argb = [0xFF0000, 0x00FF00, 0x0000FF, 0x12FFFFFF];
+        let img = Image::from_argb8888(&argb, 2, 2).unwrap();
+
+        assert_eq!(img.get_pixel(0, 1), consts::BLUE);
+        assert_eq!(img.to_argb8888(), vec![0xFFFF0000, 0xFF00FF00, 0xFF0000FF, 0xFFFFFFFF]);
+        match Image::from_argb8888(&argb, 3, 2) {
+            Err(BmpError { kind: BmpErrorKind::WrongBufferSize, .. }) => (/* Expected */),
+            _ => panic!("The framebuffer is too short"),
+        }
+    }
+
+    #[test]
+    fn zip_map_combines_pixels_at_the_same_position() {
+        let a = Image::from_raw_rgb(&[10, 20, 30, 40, 50, 60], 1, 2).unwrap();
+        let b = Image::from_raw_rgb(&[1, 2, 3, 4, 5, 6], 1, 2).unwrap();
+
+        let diff = a.zip_map(&b, |a, b| px!(a.r - b.r, a.g - b.g, a.b - b.b)).unwrap();
+        assert_eq!(diff.get_pixel(0, 0), px!(9, 18, 27));
+        assert_eq!(diff.get_pixel(0, 1), px!(36, 45, 54));
+
+        match a.zip_map(&Image::new(2, 1), |a, _| a) {
+            Err(BmpError { kind: BmpErrorKind::MismatchedDimensions, .. }) => (/* Expected */),
+            _ => panic!("The images have different dimensions"),
+        }
+    }
+
+    #[test]
+    fn fill_rect_is_clipped_to_the_image() {
+        let mut img = Image::new(3, 3);
+        img.fill_rect(1, 2, 5, 5, consts::WHITE);
+        img.fill_rect(5, 0, 1, 1, consts::RED);
+
+        let white: Vec<_> = img
+            .coordinates()
+            .filter(|&(x, y)| img.get_pixel(x, y) == consts::WHITE)
+            .collect();
+        assert_eq!(white, vec![(1, 2), (2, 2)]);
+
+        img.fill(consts::RED);
+        assert!(img.coordinates().all(|(x, y)| img.get_pixel(x, y) == consts::RED));
+    }
+
+    #[test]
+    fn alternative_iteration_orders_visit_every_coordinate_once() {
+        let img = Image::new(3, 2);
+        let mut coords = img.coordinates_column_major();
+        assert_eq!(coords.size_hint(), (6, Some(6)));
+        assert_eq!(coords.next(), Some((0, 0)));
+        assert_eq!(coords.next(), Some((0, 1)));
+        assert_eq!(coords.next(), Some((1, 0)));
+
+        let mut bottom_up: Vec<_> = img.coordinates_bottom_up().collect();
+        assert_eq!(bottom_up[..4], [(0, 1), (1, 1), (2, 1), (0, 0)]);
+        bottom_up.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(bottom_up, img.coordinates().collect::<Vec<_>>());
+
+        assert_eq!(Image::new(0, 3).coordinates_bottom_up().next(), None);
+    }
+
+    #[test]
+    fn tiles_cover_the_image_and_are_clipped_at_the_edges() {
+        let mut img = Image::new(5, 3);
+        img.set_pixel(4, 2, consts::RED);
+
+        let tiles: Vec<_> = img.tiles(2, 2).collect();
+        let rects: Vec<_> = tiles
+            .iter()
+            .map(|t| (t.position(), t.get_width(), t.get_height()))
+            .collect();
+        assert_eq!(
+            rects,
+            vec![
+                ((0, 0), 2, 2),
+                ((2, 0), 2, 2),
+                ((4, 0), 1, 2),
+                ((0, 2), 2, 1),
+                ((2, 2), 2, 1),
+                ((4, 2), 1, 1),
+            ]
+        );
+        assert_eq!(tiles[5].get_pixel(0, 0), consts::RED);
+    }
+
+    #[test]
+    fn view_reads_from_the_parent_image() {
+        let img = open("test/rgbw.bmp").unwrap();
+        let view = img.view(1, 0, 1, 2);
+        assert_eq!(view.get_pixel(0, 0), consts::LIME);
+        assert_eq!(view.get_pixel(0, 1), consts::WHITE);
+        assert_eq!(view.coordinates().count(), 2);
+
+        let crop = view.to_image();
+        assert_eq!((crop.get_width(), crop.get_height()), (1, 2));
+        assert_eq!(crop.get_pixel(0, 0), consts::LIME);
+        assert_eq!(crop.get_pixel(0, 1), consts::WHITE);
+    }
+
+    #[test]
+    #[should_panic]
+    fn view_panics_outside_of_the_image() {
+        Image::new(2, 2).view(1, 1, 2, 1);
+    }
+
+    #[test]
+    fn view_mut_writes_to_the_parent_image() {
+        let mut img = Image::new(4, 4);
+        {
+            let mut view = img.view_mut(1, 1, 2, 3);
+            view.fill(consts::WHITE);
+            view.set_pixel(1, 2, consts::RED);
+            assert_eq!(view.get_pixel(0, 0), consts::WHITE);
+
+            let (mut top, mut bottom) = view.split_at_row(1);
+            assert_eq!((top.get_height(), bottom.get_height()), (1, 2));
+            assert_eq!(bottom.position(), (1, 2));
+            top.fill(consts::BLUE);
+            bottom.fill_rect(0, 0, 1, 1, consts::LIME);
+        }
+
+        let expected = ["....", ".BB.", ".GW.", ".WR."];
+        for (x, y) in img.coordinates() {
+            let expected = match expected[y as usize].as_bytes()[x as usize] {
+                b'B' => consts::BLUE,
+                b'G' => consts::LIME,
+                b'W' => consts::WHITE,
+                b'R' => consts::RED,
+                _ => consts::BLACK,
+            };
+            assert_eq!(img.get_pixel(x, y), expected, "at ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn sample_maps_coordinates_outside_of_the_image() {
+        let img = Image::from_raw_rgb(&[0, 0, 0, 1, 1, 1, 2, 2, 2], 3, 1).unwrap();
+        let sample_row =
+            |edge| -> Vec<u8> { (-4..7).map(|x| img.sample(x, 0, edge).r).collect() };
+
+        assert_eq!(sample_row(Edge::Clamp), [0, 0, 0, 0, 0, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(sample_row(Edge::Wrap), [2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0]);
+        assert_eq!(sample_row(Edge::Mirror), [2, 2, 1, 0, 0, 1, 2, 2, 1, 0, 0]);
+        assert_eq!(
+            sample_row(Edge::Constant(px!(9, 9, 9))),
+            [9, 9, 9, 9, 0, 1, 2, 9, 9, 9, 9]
+        );
+        assert_eq!(img.sample(1, -1, Edge::Mirror), px!(1, 1, 1));
+    }
+
+    #[test]
+    fn rows_file_order_starts_with_the_bottom_row() {
+        let img = open("test/rgbw.bmp").unwrap();
+        let rows: Vec<_> = img.rows_file_order().collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, &[consts::BLUE, consts::WHITE][..]),
+                (0, &[consts::RED, consts::LIME][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn iterating_over_an_image_follows_the_coordinates() {
+        let img = open("test/rgbw.bmp").unwrap();
+        let pixels: Vec<_> = (&img).into_iter().collect();
+        let expected: Vec<_> = img
+            .coordinates()
+            .map(|(x, y)| (x, y, img.get_pixel(x, y)))
+            .collect();
+        assert_eq!(expected, pixels);
+        assert_eq!(consts::LIME, pixels[1].2);
+
+        let mut iter = (&img).into_iter();
+        iter.next();
+        assert_eq!(3, iter.len());
+        assert_eq!(0, (&Image::new(0, 5)).into_iter().count());
+        assert_eq!(0, (&Image::new(5, 0)).into_iter().count());
+    }
+
+    #[test]
+    fn pixels_can_be_changed_while_iterating() {
+        let mut img = Image::new(3, 2);
+        for (x, y, px) in &mut img {
+            if y == 0 {
+                *px = px!(x as u8, 0, 0);
+            }
+        }
+        assert_eq!(px!(2, 0, 0), img.get_pixel(2, 0));
+        assert_eq!(px!(0, 0, 0), img.get_pixel(2, 1));
+        assert_eq!(6, (&mut img).into_iter().len());
+    }
+
+    fn hash_of(img: &Image) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        img.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equal_images_have_equal_hashes() {
+        let img = open("test/rgbw.bmp").unwrap();
+        assert_eq!(hash_of(&img), hash_of(&img.clone()));
+
+        let mut changed = img.clone();
+        changed.set_pixel(1, 1, consts::BLACK);
+        assert_ne!(hash_of(&img), hash_of(&changed));
+
+        // The same pixels in other dimensions are different images
+        assert_ne!(hash_of(&Image::new(2, 3)), hash_of(&Image::new(3, 2)));
+    }
+
+    #[test]
+    fn sparse_and_region_coordinates_follow_the_full_iterator() {
+        let img = Image::new(7, 5);
+        let stepped: Vec<_> = img
+            .coordinates()
+            .filter(|&(x, y)| x % 3 == 0 && y % 2 == 0)
+            .collect();
+        assert_eq!(stepped, img.coordinates_step(3, 2).collect::<Vec<_>>());
+        assert_eq!(9, img.coordinates_step(3, 2).count());
+        assert_eq!(35, img.coordinates_step(1, 1).count());
+
+        let rect: Vec<_> = img
+            .coordinates()
+            .filter(|&(x, y)| (2..5).contains(&x) && (1..3).contains(&y))
+            .collect();
+        assert_eq!(rect, img.coordinates_in_rect(2, 1, 3, 2).collect::<Vec<_>>());
+        assert_eq!(0, img.coordinates_in_rect(7, 0, 3, 3).count());
+        assert_eq!(0, img.coordinates_in_rect(2, 1, 0, 4).count());
+        assert_eq!(
+            vec![(6, 4)],
+            img.coordinates_in_rect(6, 4, u32::MAX, u32::MAX)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_steps_panic() {
+        Image::new(2, 2).coordinates_step(0, 1);
+    }
+
+    #[test]
+    fn layout_matches_the_rows() {
+        for width in 0..9 {
+            let img = Image::new(width, 3);
+            let stride = img.row_stride_bytes() as u64 + img.padding_bytes() as u64;
+            assert_eq!(row_stride(24, width), stride);
+        }
+
+        let img = open("test/bmptestsuite-0.9/valid/24bpp-321x240.bmp").unwrap();
+        let stride = img.row_stride_bytes();
+        let base = img.as_bytes().as_ptr() as usize;
+        for &y in &[0, 1, 120, 239] {
+            let offset = (img.get_height() - y - 1) as usize * stride;
+            assert_eq!(base + offset, img.row(y).as_ptr() as usize);
+            assert_eq!(stride, Pixel::slice_as_bytes(img.row(y)).len());
+        }
+        assert_eq!(1, img.padding_bytes());
+    }
+
     // TODO: Add benches when they are considered stable
     // #[bench]
     // fn write_bmp(b: &mut test::Bencher) {
@@ -0,0 +1,129 @@
+use {BmpError, BmpErrorKind, BmpResult};
+
+/// Limits on the dimensions of an image, used to reject images that are too large before any
+/// memory is allocated for their pixels.
+///
+/// Every `Decoder` checks the default limits, including the one used by `bmp::open`, unless other
+/// limits are set with `Decoder::with_limits`.
+///
+/// # Example
+///
+/// ```
+/// let limits = bmp::Limits {
+///     max_pixels: 100,
+///     ..bmp::Limits::default()
+/// };
+/// assert!(bmp::Image::try_new_with_limits(10, 10, limits).is_ok());
+/// assert!(bmp::Image::try_new_with_limits(10, 11, limits).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Limits {
+    /// The maximum width of an image.
+    pub max_width: u32,
+    /// The maximum height of an image.
+    pub max_height: u32,
+    /// The maximum number of pixels in an image, `width * height`.
+    pub max_pixels: u32,
+}
+
+impl Limits {
+    /// The default maximum number of pixels, which is 16384x16384 pixels or 768 MiB of pixel data.
+    pub const DEFAULT_MAX_PIXELS: u32 = 1 << 28;
+
+    /// Returns limits that accept every image where `width * height` fits in a `u32`.
+    pub fn none() -> Limits {
+        Limits {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            max_pixels: u32::MAX,
+        }
+    }
+
+    // Returns an `InvalidDimensions` error if the image is empty, or if it exceeds the limits
+    pub(crate) fn check(&self, width: u32, height: u32) -> BmpResult<()> {
+        if width == 0 || height == 0 {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::InvalidDimensions,
+                "dimensions",
+                "at least 1x1",
+                format!("{}x{}", width, height),
+            ));
+        }
+        self.check_max(width, height)
+    }
+
+    // Returns an `InvalidDimensions` error if the image exceeds the limits. Empty images are
+    // accepted, for decoders that have to read every valid image.
+    pub(crate) fn check_max(&self, width: u32, height: u32) -> BmpResult<()> {
+        let invalid = |expected: String| {
+            Err(BmpError::mismatch(
+                BmpErrorKind::InvalidDimensions,
+                "dimensions",
+                expected,
+                format!("{}x{}", width, height),
+            ))
+        };
+        if width > self.max_width || height > self.max_height {
+            return invalid(format!("at most {}x{}", self.max_width, self.max_height));
+        }
+        match width.checked_mul(height) {
+            Some(pixels) if pixels <= self.max_pixels => Ok(()),
+            _ => invalid(format!("at most {} pixels", self.max_pixels)),
+        }
+    }
+}
+
+/// The default limits only cap the number of pixels, at `Limits::DEFAULT_MAX_PIXELS`.
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_pixels: Limits::DEFAULT_MAX_PIXELS,
+            ..Limits::none()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_images_are_rejected() {
+        match Limits::none().check(0, 10) {
+            Err(BmpError {
+                kind: BmpErrorKind::InvalidDimensions,
+                ..
+            }) => (/* Expected */),
+            other => panic!(
+                "Expected an InvalidDimensions error, but was {:?}",
+                other.err()
+            ),
+        }
+    }
+
+    #[test]
+    fn products_overflowing_u32_are_rejected() {
+        assert!(Limits::none().check(1 << 16, 1 << 16).is_err());
+        assert!(Limits::none().check(1 << 16, (1 << 16) - 1).is_ok());
+    }
+
+    #[test]
+    fn decoders_accept_empty_images_within_the_limits() {
+        assert!(Limits::default().check_max(0, 10).is_ok());
+        assert!(Limits::default().check_max(1 << 15, 1 << 14).is_err());
+        assert!(Limits::none().check_max(1 << 15, 1 << 14).is_ok());
+    }
+
+    #[test]
+    fn every_limit_is_checked() {
+        let limits = Limits {
+            max_width: 10,
+            max_height: 20,
+            max_pixels: 150,
+        };
+        assert!(limits.check(10, 15).is_ok());
+        assert!(limits.check(11, 1).is_err());
+        assert!(limits.check(1, 21).is_err());
+        assert!(limits.check(10, 16).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use {decoder, encoded_size, ppm_to_dpi, BmpDibHeader, BmpHeader, BmpResult, BmpVersion};
+use {ColorSpace, CompressionType, Image};
+
+/// A summary of the headers of a BMP file.
+///
+/// It is read from a file without decoding the pixels with `read_metadata` or `open_metadata`,
+/// or describes the file an `Image` is encoded as with `Image::metadata`.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct BmpMetadata {
+    pub version: BmpVersion,
+    pub bits_per_pixel: u16,
+    pub compression: CompressionType,
+    pub width: u32,
+    pub height: u32,
+    /// The number of colors in the color palette, 0 if the image doesn't have one.
+    pub palette_size: u32,
+    /// The horizontal and vertical resolution in dots per inch.
+    pub dpi: (f32, f32),
+    /// The size of the file in bytes, as given by the header.
+    pub file_size: u64,
+    /// The color space of BMP Version 4 and 5 images.
+    pub color_space: Option<ColorSpace>,
+}
+
+impl BmpMetadata {
+    pub(crate) fn from_headers(
+        header: &BmpHeader,
+        dib_header: &BmpDibHeader,
+        version: BmpVersion,
+        color_space: Option<ColorSpace>,
+    ) -> BmpMetadata {
+        let palette_size = match dib_header.bits_per_pixel {
+            _ if dib_header.num_colors != 0 => dib_header.num_colors,
+            bpp @ 1 | bpp @ 4 | bpp @ 8 => 1 << bpp,
+            _ => 0,
+        };
+        BmpMetadata {
+            version,
+            bits_per_pixel: dib_header.bits_per_pixel,
+            compression: CompressionType::from_u32(dib_header.compress_type),
+            width: dib_header.width.unsigned_abs(),
+            height: dib_header.height.unsigned_abs(),
+            palette_size,
+            dpi: (ppm_to_dpi(dib_header.hres), ppm_to_dpi(dib_header.vres)),
+            file_size: header.file_size as u64,
+            color_space,
+        }
+    }
+}
+
+/// Reads the headers of the BMP image from `source` without decoding the pixels, also for images
+/// that cannot be decoded by this crate, such as compressed images.
+///
+/// Returns a `BmpError` if the source is not a BMP image of version 3, 4, or 5.
+pub fn read_metadata<R: Read>(source: &mut R) -> BmpResult<BmpMetadata> {
+    decoder::decode_metadata(source)
+}
+
+/// Reads the headers of the BMP file specified by `path`. See `read_metadata` for details.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{BmpVersion, CompressionType};
+///
+/// let meta = bmp::open_metadata("test/bmpsuite-2.5/g/pal8v5.bmp").unwrap();
+/// assert_eq!(BmpVersion::Five, meta.version);
+/// assert_eq!(CompressionType::Uncompressed, meta.compression);
+/// assert_eq!((127, 64, 8), (meta.width, meta.height, meta.bits_per_pixel));
+/// ```
+pub fn open_metadata<P: AsRef<Path>>(path: P) -> BmpResult<BmpMetadata> {
+    let f = fs::File::open(path)?;
+    decoder::decode_metadata(io::BufReader::new(f))
+}
+
+/// The format of the file an `Image` was decoded from, which is otherwise lost since every image
+/// is stored as 24-bit RGB pixels in memory.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct SourceFormat {
+    pub version: BmpVersion,
+    pub bits_per_pixel: u16,
+    pub compression: CompressionType,
+    /// The color space of BMP Version 4 and 5 images.
+    pub color_space: Option<ColorSpace>,
+}
+
+impl Image {
+    /// Returns the format of the file the image was decoded from, or `None` if the image was
+    /// created in memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::open("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+    /// let format = img.source_format().unwrap();
+    /// assert_eq!(bmp::BmpVersion::Three, format.version);
+    /// assert_eq!(8, format.bits_per_pixel);
+    ///
+    /// assert_eq!(None, bmp::Image::new(1, 1).source_format());
+    /// ```
+    #[inline]
+    pub fn source_format(&self) -> Option<SourceFormat> {
+        self.source_format
+    }
+
+    /// Returns the metadata of the file the image is encoded as: an uncompressed BMP Version 3
+    /// file with 24 bits per pixel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let meta = bmp::Image::new(2, 2).metadata();
+    /// assert_eq!(24, meta.bits_per_pixel);
+    /// assert_eq!(bmp::encoded_size(24, 2, 2), meta.file_size);
+    /// ```
+    pub fn metadata(&self) -> BmpMetadata {
+        BmpMetadata {
+            version: BmpVersion::Three,
+            bits_per_pixel: 24,
+            compression: CompressionType::Uncompressed,
+            width: self.width,
+            height: self.height,
+            palette_size: 0,
+            dpi: self.dpi(),
+            file_size: encoded_size(24, self.width, self.height),
+            color_space: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_of_an_encoded_image_matches_the_image() {
+        let mut img = Image::new(3, 2);
+        img.set_dpi(96.0, 96.0);
+        let mut bytes = Vec::new();
+        img.to_writer(&mut bytes).unwrap();
+
+        let meta = read_metadata(&mut &bytes[..]).unwrap();
+        assert_eq!(img.metadata(), meta);
+        assert_eq!(bytes.len() as u64, meta.file_size);
+    }
+
+    #[test]
+    fn metadata_of_images_that_cannot_be_decoded() {
+        let meta = open_metadata("test/bmpsuite-2.5/q/rgb24jpeg.bmp").unwrap();
+        assert_eq!(CompressionType::Jpeg, meta.compression);
+        assert!(::open("test/bmpsuite-2.5/q/rgb24jpeg.bmp").is_err());
+    }
+
+    #[test]
+    fn source_format_is_kept_after_decoding() {
+        let img = ::open("test/bmpsuite-2.5/g/pal8v4.bmp").unwrap();
+        let format = img.source_format().unwrap();
+        assert_eq!(BmpVersion::Four, format.version);
+        assert_eq!(8, format.bits_per_pixel);
+        assert_eq!(CompressionType::Uncompressed, format.compression);
+        // The image is encoded as 24 bits per pixel, regardless of its source
+        assert_eq!(24, img.metadata().bits_per_pixel);
+    }
+
+    #[test]
+    fn palette_size_defaults_to_every_color() {
+        let meta = open_metadata("test/bmptestsuite-0.9/valid/1bpp-1x1.bmp").unwrap();
+        assert_eq!(2, meta.palette_size);
+    }
+}
@@ -0,0 +1,196 @@
+use {BmpResult, Image};
+
+/// Returns the mean squared error between the color channels of the images `a` and `b`. It is 0
+/// for equal images, and grows with the size of the differences.
+///
+/// Returns a `BmpError` if the two images do not have the same dimensions.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{metrics, Image, Pixel};
+///
+/// let mut a = Image::new(2, 1);
+/// let b = Image::new(2, 1);
+/// a.set_pixel(0, 0, Pixel::new(6, 0, 0));
+/// assert_eq!(6.0, metrics::mse(&a, &b).unwrap());
+/// ```
+pub fn mse(a: &Image, b: &Image) -> BmpResult<f64> {
+    a.check_same_dimensions(b)?;
+    if a.data.is_empty() {
+        return Ok(0.0);
+    }
+    let sum: u64 = a
+        .data
+        .iter()
+        .zip(&b.data)
+        .map(|(a, b)| {
+            let (dr, dg, db) = (
+                a.r.abs_diff(b.r) as u64,
+                a.g.abs_diff(b.g) as u64,
+                a.b.abs_diff(b.b) as u64,
+            );
+            dr * dr + dg * dg + db * db
+        })
+        .sum();
+    Ok(sum as f64 / (a.data.len() * 3) as f64)
+}
+
+/// Returns the peak signal-to-noise ratio between the images `a` and `b` in decibels. Higher
+/// values mean more similar images, and equal images give infinity. Lossy image compression
+/// typically gives values between 30 and 50.
+///
+/// Returns a `BmpError` if the two images do not have the same dimensions.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{metrics, Image, Pixel};
+///
+/// let a = Image::new(100, 80);
+/// let mut b = a.clone();
+/// assert_eq!(std::f64::INFINITY, metrics::psnr(&a, &b).unwrap());
+///
+/// b.set_pixel(0, 0, Pixel::new(1, 0, 0));
+/// assert!(metrics::psnr(&a, &b).unwrap() > 50.0);
+/// ```
+pub fn psnr(a: &Image, b: &Image) -> BmpResult<f64> {
+    let mse = mse(a, b)?;
+    Ok(10.0 * (255.0 * 255.0 / mse).log10())
+}
+
+// The radius and standard deviation of the Gaussian window used by `ssim`
+const SSIM_RADIUS: i64 = 5;
+const SSIM_SIGMA: f64 = 1.5;
+
+// Filters the `width` by `height` values with the Gaussian window of `ssim`, repeating the values
+// along the edges outside of the image
+fn gaussian_filter(values: &[f64], width: usize, height: usize) -> Vec<f64> {
+    let weights: Vec<f64> = (-SSIM_RADIUS..=SSIM_RADIUS)
+        .map(|i| (-((i * i) as f64) / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp())
+        .collect();
+    let total: f64 = weights.iter().sum();
+    let clamp = |i: i64, size: usize| i.clamp(0, size as i64 - 1) as usize;
+
+    let mut rows = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            rows[y * width + x] = weights
+                .iter()
+                .zip(-SSIM_RADIUS..)
+                .map(|(w, i)| w * values[y * width + clamp(x as i64 + i, width)])
+                .sum::<f64>()
+                / total;
+        }
+    }
+    let mut filtered = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            filtered[y * width + x] = weights
+                .iter()
+                .zip(-SSIM_RADIUS..)
+                .map(|(w, i)| w * rows[clamp(y as i64 + i, height) * width + x])
+                .sum::<f64>()
+                / total;
+        }
+    }
+    filtered
+}
+
+/// Returns the structural similarity index between the images `a` and `b`. It compares the
+/// brightness, contrast and structure of the luma in a Gaussian window around every pixel, and
+/// matches how people perceive differences better than `psnr`. The index is 1 for equal
+/// images, and decreases towards 0 (or below, for inverted structure) as they differ more.
+///
+/// Returns a `BmpError` if the two images do not have the same dimensions.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{generators, metrics};
+///
+/// let a = generators::value_noise(64, 64, 8, 1);
+/// assert_eq!(1.0, metrics::ssim(&a, &a).unwrap());
+///
+/// let b = generators::value_noise(64, 64, 8, 2);
+/// assert!(metrics::ssim(&a, &b).unwrap() < 0.5);
+/// ```
+pub fn ssim(a: &Image, b: &Image) -> BmpResult<f64> {
+    a.check_same_dimensions(b)?;
+    if a.data.is_empty() {
+        return Ok(1.0);
+    }
+    let (width, height) = (a.width as usize, a.height as usize);
+    let luma = |img: &Image| -> Vec<f64> { img.data.iter().map(|px| px.luma() as f64).collect() };
+    let (la, lb) = (luma(a), luma(b));
+    let product =
+        |x: &[f64], y: &[f64]| -> Vec<f64> { x.iter().zip(y).map(|(x, y)| x * y).collect() };
+
+    let mean_a = gaussian_filter(&la, width, height);
+    let mean_b = gaussian_filter(&lb, width, height);
+    let sq_a = gaussian_filter(&product(&la, &la), width, height);
+    let sq_b = gaussian_filter(&product(&lb, &lb), width, height);
+    let ab = gaussian_filter(&product(&la, &lb), width, height);
+
+    let c1 = (0.01f64 * 255.0).powi(2);
+    let c2 = (0.03f64 * 255.0).powi(2);
+    let mut sum = 0.0;
+    for i in 0..la.len() {
+        let (ma, mb) = (mean_a[i], mean_b[i]);
+        let var_a = sq_a[i] - ma * ma;
+        let var_b = sq_b[i] - mb * mb;
+        let cov = ab[i] - ma * mb;
+        sum += ((2.0 * ma * mb + c1) * (2.0 * cov + c2))
+            / ((ma * ma + mb * mb + c1) * (var_a + var_b + c2));
+    }
+    Ok(sum / la.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {consts, BmpError, BmpErrorKind, Pixel};
+
+    #[test]
+    fn psnr_follows_the_mse() {
+        let a = Image::new(4, 4);
+        let mut b = a.clone();
+        b.fill(Pixel::new(1, 1, 1));
+        assert_eq!(mse(&a, &b).unwrap(), 1.0);
+        assert!((psnr(&a, &b).unwrap() - 48.1308).abs() < 1e-4);
+
+        b.fill(consts::WHITE);
+        assert_eq!(mse(&a, &b).unwrap(), 65025.0);
+        assert_eq!(psnr(&a, &b).unwrap(), 0.0);
+
+        match mse(&a, &Image::new(3, 4)) {
+            Err(BmpError {
+                kind: BmpErrorKind::MismatchedDimensions,
+                ..
+            }) => (/* Expected */),
+            other => panic!("Expected mismatched dimensions, but was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ssim_prefers_structure_over_brightness() {
+        let a = ::generators::value_noise(32, 32, 4, 9);
+        assert!((ssim(&a, &a).unwrap() - 1.0).abs() < 1e-9);
+
+        // A small change of the brightness keeps the structure
+        let mut brighter = a.clone();
+        brighter.levels(0, 255, 10, 255, 1.0);
+        // Noise of about the same error destroys it
+        let mut noisy = a.clone();
+        let noise = ::generators::noise(32, 32, 1);
+        for (x, y) in a.coordinates() {
+            let px = a.get_pixel(x, y);
+            let offset = if noise.get_pixel(x, y).r < 128 { -9 } else { 9 };
+            let v = (px.r as i32 + offset).clamp(0, 255) as u8;
+            noisy.set_pixel(x, y, Pixel::new(v, v, v));
+        }
+        assert!(mse(&a, &noisy).unwrap() > mse(&a, &brighter).unwrap() * 0.5);
+        assert!(ssim(&a, &brighter).unwrap() > 0.95);
+        assert!(ssim(&a, &noisy).unwrap() < ssim(&a, &brighter).unwrap());
+    }
+}
@@ -0,0 +1,70 @@
+use ndarray::{Array3, ArrayView3};
+
+use {BmpError, BmpErrorKind, BmpResult, ChannelOrder, Image};
+
+impl Image {
+    /// Returns the pixels as an array with the shape `(height, width, 3)`, where the last axis
+    /// holds the `r`, `g` and `b` channels. The first row of the array is the top of the image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(4, 2);
+    /// img.set_pixel(3, 1, bmp::consts::BLUE);
+    /// let array = img.to_array3();
+    /// assert_eq!(&[2, 4, 3], array.shape());
+    /// assert_eq!(255, array[[1, 3, 2]]);
+    /// ```
+    pub fn to_array3(&self) -> Array3<u8> {
+        let shape = (self.height as usize, self.width as usize, 3);
+        Array3::from_shape_vec(shape, self.as_raw(ChannelOrder::Rgb))
+            .expect("The raw pixels of an Image match its dimensions")
+    }
+
+    /// Returns a new `Image` from an array with the shape `(height, width, 3)`, where the last
+    /// axis holds the `r`, `g` and `b` channels. The first row of the array is the top of the
+    /// image.
+    ///
+    /// Returns a `BmpError` if the last axis doesn't have a length of three.
+    pub fn from_array3(array: ArrayView3<'_, u8>) -> BmpResult<Image> {
+        let (height, width, channels) = array.dim();
+        if channels != 3 {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::WrongBufferSize,
+                "channels",
+                3,
+                channels,
+            ));
+        }
+        // Iterating in logical order handles arrays of any memory layout
+        let bytes: Vec<u8> = array.iter().cloned().collect();
+        Image::from_raw_rgb(&bytes, width as u32, height as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+    use ndarray::{Array3, Axis};
+
+    #[test]
+    fn arrays_round_trip() {
+        let mut img = Image::new(3, 2);
+        img.set_pixel(2, 0, consts::RED);
+        let array = img.to_array3();
+        assert_eq!(array[[0, 2, 0]], 255);
+        assert_eq!(Image::from_array3(array.view()).unwrap(), img);
+
+        // A transposed view isn't stored in row-major order
+        let mut transposed = array.clone();
+        transposed.swap_axes(0, 1);
+        let img = Image::from_array3(transposed.view()).unwrap();
+        assert_eq!((2, 3), (img.get_width(), img.get_height()));
+        assert_eq!(img.get_pixel(0, 2), consts::RED);
+
+        let rgba = Array3::<u8>::zeros((2, 2, 4));
+        assert!(Image::from_array3(rgba.view()).is_err());
+        assert!(Image::from_array3(array.select(Axis(2), &[0, 1]).view()).is_err());
+    }
+}
@@ -0,0 +1,450 @@
+use std::fs;
+use std::io::{self, Write};
+
+use encoder;
+use {Image, ImageIndex, Pixel};
+
+/// A list of up to 256 colors, used by images where every pixel is an index into the list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Palette {
+    colors: Vec<Pixel>,
+}
+
+impl Palette {
+    /// Creates a new `Palette` of the `colors`.
+    ///
+    /// Panics if `colors` is empty or contains more than 256 colors.
+    pub fn new(colors: Vec<Pixel>) -> Palette {
+        assert!(
+            !colors.is_empty() && colors.len() <= 256,
+            "A palette must contain between 1 and 256 colors, but was {}",
+            colors.len()
+        );
+        Palette { colors }
+    }
+
+    /// Returns the colors of the palette.
+    #[inline]
+    pub fn colors(&self) -> &[Pixel] {
+        &self.colors
+    }
+
+    /// Returns the index of the color in the palette that is closest to `px`, measured by the
+    /// distance between the color channels. The first of several equally close colors is
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Palette, Pixel};
+    ///
+    /// let palette = Palette::new(vec![consts::BLACK, consts::WHITE]);
+    /// assert_eq!(1, palette.nearest(Pixel::new(200, 150, 100)));
+    /// ```
+    pub fn nearest(&self, px: Pixel) -> u8 {
+        let distance = |c: &Pixel| {
+            let (dr, dg, db) = (
+                c.r as i32 - px.r as i32,
+                c.g as i32 - px.g as i32,
+                c.b as i32 - px.b as i32,
+            );
+            dr * dr + dg * dg + db * db
+        };
+        let mut best = 0;
+        for (i, c) in self.colors.iter().enumerate().skip(1) {
+            if distance(c) < distance(&self.colors[best]) {
+                best = i;
+            }
+        }
+        best as u8
+    }
+}
+
+/// The threshold matrices used by ordered dithering.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BayerMatrix {
+    /// A 2×2 matrix, giving a coarse pattern with 5 shades between two colors.
+    Size2,
+    /// A 4×4 matrix, giving 17 shades between two colors.
+    Size4,
+    /// An 8×8 matrix, giving the finest pattern with 65 shades between two colors.
+    Size8,
+}
+
+impl BayerMatrix {
+    fn size(self) -> usize {
+        match self {
+            BayerMatrix::Size2 => 2,
+            BayerMatrix::Size4 => 4,
+            BayerMatrix::Size8 => 8,
+        }
+    }
+
+    // The threshold at `x` and `y` of the matrix, from 0 up to the number of cells
+    fn threshold(self, x: u32, y: u32) -> u32 {
+        // Interleave the bits of `x ^ y` and `y`, from the lowest to the highest bit
+        let (mut x, mut y) = (x as usize % self.size(), y as usize % self.size());
+        let mut value = 0;
+        let mut size = self.size();
+        while size > 1 {
+            value = (value << 2) | (((x ^ y) & 1) << 1) | (y & 1);
+            x >>= 1;
+            y >>= 1;
+            size >>= 1;
+        }
+        value as u32
+    }
+}
+
+/// An in-memory image where every pixel is an index into a `Palette`.
+///
+/// Like the `Image`, it is accessed in row-major order from top to bottom, where point (0, 0) is
+/// defined to be in the upper left corner of the image.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IndexedImage {
+    width: u32,
+    height: u32,
+    palette: Palette,
+    indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    /// Returns a new `IndexedImage` with the `width` and `height` specified, where every pixel
+    /// has the first color of the `palette`.
+    pub fn new(width: u32, height: u32, palette: Palette) -> IndexedImage {
+        IndexedImage {
+            width,
+            height,
+            palette,
+            indices: vec![0; width as usize * height as usize],
+        }
+    }
+
+    // The `indices` are stored in row-major order from top to bottom, and must all be valid
+    pub(crate) fn from_indices(
+        width: u32,
+        height: u32,
+        palette: Palette,
+        indices: Vec<u8>,
+    ) -> IndexedImage {
+        debug_assert_eq!(indices.len(), width as usize * height as usize);
+        IndexedImage {
+            width,
+            height,
+            palette,
+            indices,
+        }
+    }
+
+    /// Returns the `width` of the image.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the image.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the palette of the image.
+    #[inline]
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Returns the palette indexes of all the pixels, in row-major order from top to bottom.
+    #[inline]
+    pub fn indices(&self) -> &[u8] {
+        &self.indices
+    }
+
+    /// Set the palette index at the position of `x` and `y`.
+    ///
+    /// Panics if `index` is outside of the palette.
+    #[inline]
+    pub fn set_index(&mut self, x: u32, y: u32, index: u8) {
+        assert!(
+            (index as usize) < self.palette.colors.len(),
+            "{} is outside of the palette",
+            index
+        );
+        let i = self.offset(x, y);
+        self.indices[i] = index;
+    }
+
+    /// Returns the palette index at the position of `x` and `y`.
+    #[inline]
+    pub fn get_index(&self, x: u32, y: u32) -> u8 {
+        self.indices[self.offset(x, y)]
+    }
+
+    /// Returns the color at the position of `x` and `y`.
+    #[inline]
+    pub fn get_pixel(&self, x: u32, y: u32) -> Pixel {
+        self.palette.colors[self.get_index(x, y) as usize]
+    }
+
+    #[inline]
+    fn offset(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width, "{} is outside of the image", x);
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the image dimensions in top-bottom order.
+    #[inline]
+    pub fn coordinates(&self) -> ImageIndex {
+        ImageIndex::new(self.width, self.height)
+    }
+
+    /// Returns a new `Image` with the palette indexes replaced by their colors.
+    pub fn to_image(&self) -> Image {
+        let mut img = Image::new(self.width, self.height);
+        for (x, y) in self.coordinates() {
+            img.set_pixel(x, y, self.get_pixel(x, y));
+        }
+        img
+    }
+
+    /// Returns the number of bits per pixel the image is encoded with, which is the fewest of 1,
+    /// 4 or 8 bits that can index every color of the palette.
+    pub fn bits_per_pixel(&self) -> u16 {
+        match self.palette.colors.len() {
+            0..=2 => 1,
+            3..=16 => 4,
+            _ => 8,
+        }
+    }
+
+    /// Saves the image to the file specified by `path`, with its palette and the number of bits
+    /// per pixel returned by `bits_per_pixel`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Palette};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::RED);
+    /// let indexed = img.to_indexed(&Palette::new(vec![consts::BLACK, consts::RED]));
+    /// let path = std::env::temp_dir().join("indexed-save-example.bmp");
+    /// indexed.save(&path).unwrap();
+    /// assert_eq!(1, bmp::open_metadata(&path).unwrap().bits_per_pixel);
+    /// assert_eq!(consts::RED, bmp::open(&path).unwrap().get_pixel(0, 0));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> io::Result<()> {
+        let mut bmp_file = fs::File::create(path)?;
+        self.to_writer(&mut bmp_file)
+    }
+
+    /// Writes the image to the writer referenced by `destination`, like `save`.
+    pub fn to_writer<W: Write>(&self, destination: &mut W) -> io::Result<()> {
+        let bmp_data = encoder::encode_indexed_image(self)?;
+        destination.write_all(&bmp_data)?;
+        Ok(())
+    }
+}
+
+impl Image {
+    /// Returns a new `IndexedImage` where every pixel is replaced by the closest color in the
+    /// `palette`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Palette};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::DARK_RED);
+    /// let indexed = img.to_indexed(&Palette::new(vec![consts::BLACK, consts::RED]));
+    /// assert_eq!(1, indexed.get_index(0, 0));
+    /// ```
+    pub fn to_indexed(&self, palette: &Palette) -> IndexedImage {
+        let mut indexed = IndexedImage::new(self.width, self.height, palette.clone());
+        for (x, y) in self.coordinates() {
+            let i = palette.nearest(self.get_pixel(x, y));
+            indexed.set_index(x, y, i);
+        }
+        indexed
+    }
+
+    /// Returns a new `IndexedImage` where every pixel is replaced by a color in the `palette`,
+    /// using Floyd–Steinberg dithering. The difference between each pixel and its replacement
+    /// is spread to the neighboring pixels, so areas keep their average color even when the
+    /// palette has few colors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Palette};
+    ///
+    /// let mut img = Image::new(10, 10);
+    /// img.fill(consts::GRAY);
+    /// let palette = Palette::new(vec![consts::BLACK, consts::WHITE]);
+    /// let dithered = img.dither_floyd_steinberg(&palette);
+    /// // Roughly half of the pixels are white
+    /// let white = dithered.indices().iter().filter(|&&i| i == 1).count();
+    /// assert!((45..=55).contains(&white));
+    /// ```
+    pub fn dither_floyd_steinberg(&self, palette: &Palette) -> IndexedImage {
+        let mut indexed = IndexedImage::new(self.width, self.height, palette.clone());
+        let width = self.width as usize;
+        // The errors spread to the current and the next row, with one extra column on each side
+        let mut current = vec![[0i32; 3]; width + 2];
+        let mut next = vec![[0i32; 3]; width + 2];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let px = self.get_pixel(x, y);
+                let e = current[x as usize + 1];
+                let channel = |v: u8, e: i32| (v as i32 + (e + 8).div_euclid(16)).clamp(0, 255);
+                let wanted = [
+                    channel(px.r, e[0]),
+                    channel(px.g, e[1]),
+                    channel(px.b, e[2]),
+                ];
+
+                let i = palette.nearest(Pixel::new(
+                    wanted[0] as u8,
+                    wanted[1] as u8,
+                    wanted[2] as u8,
+                ));
+                indexed.set_index(x, y, i);
+
+                let c = palette.colors[i as usize];
+                let got = [c.r as i32, c.g as i32, c.b as i32];
+                let x = x as usize + 1;
+                for ch in 0..3 {
+                    let err = wanted[ch] - got[ch];
+                    current[x + 1][ch] += err * 7;
+                    next[x - 1][ch] += err * 3;
+                    next[x][ch] += err * 5;
+                    next[x + 1][ch] += err;
+                }
+            }
+            current = next;
+            next = vec![[0; 3]; width + 2];
+        }
+        indexed
+    }
+
+    /// Returns a new `IndexedImage` where every pixel is replaced by a color in the `palette`,
+    /// using ordered dithering with a Bayer `matrix`. Every pixel is offset by the threshold at
+    /// its position in the repeated matrix before the closest color is chosen, so the pattern
+    /// only depends on the position and the color of the pixel. This keeps the pattern stable
+    /// between similar frames of an animation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, BayerMatrix, Image, Palette};
+    ///
+    /// let mut img = Image::new(4, 4);
+    /// img.fill(consts::GRAY);
+    /// let palette = Palette::new(vec![consts::BLACK, consts::WHITE]);
+    /// let dithered = img.dither_ordered(&palette, BayerMatrix::Size2);
+    /// assert_eq!(&[0, 1, 0, 1, 1, 0, 1, 0], &dithered.indices()[..8]);
+    /// ```
+    pub fn dither_ordered(&self, palette: &Palette, matrix: BayerMatrix) -> IndexedImage {
+        let mut indexed = IndexedImage::new(self.width, self.height, palette.clone());
+        let cells = (matrix.size() * matrix.size()) as f32;
+        // The distance between the colors, if they were evenly spread over every channel
+        let levels = (palette.colors.len() as f32).cbrt();
+        let spread = 255.0 / (levels - 1.0).max(1.0);
+
+        for (x, y) in self.coordinates() {
+            let px = self.get_pixel(x, y);
+            let offset = ((matrix.threshold(x, y) as f32 + 0.5) / cells - 0.5) * spread;
+            let channel = |v: u8| (v as f32 + offset).round().clamp(0.0, 255.0) as u8;
+            let wanted = Pixel::new(channel(px.r), channel(px.g), channel(px.b));
+            indexed.set_index(x, y, palette.nearest(wanted));
+        }
+        indexed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+    use quantize::Quantizer;
+
+    #[test]
+    fn dithering_keeps_the_average_color() {
+        let palette = Palette::new(vec![consts::BLACK, consts::WHITE]);
+        for &level in &[32u8, 128, 200] {
+            let mut img = Image::new(64, 64);
+            img.fill(Pixel::new(level, level, level));
+
+            let dithered = img.dither_floyd_steinberg(&palette);
+            let sum: u32 = dithered
+                .coordinates()
+                .map(|(x, y)| dithered.get_pixel(x, y).r as u32)
+                .sum();
+            let average = sum as f32 / 4096.0;
+            assert!((average - level as f32).abs() < 4.0);
+
+            // Without dithering, the closest color is used everywhere
+            let indexed = img.to_indexed(&palette);
+            let closest = if level < 128 { 0 } else { 1 };
+            assert!(indexed.indices().iter().all(|&i| i == closest));
+        }
+    }
+
+    #[test]
+    fn indexed_images_are_encoded_with_their_palette() {
+        let mut img = Image::new(7, 3);
+        for (x, y) in img.coordinates() {
+            img.set_pixel(x, y, Pixel::new(x as u8 * 36, y as u8 * 120, 0));
+        }
+        for &(colors, bpp) in &[(2, 1), (16, 4), (21, 8)] {
+            let indexed = img.quantize(colors, Quantizer::MedianCut);
+            assert_eq!(bpp, indexed.bits_per_pixel());
+            let mut bytes = Vec::new();
+            indexed.to_writer(&mut bytes).unwrap();
+            assert_eq!(::encoded_size(bpp, 7, 3), bytes.len() as u64);
+
+            let decoded = ::Decoder::new().decode_indexed(&bytes[..]).unwrap();
+            assert_eq!(1 << bpp, decoded.palette().colors().len());
+            assert_eq!(indexed.indices(), decoded.indices());
+            assert_eq!(indexed.to_image().data, decoded.to_image().data);
+        }
+    }
+
+    #[test]
+    fn bayer_matrices_contain_every_threshold_once() {
+        for &matrix in &[BayerMatrix::Size2, BayerMatrix::Size4, BayerMatrix::Size8] {
+            let n = matrix.size() as u32;
+            let mut thresholds: Vec<u32> =
+                (0..n * n).map(|i| matrix.threshold(i % n, i / n)).collect();
+            thresholds.sort();
+            assert_eq!(thresholds, (0..n * n).collect::<Vec<u32>>());
+        }
+        let rows: Vec<Vec<u32>> = (0..4)
+            .map(|y| (0..4).map(|x| BayerMatrix::Size4.threshold(x, y)).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![0, 8, 2, 10],
+                vec![12, 4, 14, 6],
+                vec![3, 11, 1, 9],
+                vec![15, 7, 13, 5]
+            ]
+        );
+    }
+
+    #[test]
+    fn ordered_dithering_gives_the_shades_of_the_matrix() {
+        let palette = Palette::new(vec![consts::BLACK, consts::WHITE]);
+        for &level in &[0u8, 32, 128, 200, 255] {
+            let mut img = Image::new(8, 8);
+            img.fill(Pixel::new(level, level, level));
+            let dithered = img.dither_ordered(&palette, BayerMatrix::Size8);
+            let white = dithered.indices().iter().filter(|&&i| i == 1).count();
+            assert!((white as f32 - level as f32 / 255.0 * 64.0).abs() <= 1.0);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use rayon::prelude::*;
+
+use {Image, Pixel};
+
+impl Image {
+    /// Returns a parallel iterator over the positions and mutable references to the pixels of
+    /// the image, as `(x, y, pixel)`. The order of the pixels is not specified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate bmp;
+    /// extern crate rayon;
+    ///
+    /// use bmp::{Image, Pixel};
+    /// use rayon::prelude::*;
+    ///
+    /// # fn main() {
+    /// let mut img = Image::new(256, 256);
+    /// img.par_pixels_mut()
+    ///     .for_each(|(x, y, px)| *px = Pixel::new(x as u8, y as u8, 200));
+    /// assert_eq!(Pixel::new(10, 20, 200), img.get_pixel(10, 20));
+    /// # }
+    /// ```
+    pub fn par_pixels_mut(&mut self) -> impl ParallelIterator<Item = (u32, u32, &mut Pixel)> {
+        let (width, height) = (self.width, self.height);
+        self.data
+            .par_chunks_mut(width.max(1) as usize)
+            .enumerate()
+            .flat_map(move |(row, pixels)| {
+                // The rows are stored from bottom to top internally
+                let y = height - row as u32 - 1;
+                pixels
+                    .par_iter_mut()
+                    .enumerate()
+                    .map(move |(x, px)| (x as u32, y, px))
+            })
+    }
+
+    /// Replaces every pixel of the image with the result of applying `f` to it, using all the
+    /// threads of the rayon thread pool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Pixel};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.par_map_in_place(|px| Pixel::new(255 - px.r, 255 - px.g, 255 - px.b));
+    /// assert_eq!(consts::WHITE, img.get_pixel(50, 40));
+    /// ```
+    pub fn par_map_in_place<F>(&mut self, f: F)
+    where
+        F: Fn(Pixel) -> Pixel + Sync + Send,
+    {
+        self.data.par_iter_mut().for_each(|px| *px = f(*px));
+    }
+
+    /// Returns a new `Image` of `width` and `height`, where the pixel at `x` and `y` is the
+    /// result of `f(x, y)`. The pixels are computed in parallel, using all the threads of the
+    /// rayon thread pool.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Image, Pixel};
+    ///
+    /// let img = Image::par_from_fn(256, 256, |x, y| Pixel::new(x as u8, y as u8, 200));
+    /// assert_eq!(Pixel::new(10, 20, 200), img.get_pixel(10, 20));
+    /// ```
+    pub fn par_from_fn<F>(width: u32, height: u32, f: F) -> Image
+    where
+        F: Fn(u32, u32) -> Pixel + Sync + Send,
+    {
+        let mut img = Image::new(width, height);
+        img.par_pixels_mut().for_each(|(x, y, px)| *px = f(x, y));
+        img
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_iteration_visits_every_pixel_once() {
+        let img = Image::par_from_fn(7, 5, |x, y| Pixel::new(x as u8, y as u8, 0));
+        for (x, y) in img.coordinates() {
+            assert_eq!(img.get_pixel(x, y), Pixel::new(x as u8, y as u8, 0));
+        }
+
+        let mut counted = img.clone();
+        counted.par_map_in_place(|px| Pixel::new(px.r, px.g, px.b + 1));
+        assert!(counted
+            .coordinates()
+            .all(|(x, y)| counted.get_pixel(x, y).b == 1));
+        assert_eq!(counted.par_pixels_mut().count(), 35);
+    }
+}
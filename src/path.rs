@@ -0,0 +1,467 @@
+use draw::{Bezier, LineCap, Stroke};
+use {Image, Paint};
+
+/// The rules deciding which parts of a self-intersecting `Path` are inside of it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FillRule {
+    /// A point is inside if the path winds around it a non-zero number of times.
+    NonZero,
+    /// A point is inside if a ray from it crosses the path an odd number of times.
+    EvenOdd,
+}
+
+/// A shape made of connected lines and curves, which can be stroked or filled onto an `Image`.
+///
+/// A path consists of subpaths, each starting with `move_to`. The points are `(x, y)` positions
+/// in the image, and can be between or outside of the pixels.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, FillRule, Image, Path};
+///
+/// let triangle = Path::new()
+///     .move_to((10.0, 70.0))
+///     .line_to((50.0, 10.0))
+///     .line_to((90.0, 70.0))
+///     .close();
+///
+/// let mut img = Image::new(100, 80);
+/// img.fill_path(&triangle, FillRule::NonZero, consts::RED);
+/// assert_eq!(consts::RED, img.get_pixel(50, 50));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    subpaths: Vec<Subpath>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Subpath {
+    // Curves are flattened to lines when they are added
+    points: Vec<(f32, f32)>,
+    closed: bool,
+}
+
+impl Path {
+    /// Creates a new empty `Path`.
+    pub fn new() -> Path {
+        Path::default()
+    }
+
+    /// Returns the `Path` with a new subpath starting at `to`.
+    pub fn move_to(mut self, to: (f32, f32)) -> Path {
+        self.start_subpath(to);
+        self
+    }
+
+    /// Returns the `Path` with a line from the current point to `to`.
+    ///
+    /// Without a current point, this starts a new subpath at `to` like `move_to`.
+    pub fn line_to(mut self, to: (f32, f32)) -> Path {
+        match self.current() {
+            Some(subpath) => subpath.points.push(to),
+            None => return self.move_to(to),
+        }
+        self
+    }
+
+    /// Returns the `Path` with a quadratic Bézier curve from the current point to `to`, pulled
+    /// towards `control`.
+    ///
+    /// Without a current point, the curve starts at `control`.
+    pub fn quad_to(mut self, control: (f32, f32), to: (f32, f32)) -> Path {
+        if self.current().is_none() {
+            self = self.move_to(control);
+        }
+        if let Some(subpath) = self.current() {
+            let from = subpath.points[subpath.points.len() - 1];
+            Bezier::Quadratic(from, control, to).flatten_into(&mut subpath.points);
+        }
+        self
+    }
+
+    /// Returns the `Path` with the current subpath closed by a line back to its start.
+    ///
+    /// Lines and curves added after closing start a new subpath at the same point.
+    pub fn close(mut self) -> Path {
+        if let Some(subpath) = self.subpaths.last_mut() {
+            subpath.closed = true;
+        }
+        self
+    }
+
+    // The subpath that lines and curves are added to, if there is a current point. A new subpath
+    // is started after a closed one
+    fn current(&mut self) -> Option<&mut Subpath> {
+        let start = match self.subpaths.last() {
+            Some(subpath) if subpath.closed => subpath.points[0],
+            Some(_) => return self.subpaths.last_mut(),
+            None => return None,
+        };
+        Some(self.start_subpath(start))
+    }
+
+    fn start_subpath(&mut self, to: (f32, f32)) -> &mut Subpath {
+        self.subpaths.push(Subpath {
+            points: vec![to],
+            closed: false,
+        });
+        self.subpaths.last_mut().unwrap()
+    }
+
+    // The line segments of the path, where every subpath is closed when `close_all` is set
+    fn segments(&self, close_all: bool) -> Vec<((f32, f32), (f32, f32))> {
+        let mut segments = Vec::new();
+        for subpath in &self.subpaths {
+            let points = &subpath.points;
+            segments.extend(points.windows(2).map(|w| (w[0], w[1])));
+            if subpath.closed || close_all {
+                segments.push((points[points.len() - 1], points[0]));
+            }
+        }
+        segments
+    }
+}
+
+// The pixels covered by a stroke, within the rectangle of the image that the stroke can reach
+struct Mask {
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    covered: Vec<bool>,
+}
+
+impl Mask {
+    // A mask of the pixels within `margin` of `points`, clipped to an image of `width` and
+    // `height`
+    fn around<'a, I>(points: I, margin: f32, width: u32, height: u32) -> Mask
+    where
+        I: Iterator<Item = &'a (f32, f32)>,
+    {
+        let mut bounds = (f32::INFINITY, f32::INFINITY, -f32::INFINITY, -f32::INFINITY);
+        for p in points {
+            bounds = (
+                bounds.0.min(p.0),
+                bounds.1.min(p.1),
+                bounds.2.max(p.0),
+                bounds.3.max(p.1),
+            );
+        }
+        let clamp = |v: f32, max: u32| v.max(0.0).min(max as f32) as u32;
+        let left = clamp((bounds.0 - margin).floor(), width);
+        let top = clamp((bounds.1 - margin).floor(), height);
+        let right = clamp((bounds.2 + margin).ceil(), width).max(left);
+        let bottom = clamp((bounds.3 + margin).ceil(), height).max(top);
+        let (width, height) = (right - left, bottom - top);
+        Mask {
+            left,
+            top,
+            width,
+            height,
+            covered: vec![false; width as usize * height as usize],
+        }
+    }
+
+    // Marks the pixels within `bounds` whose center is covered by the shape
+    fn mark<F>(&mut self, bounds: (f32, f32, f32, f32), covers: F)
+    where
+        F: Fn((f32, f32)) -> bool,
+    {
+        let clamp = |v: f32, min: u32, max: u32| v.max(min as f32).min(max as f32) as u32;
+        let (right, bottom) = (self.left + self.width, self.top + self.height);
+        let x_start = clamp(bounds.0.floor(), self.left, right);
+        let y_start = clamp(bounds.1.floor(), self.top, bottom);
+        let x_end = clamp(bounds.2.ceil(), self.left, right);
+        let y_end = clamp(bounds.3.ceil(), self.top, bottom);
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                let i = (y - self.top) as usize * self.width as usize + (x - self.left) as usize;
+                if !self.covered[i] && covers((x as f32 + 0.5, y as f32 + 0.5)) {
+                    self.covered[i] = true;
+                }
+            }
+        }
+    }
+
+    // The image coordinates of the marked pixels
+    fn pixels<'a>(&'a self) -> impl Iterator<Item = (u32, u32)> + 'a {
+        let (left, top, width) = (self.left, self.top, self.width.max(1));
+        self.covered
+            .iter()
+            .enumerate()
+            .filter(|&(_, &m)| m)
+            .map(move |(i, _)| (left + i as u32 % width, top + i as u32 / width))
+    }
+}
+
+// Marks the pixels covered by the stroked line through `points`
+fn mark_polyline(mask: &mut Mask, mut points: Vec<(f32, f32)>, closed: bool, stroke: &Stroke) {
+    let half = stroke.width / 2.0;
+    let butt = Stroke::new(stroke.width);
+    let round = Stroke::new(stroke.width).with_cap(LineCap::Round);
+    points.dedup();
+    if points.len() == 1 {
+        let p = points[0];
+        mask.mark(around(p, stroke.width), |q| stroke.covers(q, p, p));
+        return;
+    }
+
+    let closed = closed && points.len() > 2;
+    let mut segments: Vec<_> = points.windows(2).map(|w| (w[0], w[1])).collect();
+    if closed {
+        segments.push((points[points.len() - 1], points[0]));
+    }
+    for &(a, b) in &segments {
+        let bounds = (
+            a.0.min(b.0) - half,
+            a.1.min(b.1) - half,
+            a.0.max(b.0) + half,
+            a.1.max(b.1) + half,
+        );
+        mask.mark(bounds, |p| butt.covers(p, a, b));
+    }
+
+    // Round joins between the segments
+    let joins = if closed {
+        &points[..]
+    } else {
+        &points[1..points.len() - 1]
+    };
+    for &j in joins {
+        mask.mark(around(j, half), |p| round.covers(p, j, j));
+    }
+
+    if !closed {
+        let n = points.len();
+        for &(end, prev) in &[(points[0], points[1]), (points[n - 1], points[n - 2])] {
+            let (dx, dy) = (end.0 - prev.0, end.1 - prev.1);
+            let length = (dx * dx + dy * dy).sqrt();
+            let tip = (end.0 + dx / length * half, end.1 + dy / length * half);
+            let bounds = around(end, stroke.width);
+            match stroke.cap {
+                LineCap::Butt => (),
+                LineCap::Round => mask.mark(bounds, |p| round.covers(p, end, end)),
+                LineCap::Square => mask.mark(bounds, |p| butt.covers(p, end, tip)),
+            }
+        }
+    }
+}
+
+fn around(p: (f32, f32), margin: f32) -> (f32, f32, f32, f32) {
+    (p.0 - margin, p.1 - margin, p.0 + margin, p.1 + margin)
+}
+
+impl Image {
+    /// Draws the outline of `path` with the width and caps given by `stroke`, in the color `val`.
+    /// The lines of a subpath are joined by rounded corners, and the caps are drawn at the ends
+    /// of the subpaths that are not closed, and at the ends of every dash. A pixel is drawn if its
+    /// center is inside the outline, and every pixel is only drawn once. The parts of the path
+    /// that fall outside of the image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image, Path, Stroke};
+    ///
+    /// let path = Path::new()
+    ///     .move_to((10.0, 10.0))
+    ///     .line_to((90.0, 10.0))
+    ///     .quad_to((90.0, 70.0), (10.0, 70.0));
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.stroke_path(&path, &Stroke::new(3.0), consts::RED);
+    /// assert_eq!(consts::RED, img.get_pixel(50, 11));
+    /// assert_eq!(consts::BLACK, img.get_pixel(50, 40));
+    /// ```
+    pub fn stroke_path<P: Into<Paint>>(&mut self, path: &Path, stroke: &Stroke, val: P) {
+        // Only the pixels within the width of the stroke from the path can be covered
+        let points = path.subpaths.iter().flat_map(|s| &s.points);
+        let mut mask = Mask::around(points, stroke.width, self.width, self.height);
+        // The dashes beyond the width of the stroke from the image aren't split at all
+        let (width, height) = (self.width as f32, self.height as f32);
+        let bounds = (
+            -stroke.width,
+            -stroke.width,
+            width + stroke.width,
+            height + stroke.width,
+        );
+        for subpath in &path.subpaths {
+            let mut points = subpath.points.clone();
+            let closed = subpath.closed && points.len() > 2;
+            if stroke.style.dashes.is_empty() {
+                mark_polyline(&mut mask, points, closed, stroke);
+                continue;
+            }
+            if closed {
+                points.push(points[0]);
+            }
+            for dash in stroke.style.split(&points, bounds) {
+                mark_polyline(&mut mask, dash, false, stroke);
+            }
+        }
+
+        let val = val.into();
+        for (x, y) in mask.pixels() {
+            self.paint_pixel(x, y, val);
+        }
+    }
+
+    /// Fills the inside of `path` with the color `val`, where `rule` decides which parts of a
+    /// self-intersecting path are inside. Every subpath is treated as closed. A pixel is drawn
+    /// if its center is inside the path. The parts of the path that fall outside of the image
+    /// are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, FillRule, Image, Path};
+    ///
+    /// // A square with a square hole
+    /// let path = Path::new()
+    ///     .move_to((10.0, 10.0))
+    ///     .line_to((70.0, 10.0))
+    ///     .line_to((70.0, 70.0))
+    ///     .line_to((10.0, 70.0))
+    ///     .move_to((30.0, 30.0))
+    ///     .line_to((50.0, 30.0))
+    ///     .line_to((50.0, 50.0))
+    ///     .line_to((30.0, 50.0));
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill_path(&path, FillRule::EvenOdd, consts::RED);
+    /// assert_eq!(consts::RED, img.get_pixel(20, 20));
+    /// assert_eq!(consts::BLACK, img.get_pixel(40, 40));
+    /// ```
+    pub fn fill_path<P: Into<Paint>>(&mut self, path: &Path, rule: FillRule, val: P) {
+        let val = val.into();
+        // A segment with a point at infinity or NaN has no crossings that can be computed
+        let finite = |p: (f32, f32)| p.0.is_finite() && p.1.is_finite();
+        let mut segments = path.segments(true);
+        segments.retain(|&(a, b)| finite(a) && finite(b));
+        let mut crossings = Vec::new();
+        for y in 0..self.height {
+            // Find where the path crosses the centers of the row, and in which direction
+            let cy = y as f32 + 0.5;
+            crossings.clear();
+            for &(a, b) in &segments {
+                if (a.1 <= cy && cy < b.1) || (b.1 <= cy && cy < a.1) {
+                    let x = a.0 + (cy - a.1) * (b.0 - a.0) / (b.1 - a.1);
+                    crossings.push((x, if b.1 > a.1 { 1 } else { -1 }));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for w in crossings.windows(2) {
+                winding += w[0].1;
+                let inside = match rule {
+                    FillRule::NonZero => winding != 0,
+                    FillRule::EvenOdd => winding % 2 != 0,
+                };
+                if inside {
+                    // The pixels with their centers between the two crossings
+                    let x_start = (w[0].0 - 0.5).ceil().max(0.0) as u32;
+                    let x_end = ((w[1].0 - 0.5).ceil().max(0.0) as u32).min(self.width);
+                    for x in x_start..x_end {
+                        self.paint_pixel(x, y, val);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {consts, BlendMode};
+
+    fn square(path: Path, x: f32, y: f32, size: f32) -> Path {
+        path.move_to((x, y))
+            .line_to((x + size, y))
+            .line_to((x + size, y + size))
+            .line_to((x, y + size))
+            .close()
+    }
+
+    fn count(img: &Image) -> usize {
+        img.coordinates()
+            .filter(|&(x, y)| img.get_pixel(x, y) != consts::BLACK)
+            .count()
+    }
+
+    #[test]
+    fn line_to_after_close_starts_at_the_closed_subpath() {
+        let path = Path::new()
+            .line_to((1.0, 1.0))
+            .line_to((2.0, 1.0))
+            .close()
+            .line_to((3.0, 3.0));
+        assert_eq!(path.subpaths.len(), 2);
+        assert_eq!(path.subpaths[1].points, vec![(1.0, 1.0), (3.0, 3.0)]);
+    }
+
+    #[test]
+    fn fill_path_follows_the_fill_rule() {
+        // Two nested squares winding in the same direction
+        let path = square(square(Path::new(), 1.0, 1.0, 8.0), 3.0, 3.0, 4.0);
+
+        let mut img = Image::new(10, 10);
+        img.fill_path(&path, FillRule::NonZero, consts::WHITE);
+        assert_eq!(count(&img), 64);
+
+        let mut img = Image::new(10, 10);
+        img.fill_path(&path, FillRule::EvenOdd, consts::WHITE);
+        assert_eq!(count(&img), 64 - 16);
+    }
+
+    #[test]
+    fn fill_path_ignores_non_finite_segments() {
+        let path = square(Path::new(), 1.0, 1.0, 8.0)
+            .move_to((f32::NEG_INFINITY, 2.0))
+            .line_to((5.0, f32::INFINITY))
+            .line_to((f32::NAN, 4.0))
+            .close();
+        let mut img = Image::new(10, 10);
+        img.fill_path(&path, FillRule::EvenOdd, consts::WHITE);
+        assert_eq!(img.get_pixel(1, 1), consts::WHITE);
+        assert_eq!(img.get_pixel(0, 0), consts::BLACK);
+    }
+
+    #[test]
+    fn stroke_path_draws_every_pixel_once() {
+        let path = square(Path::new(), 2.5, 2.5, 5.0);
+        let mut img = Image::new(10, 10);
+        img.stroke_path(
+            &path,
+            &Stroke::new(1.0),
+            Paint::new(consts::WHITE, BlendMode::Xor),
+        );
+        assert_eq!(count(&img), 20);
+        assert_eq!(img.get_pixel(2, 2), consts::WHITE);
+        assert_eq!(img.get_pixel(5, 5), consts::BLACK);
+    }
+
+    #[test]
+    fn stroke_path_is_clipped_to_the_image() {
+        let stroke = Stroke::new(3.0).with_cap(LineCap::Square);
+        let mut img = Image::new(10, 10);
+        img.stroke_path(
+            &Path::new().move_to((-5.0, 5.5)).line_to((4.5, 5.5)),
+            &stroke,
+            consts::WHITE,
+        );
+        assert_eq!(count(&img), 6 * 3);
+
+        // A path far outside of the image draws nothing
+        img.stroke_path(
+            &Path::new().move_to((-1e9, -1e9)).line_to((-1e9, 1e9)),
+            &stroke,
+            consts::RED,
+        );
+        img.stroke_path(&square(Path::new(), 20.0, 20.0, 5.0), &stroke, consts::RED);
+        assert_eq!(count(&img), 6 * 3);
+        assert!(img.coordinates().all(|(x, y)| img.get_pixel(x, y) != consts::RED));
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use {BmpError, BmpErrorKind, BmpResult, Pixel, RowDecoder, RowEncoder};
+
+/// A chain of row transforms that streams an image from a `RowDecoder` to a `RowEncoder`.
+///
+/// Only a single row is kept in memory at a time, so images of any size are processed in
+/// constant memory. The steps are applied to every row in the order they are added, and can
+/// only depend on the row itself: a vertical flip, for instance, would need the whole image.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{Pipeline, Pixel};
+///
+/// let mut pipeline = Pipeline::new()
+///     .crop_columns(1, 1)
+///     .flip_horizontal()
+///     .map_pixels(|px| Pixel::new(255 - px.r, 255 - px.g, 255 - px.b));
+///
+/// let source = std::fs::File::open("test/rgbw.bmp").unwrap();
+/// let bytes = pipeline.run(source, Vec::new()).unwrap();
+///
+/// let img = bmp::from_reader(&mut &bytes[..]).unwrap();
+/// assert_eq!((1, 2), (img.get_width(), img.get_height()));
+/// assert_eq!(Pixel::new(255, 0, 255), img.get_pixel(0, 0));
+/// ```
+pub struct Pipeline<'a> {
+    steps: Vec<Step<'a>>,
+}
+
+type PixelFn<'a> = Box<dyn FnMut(Pixel) -> Pixel + 'a>;
+type RowFn<'a> = Box<dyn FnMut(u32, &mut [Pixel]) + 'a>;
+
+enum Step<'a> {
+    CropColumns(u32, u32),
+    FlipHorizontal,
+    MapPixels(PixelFn<'a>),
+    MapRows(RowFn<'a>),
+}
+
+impl<'a> Pipeline<'a> {
+    /// Returns a new `Pipeline` without any steps, which copies the image.
+    pub fn new() -> Pipeline<'a> {
+        Pipeline { steps: Vec::new() }
+    }
+
+    /// Keeps the `width` columns starting at `x`, and drops the rest of every row.
+    ///
+    /// Running the pipeline returns a `BmpError` if the columns are outside of the rows.
+    pub fn crop_columns(mut self, x: u32, width: u32) -> Pipeline<'a> {
+        self.steps.push(Step::CropColumns(x, width));
+        self
+    }
+
+    /// Reverses the order of the pixels in every row.
+    pub fn flip_horizontal(mut self) -> Pipeline<'a> {
+        self.steps.push(Step::FlipHorizontal);
+        self
+    }
+
+    /// Replaces every pixel with the result of `f`.
+    pub fn map_pixels<F: FnMut(Pixel) -> Pixel + 'a>(mut self, f: F) -> Pipeline<'a> {
+        self.steps.push(Step::MapPixels(Box::new(f)));
+        self
+    }
+
+    /// Calls `f` with the `y` coordinate and the pixels of every row, to edit the row in place.
+    /// The rows are passed from the bottom to the top of the image.
+    pub fn map_rows<F: FnMut(u32, &mut [Pixel]) + 'a>(mut self, f: F) -> Pipeline<'a> {
+        self.steps.push(Step::MapRows(Box::new(f)));
+        self
+    }
+
+    /// Decodes the image from `source`, runs every row through the pipeline, and encodes the
+    /// result to `destination`, which is returned. The resolution of the image is kept.
+    pub fn run<R: Read, W: Write>(&mut self, source: R, destination: W) -> BmpResult<W> {
+        self.run_rows(RowDecoder::new(source)?, destination)
+    }
+
+    /// Runs the rows of `rows` through the pipeline like `run`, for a decoder with other options
+    /// than the defaults.
+    pub fn run_rows<R: Read, W: Write>(
+        &mut self,
+        mut rows: RowDecoder<R>,
+        destination: W,
+    ) -> BmpResult<W> {
+        let width = self.output_width(rows.get_width())?;
+        let (hres, vres) = rows.resolution();
+        let mut encoder =
+            RowEncoder::new(destination, width, rows.get_height())?.with_resolution(hres, vres);
+
+        let mut buffer = Vec::with_capacity(rows.get_width() as usize);
+        while let Some((y, row)) = rows.next_row()? {
+            buffer.clear();
+            buffer.extend_from_slice(row);
+            self.apply(y, &mut buffer);
+            encoder.write_row(&buffer)?;
+        }
+        encoder.finish()
+    }
+
+    /// Runs the image in the file `input` through the pipeline like `run`, and writes the result
+    /// to the file `output`.
+    pub fn run_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        &mut self,
+        input: P,
+        output: Q,
+    ) -> BmpResult<()> {
+        let source = io::BufReader::new(fs::File::open(input)?);
+        let destination = io::BufWriter::new(fs::File::create(output)?);
+        self.run(source, destination)?;
+        Ok(())
+    }
+
+    // Returns the width of the rows after every step, or a `BmpError` if the columns of a crop
+    // are outside of the rows
+    fn output_width(&self, width: u32) -> BmpResult<u32> {
+        self.steps
+            .iter()
+            .try_fold(width, |width, step| match *step {
+                Step::CropColumns(x, w) if x as u64 + w as u64 > width as u64 => {
+                    Err(BmpError::mismatch(
+                        BmpErrorKind::OutOfBounds,
+                        "columns",
+                        format!("within a row of {} pixels", width),
+                        format!("{} columns at {}", w, x),
+                    ))
+                }
+                Step::CropColumns(_, w) => Ok(w),
+                _ => Ok(width),
+            })
+    }
+
+    fn apply(&mut self, y: u32, row: &mut Vec<Pixel>) {
+        for step in &mut self.steps {
+            match *step {
+                Step::CropColumns(x, width) => {
+                    row.truncate(x as usize + width as usize);
+                    row.drain(..x as usize);
+                }
+                Step::FlipHorizontal => row.reverse(),
+                Step::MapPixels(ref mut f) => {
+                    for px in row.iter_mut() {
+                        *px = f(*px);
+                    }
+                }
+                Step::MapRows(ref mut f) => f(y, row),
+            }
+        }
+    }
+}
+
+impl<'a> Default for Pipeline<'a> {
+    fn default() -> Pipeline<'a> {
+        Pipeline::new()
+    }
+}
+
+impl<'a> fmt::Debug for Pipeline<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let steps: Vec<&str> = self
+            .steps
+            .iter()
+            .map(|step| match *step {
+                Step::CropColumns(..) => "crop_columns",
+                Step::FlipHorizontal => "flip_horizontal",
+                Step::MapPixels(_) => "map_pixels",
+                Step::MapRows(_) => "map_rows",
+            })
+            .collect();
+        f.debug_struct("Pipeline").field("steps", &steps).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {consts, Image};
+
+    fn encode(img: &Image) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        img.to_writer(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn streamed_results_match_the_image_operations() {
+        let img = ::open("test/bmptestsuite-0.9/valid/24bpp-320x240.bmp").unwrap();
+        let invert = |px: Pixel| Pixel::new(255 - px.r, 255 - px.g, 255 - px.b);
+
+        let mut pipeline = Pipeline::new()
+            .crop_columns(10, 100)
+            .flip_horizontal()
+            .map_pixels(invert);
+        let bytes = pipeline.run(&encode(&img)[..], Vec::new()).unwrap();
+
+        let mut expected = img.crop(10, 0, 100, 240).unwrap().flip_horizontal();
+        for px in &mut expected.data {
+            *px = invert(*px);
+        }
+        assert_eq!(expected.data, ::from_reader(&mut &bytes[..]).unwrap().data);
+    }
+
+    #[test]
+    fn rows_are_passed_from_the_bottom() {
+        let mut img = Image::new(3, 4);
+        img.fill(consts::WHITE);
+        let mut visited = Vec::new();
+        {
+            let mut pipeline = Pipeline::new().map_rows(|y, row| {
+                visited.push(y);
+                if y == 0 {
+                    row[0] = consts::RED;
+                }
+            });
+            let bytes = pipeline.run(&encode(&img)[..], Vec::new()).unwrap();
+            img.set_pixel(0, 0, consts::RED);
+            assert_eq!(img.data, ::from_reader(&mut &bytes[..]).unwrap().data);
+        }
+        assert_eq!(vec![3, 2, 1, 0], visited);
+    }
+
+    #[test]
+    fn indexed_images_are_expanded() {
+        let path = "test/bmpsuite-2.5/g/pal8.bmp";
+        let bytes = Pipeline::new()
+            .run(fs::File::open(path).unwrap(), Vec::new())
+            .unwrap();
+        let img = ::from_reader(&mut &bytes[..]).unwrap();
+        assert_eq!(img.data, ::open(path).unwrap().data);
+    }
+
+    #[test]
+    fn crops_outside_of_the_rows_are_rejected() {
+        let bytes = encode(&Image::new(4, 4));
+        let mut pipeline = Pipeline::new().crop_columns(1, 2).crop_columns(1, 2);
+        match pipeline.run(&bytes[..], Vec::new()) {
+            Err(BmpError {
+                kind: BmpErrorKind::OutOfBounds,
+                ..
+            }) => (/* Expected */),
+            other => panic!("Expected an OutOfBounds error, but was {:?}", other.err()),
+        }
+    }
+}
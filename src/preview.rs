@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use {Filter, Image, Pixel};
+
+// The upper half block, which is colored with the top pixel as the foreground and the bottom
+// pixel as the background
+const UPPER_HALF_BLOCK: char = '\u{2580}';
+const RESET: &str = "\x1b[0m";
+
+impl Image {
+    /// Returns a preview of the image for terminals with 24-bit colors, where every character is
+    /// an upper half block that shows two pixels on top of each other. Images wider than
+    /// `max_width` characters are downscaled to `max_width` first, keeping their aspect ratio.
+    ///
+    /// Every line ends by resetting the colors. Printing the preview is a quick way to look at an
+    /// image over SSH, or in the output of a failing test.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(2, 2);
+    /// img.set_pixel(0, 0, bmp::consts::RED);
+    ///
+    /// let preview = img.render_ansi(80);
+    /// assert_eq!(1, preview.lines().count());
+    /// assert!(preview.starts_with("\x1b[38;2;255;0;0m\x1b[48;2;0;0;0m\u{2580}"));
+    /// ```
+    pub fn render_ansi(&self, max_width: u32) -> String {
+        if self.width == 0 || self.height == 0 || max_width == 0 {
+            return String::new();
+        }
+        let scaled;
+        let img = if self.width > max_width {
+            let height = self.height as u64 * max_width as u64 / self.width as u64;
+            scaled = self.resize_with(max_width, height.max(1) as u32, Filter::Bilinear);
+            &scaled
+        } else {
+            self
+        };
+
+        let mut out = String::new();
+        for y in (0..img.height).step_by(2) {
+            let top = img.row(y);
+            let bottom = if y + 1 < img.height {
+                Some(img.row(y + 1))
+            } else {
+                None
+            };
+            for (x, &px) in top.iter().enumerate() {
+                write_color(&mut out, 38, px);
+                match bottom {
+                    Some(bottom) => write_color(&mut out, 48, bottom[x]),
+                    // The bottom half of the last line of an odd height is left empty
+                    None => out.push_str("\x1b[49m"),
+                }
+                out.push(UPPER_HALF_BLOCK);
+            }
+            out.push_str(RESET);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+// Writes the escape code of the foreground (38) or background (48) color `px`
+fn write_color(out: &mut String, layer: u8, px: Pixel) {
+    // Writing to a `String` never fails
+    let _ = write!(out, "\x1b[{};2;{};{};{}m", layer, px.r, px.g, px.b);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn two_rows_share_a_line() {
+        let mut img = Image::new(3, 5);
+        img.fill(consts::WHITE);
+        let preview = img.render_ansi(80);
+        assert_eq!(3, preview.lines().count());
+        assert!(preview.lines().all(|line| line.ends_with(RESET)));
+        assert_eq!(9, preview.matches(UPPER_HALF_BLOCK).count());
+        // The last line only has a top half
+        assert_eq!(3, preview.matches("\x1b[49m").count());
+    }
+
+    #[test]
+    fn wide_images_are_downscaled() {
+        let img = Image::new(200, 100);
+        let preview = img.render_ansi(50);
+        let first = preview.lines().next().unwrap();
+        assert_eq!(50, first.matches(UPPER_HALF_BLOCK).count());
+        assert_eq!(13, preview.lines().count());
+    }
+
+    #[test]
+    fn empty_previews() {
+        assert_eq!("", Image::new(0, 0).render_ansi(80));
+        assert_eq!("", Image::new(4, 4).render_ansi(0));
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use {BmpError, BmpErrorKind, BmpResult};
+
+/// A flag that cancels a decoding or encoding in progress, typically from another thread.
+///
+/// The token is cloned into a `Decoder`, an `Encoder` or a `RowEncoder`, which check it before
+/// every row and return a `BmpError` with the kind `Cancelled` once it is cancelled.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{BmpErrorKind, CancellationToken, Decoder};
+///
+/// let token = CancellationToken::new();
+/// let mut decoder = Decoder::new().with_cancellation(token.clone());
+/// token.cancel();
+///
+/// let f = std::fs::File::open("test/rgbw.bmp").unwrap();
+/// let err = decoder.decode(f).unwrap_err();
+/// assert_eq!(&BmpErrorKind::Cancelled, err.kind());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Returns a new `CancellationToken` that is not cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels the operations that use the token, or any of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// The progress callback and the cancellation token of a decoder or an encoder, updated after
+// every row
+#[derive(Default)]
+pub(crate) struct Monitor {
+    progress: Option<Box<dyn FnMut(u64, u64) + Send>>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Monitor {
+    pub(crate) fn set_progress<F: FnMut(u64, u64) + Send + 'static>(&mut self, progress: F) {
+        self.progress = Some(Box::new(progress));
+    }
+
+    pub(crate) fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
+    // Returns a `Cancelled` error if the token is cancelled
+    pub(crate) fn check(&self) -> BmpResult<()> {
+        match self.cancellation {
+            Some(ref token) if token.is_cancelled() => Err(BmpError::new(
+                BmpErrorKind::Cancelled,
+                "cancelled by the cancellation token",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn report(&mut self, bytes_done: u64, bytes_total: u64) {
+        if let Some(ref mut progress) = self.progress {
+            progress(bytes_done, bytes_total);
+        }
+    }
+}
+
+impl fmt::Debug for Monitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Monitor")
+            .field("progress", &self.progress.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
@@ -0,0 +1,252 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use {row_stride, BmpError, BmpErrorKind, BmpResult, Decoder, Image};
+
+// The bytes that are needed to know the size of an image: the BMP header, and the DIB header up
+// to and including the bits per pixel
+const SIZE_FIELDS_END: usize = 30;
+
+// The largest offset of the pixels that is buffered. It leaves room for the largest headers, a
+// palette and an embedded color profile, while a bogus offset can't make the decoder buffer
+// gigabytes.
+const MAX_PIXEL_OFFSET: u64 = 1 << 20;
+
+/// The result of feeding bytes to a `PushDecoder`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+    /// More bytes are needed to complete the image. The number of missing bytes is given once
+    /// the headers are read.
+    NeedMore(Option<u64>),
+    /// Every byte of the image has arrived, and the image is decoded.
+    Complete(Image),
+}
+
+/// A decoder that is pushed the bytes of an image as they arrive, instead of reading them from a
+/// `Read` source, for protocols and servers where the bytes arrive in chunks.
+///
+/// The bytes are buffered until the size given by the headers has arrived, and the image is then
+/// decoded with a `Decoder`. The bytes that arrive after the end of an image are kept as the
+/// start of the next image, so a stream of concatenated images can be decoded one by one.
+///
+/// The dimensions are checked against the limits of the decoder as soon as they arrive, so an
+/// image that is too large is rejected before its pixels are buffered. After an error, the bytes
+/// of the failed image are dropped. If its headers were rejected, the whole buffer is dropped,
+/// since it is unknown where the next image starts, and the rest of the stream should be
+/// abandoned.
+///
+/// # Example
+///
+/// ```
+/// use bmp::push::{PushDecoder, Status};
+///
+/// let bytes = std::fs::read("test/rgbw.bmp").unwrap();
+/// let mut decoder = PushDecoder::new();
+/// assert_eq!(Status::NeedMore(None), decoder.feed(&bytes[..20]).unwrap());
+/// assert_eq!(Status::NeedMore(Some(30)), decoder.feed(&bytes[20..40]).unwrap());
+///
+/// match decoder.feed(&bytes[40..]).unwrap() {
+///     Status::Complete(img) => assert_eq!(bmp::consts::RED, img.get_pixel(0, 0)),
+///     Status::NeedMore(_) => panic!("The whole image was fed"),
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct PushDecoder {
+    decoder: Decoder,
+    buffer: Vec<u8>,
+}
+
+impl PushDecoder {
+    /// Returns a new `PushDecoder` that decodes the images with a default `Decoder`.
+    pub fn new() -> PushDecoder {
+        PushDecoder::with_decoder(Decoder::new())
+    }
+
+    /// Returns a new `PushDecoder` that decodes the images with `decoder`, to decode them with
+    /// other options than the defaults.
+    pub fn with_decoder(decoder: Decoder) -> PushDecoder {
+        PushDecoder {
+            decoder,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the number of bytes that are buffered, but not yet decoded.
+    #[inline]
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Appends `bytes` to the image, and decodes it once it is complete. Feeding an empty slice
+    /// decodes the next image from the bytes that are already buffered.
+    ///
+    /// Returns a `BmpError` if the bytes are not a BMP image, if the image exceeds the limits of
+    /// the decoder, or if the complete image cannot be decoded.
+    pub fn feed(&mut self, bytes: &[u8]) -> BmpResult<Status> {
+        self.buffer.extend_from_slice(bytes);
+        let size = match self.image_size() {
+            Ok(Some(size)) => size,
+            Ok(None) => return Ok(Status::NeedMore(None)),
+            Err(err) => {
+                self.buffer.clear();
+                return Err(err);
+            }
+        };
+        if (self.buffer.len() as u64) < size {
+            return Ok(Status::NeedMore(Some(size - self.buffer.len() as u64)));
+        }
+
+        let result = self.decoder.decode(&self.buffer[..size as usize]);
+        // The image is dropped even if it can't be decoded, so the images after it still can
+        self.buffer.drain(..size as usize);
+        result.map(Status::Complete)
+    }
+
+    /// Decodes the buffered bytes at the end of the stream, for encoders that leave out the
+    /// padding of the last row. Returns a `BmpError` if the bytes are not a complete image.
+    pub fn finish(mut self) -> BmpResult<Image> {
+        match self.feed(&[])? {
+            Status::Complete(img) => Ok(img),
+            Status::NeedMore(_) => self.decoder.decode(&self.buffer[..]),
+        }
+    }
+
+    // Returns the size of the image given by its headers, up to the end of the pixels, or `None`
+    // if the headers haven't arrived. Returns a `BmpError` if the headers are rejected.
+    fn image_size(&self) -> BmpResult<Option<u64>> {
+        if self.buffer.len() >= 2 && self.buffer[..2] != b"BM"[..] {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::WrongMagicNumbers,
+                "magic",
+                "[66, 77]",
+                format!("{:?}", &self.buffer[..2]),
+            ));
+        }
+        if self.buffer.len() < SIZE_FIELDS_END {
+            return Ok(None);
+        }
+        let pixel_offset = LittleEndian::read_u32(&self.buffer[10..14]) as u64;
+        let width = LittleEndian::read_i32(&self.buffer[18..22]).unsigned_abs();
+        let height = LittleEndian::read_i32(&self.buffer[22..26]).unsigned_abs();
+        let bpp = LittleEndian::read_u16(&self.buffer[28..30]);
+        self.decoder.check_limits(width, height)?;
+        if pixel_offset > MAX_PIXEL_OFFSET {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::UnsupportedHeader,
+                "pixel_offset",
+                format!("at most {}", MAX_PIXEL_OFFSET),
+                pixel_offset,
+            ));
+        }
+        let data_size = row_stride(bpp, width).saturating_mul(height as u64);
+        let size = pixel_offset.saturating_add(data_size);
+        // The decoder reports malformed headers, which at least have to arrive
+        Ok(Some(size.max(SIZE_FIELDS_END as u64)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Limits;
+
+    #[test]
+    fn images_are_decoded_from_chunks_of_any_size() {
+        let bytes = ::std::fs::read("test/bmpsuite-2.5/g/pal8v5.bmp").unwrap();
+        let expected = ::open("test/bmpsuite-2.5/g/pal8v5.bmp").unwrap();
+        for &chunk_size in &[1, 7, 512, bytes.len()] {
+            let mut decoder = PushDecoder::new();
+            let mut images = Vec::new();
+            for chunk in bytes.chunks(chunk_size) {
+                if let Status::Complete(img) = decoder.feed(chunk).unwrap() {
+                    images.push(img);
+                }
+            }
+            assert_eq!(vec![expected.clone()], images);
+            assert_eq!(0, decoder.buffered());
+        }
+    }
+
+    #[test]
+    fn concatenated_images_are_decoded_one_by_one() {
+        let mut bytes = Vec::new();
+        ::Image::new(3, 2).to_writer(&mut bytes).unwrap();
+        ::Image::new(1, 5).to_writer(&mut bytes).unwrap();
+
+        let mut decoder = PushDecoder::new();
+        match decoder.feed(&bytes).unwrap() {
+            Status::Complete(img) => assert_eq!(3, img.get_width()),
+            other => panic!("Expected the first image, but was {:?}", other),
+        }
+        match decoder.feed(&[]).unwrap() {
+            Status::Complete(img) => assert_eq!(5, img.get_height()),
+            other => panic!("Expected the second image, but was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_padding_is_decoded_when_finished() {
+        let mut bytes = Vec::new();
+        ::Image::new(1, 1).to_writer(&mut bytes).unwrap();
+        bytes.pop();
+
+        let mut decoder = PushDecoder::new();
+        assert_eq!(Status::NeedMore(Some(1)), decoder.feed(&bytes).unwrap());
+        assert!(decoder.finish().is_ok());
+    }
+
+    #[test]
+    fn wrong_magic_numbers_are_reported_early() {
+        let mut decoder = PushDecoder::new();
+        assert!(decoder.feed(b"PNG").is_err());
+        assert_eq!(0, decoder.buffered());
+    }
+
+    #[test]
+    fn huge_images_are_rejected_before_their_pixels_are_buffered() {
+        let mut bytes = Vec::new();
+        ::Image::new(1, 1).to_writer(&mut bytes).unwrap();
+        bytes[18..22].copy_from_slice(&(1i32 << 15).to_le_bytes());
+        bytes[22..26].copy_from_slice(&(1i32 << 15).to_le_bytes());
+
+        let mut decoder = PushDecoder::new();
+        match decoder.feed(&bytes[..SIZE_FIELDS_END]) {
+            Err(BmpError {
+                kind: BmpErrorKind::InvalidDimensions,
+                ..
+            }) => (/* Expected */),
+            other => panic!("Expected an InvalidDimensions error, but was {:?}", other),
+        }
+        assert_eq!(0, decoder.buffered());
+
+        bytes[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(PushDecoder::new().feed(&bytes[..SIZE_FIELDS_END]).is_err());
+    }
+
+    #[test]
+    fn images_after_an_image_that_cannot_be_decoded_are_decoded() {
+        let mut bytes = Vec::new();
+        ::Image::new(2, 2).to_writer(&mut bytes).unwrap();
+        // The compression type is only rejected when the image is decoded
+        bytes[30..34].copy_from_slice(&3u32.to_le_bytes());
+        ::Image::new(1, 5).to_writer(&mut bytes).unwrap();
+
+        let mut decoder = PushDecoder::new();
+        assert!(decoder.feed(&bytes).is_err());
+        match decoder.feed(&[]).unwrap() {
+            Status::Complete(img) => assert_eq!(5, img.get_height()),
+            other => panic!("Expected the second image, but was {:?}", other),
+        }
+    }
+
+    #[test]
+    fn images_are_decoded_with_the_options_of_the_decoder() {
+        let mut bytes = Vec::new();
+        ::Image::new(4, 4).to_writer(&mut bytes).unwrap();
+        let limits = Limits {
+            max_pixels: 4,
+            ..Limits::default()
+        };
+        let mut decoder = PushDecoder::with_decoder(Decoder::new().with_limits(limits));
+        assert!(decoder.feed(&bytes).is_err());
+    }
+}
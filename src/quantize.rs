@@ -0,0 +1,440 @@
+use std::collections::{HashMap, HashSet};
+
+use {Image, IndexedImage, Palette, Pixel};
+
+/// The algorithms used to choose the palette when quantizing an `Image`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Quantizer {
+    /// Splits the colors in groups with about the same number of pixels. Gives the best palette,
+    /// but needs to count every unique color first.
+    MedianCut,
+    /// Merges similar colors in a tree while the pixels are read. Faster, and uses less memory
+    /// on large images.
+    Octree,
+}
+
+// A group of colors, with the number of pixels of each color
+struct ColorBox {
+    colors: Vec<(Pixel, u32)>,
+}
+
+impl ColorBox {
+    // The channel with the widest range of values, and the size of the range
+    fn widest_channel(&self) -> (usize, u8) {
+        let mut min = [255u8; 3];
+        let mut max = [0u8; 3];
+        for &(px, _) in &self.colors {
+            for (ch, &v) in [px.r, px.g, px.b].iter().enumerate() {
+                min[ch] = min[ch].min(v);
+                max[ch] = max[ch].max(v);
+            }
+        }
+        (0..3)
+            .map(|ch| (ch, max[ch] - min[ch]))
+            .max_by_key(|&(ch, range)| (range, 3 - ch))
+            .unwrap()
+    }
+
+    // Splits the box in two halves with about the same number of pixels, along the widest channel
+    fn split(mut self) -> (ColorBox, ColorBox) {
+        let (ch, _) = self.widest_channel();
+        self.colors.sort_by_key(|&(px, _)| [px.r, px.g, px.b][ch]);
+
+        let total: u32 = self.colors.iter().map(|&(_, n)| n).sum();
+        let mut count = 0;
+        let mut mid = 1;
+        for (i, &(_, n)) in self.colors.iter().enumerate() {
+            count += n;
+            if count * 2 >= total {
+                // Keep at least one color in each half
+                mid = (i + 1).min(self.colors.len() - 1);
+                break;
+            }
+        }
+        let upper = self.colors.split_off(mid);
+        (self, ColorBox { colors: upper })
+    }
+
+    // The average color of the pixels in the box
+    fn average(&self) -> Pixel {
+        let mut sum = [0u64; 3];
+        let mut total = 0u64;
+        for &(px, n) in &self.colors {
+            sum[0] += px.r as u64 * n as u64;
+            sum[1] += px.g as u64 * n as u64;
+            sum[2] += px.b as u64 * n as u64;
+            total += n as u64;
+        }
+        let avg = |s: u64| ((s + total / 2) / total) as u8;
+        Pixel::new(avg(sum[0]), avg(sum[1]), avg(sum[2]))
+    }
+}
+
+// A node in an octree, where each level splits the colors by one bit of every channel
+#[derive(Clone, Default)]
+struct OctreeNode {
+    // The indexes of the children in the tree, where 0 is no child
+    children: [usize; 8],
+    sum: [u64; 3],
+    count: u64,
+    leaf: bool,
+    index: u8,
+}
+
+// The number of levels below the root, one for every bit of the channels
+const OCTREE_DEPTH: usize = 8;
+
+struct Octree {
+    nodes: Vec<OctreeNode>,
+    // The nodes with children at every level, which can be merged into leaves
+    reducible: Vec<Vec<usize>>,
+    leaves: usize,
+}
+
+// The child of a node at `level` containing `px`
+fn octant(px: Pixel, level: usize) -> usize {
+    let bit = 7 - level;
+    (((px.r >> bit) & 1) << 2 | ((px.g >> bit) & 1) << 1 | ((px.b >> bit) & 1)) as usize
+}
+
+impl Octree {
+    fn new() -> Octree {
+        let mut tree = Octree {
+            nodes: Vec::new(),
+            reducible: vec![Vec::new(); OCTREE_DEPTH],
+            leaves: 0,
+        };
+        tree.add_node(0);
+        tree
+    }
+
+    fn add_node(&mut self, level: usize) -> usize {
+        let i = self.nodes.len();
+        let leaf = level == OCTREE_DEPTH;
+        self.nodes.push(OctreeNode {
+            leaf,
+            ..OctreeNode::default()
+        });
+        if leaf {
+            self.leaves += 1;
+        } else {
+            self.reducible[level].push(i);
+        }
+        i
+    }
+
+    // The leaf containing `px`, adding the nodes down to it if `grow` is set
+    fn leaf(&mut self, px: Pixel, grow: bool) -> usize {
+        let (mut node, mut level) = (0, 0);
+        while !self.nodes[node].leaf {
+            let i = octant(px, level);
+            if self.nodes[node].children[i] == 0 {
+                assert!(grow, "{} was not added to the tree", px);
+                let child = self.add_node(level + 1);
+                self.nodes[node].children[i] = child;
+            }
+            node = self.nodes[node].children[i];
+            level += 1;
+        }
+        node
+    }
+
+    fn insert(&mut self, px: Pixel) {
+        let leaf = self.leaf(px, true);
+        let node = &mut self.nodes[leaf];
+        node.sum[0] += px.r as u64;
+        node.sum[1] += px.g as u64;
+        node.sum[2] += px.b as u64;
+        node.count += 1;
+    }
+
+    // Merges the children of the deepest reducible node into it
+    fn reduce(&mut self) {
+        let node = match self
+            .reducible
+            .iter_mut()
+            .rev()
+            .find_map(|nodes| nodes.pop())
+        {
+            Some(node) => node,
+            None => return,
+        };
+        let children = self.nodes[node].children;
+        for &child in children.iter().filter(|&&child| child != 0) {
+            let (sum, count) = (self.nodes[child].sum, self.nodes[child].count);
+            let parent = &mut self.nodes[node];
+            for (total, s) in parent.sum.iter_mut().zip(sum.iter()) {
+                *total += s;
+            }
+            parent.count += count;
+            self.leaves -= 1;
+        }
+        let parent = &mut self.nodes[node];
+        parent.children = [0; 8];
+        parent.leaf = true;
+        self.leaves += 1;
+    }
+
+    // Numbers the leaves, and returns their average colors
+    fn palette(&mut self) -> Palette {
+        let mut colors = Vec::new();
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &mut self.nodes[i];
+            if node.leaf {
+                node.index = colors.len() as u8;
+                let avg = |s: u64| ((s + node.count / 2) / node.count) as u8;
+                colors.push(Pixel::new(
+                    avg(node.sum[0]),
+                    avg(node.sum[1]),
+                    avg(node.sum[2]),
+                ));
+            } else {
+                stack.extend(node.children.iter().rev().filter(|&&c| c != 0));
+            }
+        }
+        Palette::new(colors)
+    }
+}
+
+impl Image {
+    /// Returns a new `IndexedImage` with a `Palette` of at most `max_colors` colors, chosen by
+    /// the `quantizer`.
+    ///
+    /// Panics if `max_colors` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Image, Quantizer};
+    ///
+    /// let img = Image::new(100, 80);
+    /// let indexed = img.quantize(256, Quantizer::Octree);
+    /// assert_eq!(img, indexed.to_image());
+    /// ```
+    pub fn quantize(&self, max_colors: usize, quantizer: Quantizer) -> IndexedImage {
+        match quantizer {
+            Quantizer::MedianCut => self.quantize_median_cut(max_colors),
+            Quantizer::Octree => self.quantize_octree(max_colors),
+        }
+    }
+
+    /// Returns a new `IndexedImage` with a `Palette` of at most `max_colors` colors chosen with
+    /// an octree. The colors are added to a tree where every level splits them by one more bit
+    /// of each channel, and the deepest branches are merged whenever there are more than
+    /// `max_colors` leaves. Every leaf becomes the average of its colors in the palette.
+    ///
+    /// The tree never has many more than `max_colors` leaves, so the memory use does not grow
+    /// with the number of colors in the image. A `max_colors` above 256 is treated as 256.
+    ///
+    /// Panics if `max_colors` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::RED);
+    /// img.fill_rect(0, 0, 50, 80, consts::BLUE);
+    ///
+    /// let indexed = img.quantize_octree(16);
+    /// assert_eq!(2, indexed.palette().colors().len());
+    /// assert_eq!(img, indexed.to_image());
+    /// ```
+    pub fn quantize_octree(&self, max_colors: usize) -> IndexedImage {
+        assert!(max_colors > 0, "Expected at least 1 color");
+        let max_colors = max_colors.min(256);
+
+        let mut tree = Octree::new();
+        for &px in &self.data {
+            tree.insert(px);
+            while tree.leaves > max_colors {
+                tree.reduce();
+            }
+        }
+        if self.data.is_empty() {
+            tree.insert(Pixel::new(0, 0, 0));
+        }
+
+        let palette = tree.palette();
+        let mut indexed = IndexedImage::new(self.width, self.height, palette);
+        for (x, y) in self.coordinates() {
+            let leaf = tree.leaf(self.get_pixel(x, y), false);
+            indexed.set_index(x, y, tree.nodes[leaf].index);
+        }
+        indexed
+    }
+
+    /// Returns the number of different colors in the image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.set_pixel(0, 0, consts::RED);
+    /// assert_eq!(2, img.count_unique_colors());
+    /// ```
+    pub fn count_unique_colors(&self) -> usize {
+        self.data.iter().collect::<HashSet<_>>().len()
+    }
+
+    /// Returns `true` if the image has at most `max_colors` different colors, so it can be
+    /// stored with a palette of that size without losing any colors. The pixels are only read
+    /// until there are too many colors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.set_pixel(0, 0, consts::RED);
+    /// // Fits in a 1 bpp image
+    /// assert!(img.is_paletteable(2));
+    /// assert!(!img.is_paletteable(1));
+    /// ```
+    pub fn is_paletteable(&self, max_colors: usize) -> bool {
+        let mut colors = HashSet::new();
+        for px in &self.data {
+            if colors.insert(px) && colors.len() > max_colors {
+                return false;
+            }
+        }
+        true
+    }
+
+    // The number of pixels of every color in the image
+    fn histogram(&self) -> HashMap<Pixel, u32> {
+        let mut histogram = HashMap::new();
+        for &px in &self.data {
+            *histogram.entry(px).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Returns a new `IndexedImage` with a `Palette` of at most `max_colors` colors chosen with
+    /// the median cut algorithm. The colors of the image are repeatedly split in two groups
+    /// along the channel with the widest range, until there are `max_colors` groups. Every group
+    /// becomes the average of its colors in the palette.
+    ///
+    /// The palette has fewer colors if the image does. A `max_colors` above 256 is treated as
+    /// 256, since that is the largest size of a `Palette`.
+    ///
+    /// Panics if `max_colors` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::RED);
+    /// img.fill_rect(0, 0, 50, 80, consts::BLUE);
+    ///
+    /// let indexed = img.quantize_median_cut(16);
+    /// assert_eq!(2, indexed.palette().colors().len());
+    /// assert_eq!(img, indexed.to_image());
+    /// ```
+    pub fn quantize_median_cut(&self, max_colors: usize) -> IndexedImage {
+        assert!(max_colors > 0, "Expected at least 1 color");
+        let max_colors = max_colors.min(256);
+
+        let mut colors: Vec<_> = self.histogram().into_iter().collect();
+        // Sort the colors, so the palette does not depend on the order of the hash map
+        colors.sort_by_key(|&(px, _)| (px.r, px.g, px.b));
+        if colors.is_empty() {
+            return IndexedImage::new(
+                self.width,
+                self.height,
+                Palette::new(vec![Pixel::new(0, 0, 0)]),
+            );
+        }
+        let mut boxes = vec![ColorBox { colors }];
+        while boxes.len() < max_colors {
+            // Split the box with the widest range of colors
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .filter(|&(_, b)| b.colors.len() > 1)
+                .max_by_key(|&(_, b)| b.widest_channel().1)
+                .map(|(i, _)| i);
+            match widest {
+                Some(i) => {
+                    let (lower, upper) = boxes.swap_remove(i).split();
+                    boxes.push(lower);
+                    boxes.push(upper);
+                }
+                None => break,
+            }
+        }
+
+        let palette = Palette::new(boxes.iter().map(ColorBox::average).collect());
+        let mut indices = HashMap::new();
+        for (i, b) in boxes.iter().enumerate() {
+            for &(px, _) in &b.colors {
+                indices.insert(px, i as u8);
+            }
+        }
+
+        let mut indexed = IndexedImage::new(self.width, self.height, palette);
+        for (x, y) in self.coordinates() {
+            indexed.set_index(x, y, indices[&self.get_pixel(x, y)]);
+        }
+        indexed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_cut_splits_the_widest_channel() {
+        let mut img = Image::new(4, 4);
+        for (x, y) in img.coordinates() {
+            // A wide range of red, and a narrow range of green
+            img.set_pixel(x, y, Pixel::new((x * 80) as u8, (y * 4) as u8, 0));
+        }
+
+        let indexed = img.quantize_median_cut(2);
+        let mut colors = indexed.palette().colors().to_vec();
+        colors.sort_by_key(|px| px.r);
+        assert_eq!(colors, vec![Pixel::new(40, 6, 0), Pixel::new(200, 6, 0)]);
+        for (x, y) in img.coordinates() {
+            assert_eq!(indexed.get_pixel(x, y).r, if x < 2 { 40 } else { 200 });
+        }
+
+        assert_eq!(img.quantize_median_cut(1000).palette().colors().len(), 16);
+        assert_eq!(img.quantize_median_cut(1000).to_image(), img);
+    }
+
+    #[test]
+    fn octree_merges_similar_colors() {
+        let mut img = Image::new(4, 4);
+        for (x, y) in img.coordinates() {
+            // Two groups of similar dark and bright colors
+            let base = if x < 2 { 10 } else { 240 };
+            img.set_pixel(x, y, Pixel::new(base + y as u8, base, base));
+        }
+
+        let indexed = img.quantize_octree(2);
+        let mut colors = indexed.palette().colors().to_vec();
+        colors.sort_by_key(|px| px.r);
+        assert_eq!(
+            colors,
+            vec![Pixel::new(12, 10, 10), Pixel::new(242, 240, 240)]
+        );
+        for (x, y) in img.coordinates() {
+            assert_eq!(indexed.get_pixel(x, y).g, if x < 2 { 10 } else { 240 });
+        }
+
+        for max_colors in 1..10 {
+            let indexed = img.quantize(max_colors, Quantizer::Octree);
+            assert!(indexed.palette().colors().len() <= max_colors);
+        }
+        assert_eq!(img.quantize_octree(256).to_image(), img);
+    }
+}
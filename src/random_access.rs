@@ -0,0 +1,170 @@
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use decoder;
+use {row_stride, BmpResult, Pixel};
+
+/// A BMP file that reads single pixels on demand, by seeking directly to their offset in the
+/// file instead of decoding the whole image.
+///
+/// The headers and the color palette are read once when the file is opened. Reading a pixel then
+/// costs a seek and a read of at most three bytes, which is much faster than a full decode for
+/// samplers that need a handful of pixels from many files. The pixels are the same as those of
+/// the decoded image, including black for palette indexes outside of the palette.
+///
+/// # Example
+///
+/// ```
+/// let mut file = bmp::BmpFile::open("test/rgbw.bmp").unwrap();
+/// assert_eq!((2, 2), (file.get_width(), file.get_height()));
+/// assert_eq!(bmp::consts::RED, file.get_pixel(0, 0).unwrap());
+/// assert_eq!(bmp::consts::WHITE, file.get_pixel(1, 1).unwrap());
+/// ```
+#[derive(Debug)]
+pub struct BmpFile<R> {
+    source: R,
+    width: u32,
+    height: u32,
+    bits_per_pixel: u16,
+    palette: Option<Vec<Pixel>>,
+    pixel_offset: u64,
+    stride: u64,
+}
+
+impl BmpFile<fs::File> {
+    /// Opens the BMP file specified by `path`, and reads its headers.
+    ///
+    /// Returns a `BmpError` if the file cannot be read, or if it isn't an uncompressed image that
+    /// can be decoded by this crate.
+    pub fn open<P: AsRef<Path>>(path: P) -> BmpResult<BmpFile<fs::File>> {
+        BmpFile::new(fs::File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> BmpFile<R> {
+    /// Reads the headers of the image in `source`, starting at its current position. See
+    /// `BmpFile::open` for details.
+    pub fn new(mut source: R) -> BmpResult<BmpFile<R>> {
+        let start = source.stream_position()?;
+        let headers = decoder::read_headers(io::BufReader::new(&mut source))?;
+        let (header, dib_header, palette) = headers;
+        let width = dib_header.width.unsigned_abs();
+        Ok(BmpFile {
+            source,
+            width,
+            height: dib_header.height.unsigned_abs(),
+            bits_per_pixel: dib_header.bits_per_pixel,
+            palette,
+            pixel_offset: start + header.pixel_offset as u64,
+            stride: row_stride(dib_header.bits_per_pixel, width),
+        })
+    }
+
+    /// Returns the `width` of the image.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the image.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Reads the pixel value at the position of `x` and `y` from the file.
+    ///
+    /// Returns a `BmpError` if the pixel cannot be read, for instance if the file is truncated.
+    /// Panics if the position is outside of the image.
+    pub fn get_pixel(&mut self, x: u32, y: u32) -> BmpResult<Pixel> {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside of the image",
+            x,
+            y
+        );
+        let bpp = self.bits_per_pixel as u64;
+        let bit = x as u64 * bpp;
+        // The rows are stored from bottom to top in the file
+        let row = (self.height - y - 1) as u64;
+        let offset = self.pixel_offset + row * self.stride + bit / 8;
+        self.source.seek(SeekFrom::Start(offset))?;
+
+        let palette = match self.palette {
+            Some(ref palette) => palette,
+            None => {
+                let mut bgr = [0; 3];
+                self.source.read_exact(&mut bgr)?;
+                return Ok(Pixel::new(bgr[2], bgr[1], bgr[0]));
+            }
+        };
+        let mut byte = [0];
+        self.source.read_exact(&mut byte)?;
+        // The pixels are packed from the most significant bit of each byte
+        let shift = 8 - bpp - bit % 8;
+        let index = (byte[0] >> shift) & (0xff >> (8 - bpp));
+        Ok(palette
+            .get(index as usize)
+            .cloned()
+            .unwrap_or(Pixel::new(0, 0, 0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use {BmpError, BmpErrorKind, Image};
+
+    #[test]
+    fn pixels_match_the_decoded_image() {
+        let names = [
+            "1bpp-321x240",
+            "4bpp-323x240",
+            "8bpp-322x240",
+            "24bpp-321x240",
+        ];
+        for name in &names {
+            let path = format!("test/bmptestsuite-0.9/valid/{}.bmp", name);
+            let img = ::open(&path).unwrap();
+            let mut file = BmpFile::open(&path).unwrap();
+            let (w, h) = (img.get_width(), img.get_height());
+            for &(x, y) in &[(0, 0), (w - 1, h - 1), (w / 2, h / 3), (17, 5), (w - 2, 0)] {
+                assert_eq!(
+                    img.get_pixel(x, y),
+                    file.get_pixel(x, y).unwrap(),
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn images_can_start_inside_of_a_stream() {
+        let mut img = Image::new(3, 2);
+        img.set_pixel(2, 1, ::consts::RED);
+        let mut bytes = vec![0; 5];
+        img.to_writer(&mut bytes).unwrap();
+
+        let mut source = Cursor::new(bytes);
+        source.set_position(5);
+        let mut file = BmpFile::new(source).unwrap();
+        assert_eq!(::consts::RED, file.get_pixel(2, 1).unwrap());
+    }
+
+    #[test]
+    fn compressed_images_are_rejected() {
+        match BmpFile::open("test/bmpsuite-2.5/g/pal8rle.bmp") {
+            Err(BmpError {
+                kind: BmpErrorKind::UnsupportedCompressionType,
+                ..
+            }) => (/* Expected */),
+            other => panic!(
+                "Expected an UnsupportedCompressionType error, but was {:?}",
+                other.err()
+            ),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::f32::consts::PI;
+
+use {Image, Pixel};
+
+/// The resampling filters used when resizing an `Image`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Filter {
+    /// Picks the closest source pixel. Fast, but blocky.
+    Nearest,
+    /// Linear interpolation between the closest source pixels.
+    Bilinear,
+    /// Cubic interpolation using the Catmull-Rom spline.
+    Bicubic,
+    /// Windowed sinc interpolation with three lobes. Sharpest, but the slowest.
+    Lanczos3,
+}
+
+impl Filter {
+    // The distance from the center where the kernel becomes zero
+    fn support(self) -> f32 {
+        match self {
+            Filter::Nearest => 0.5,
+            Filter::Bilinear => 1.0,
+            Filter::Bicubic => 2.0,
+            Filter::Lanczos3 => 3.0,
+        }
+    }
+
+    fn kernel(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Filter::Nearest => {
+                if x < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Bilinear => (1.0 - x).max(0.0),
+            Filter::Bicubic => catmull_rom(x),
+            Filter::Lanczos3 => {
+                if x < 3.0 {
+                    sinc(x) * sinc(x / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+fn catmull_rom(x: f32) -> f32 {
+    // Keys' cubic convolution kernel with a = -0.5
+    let a = -0.5;
+    if x < 1.0 {
+        ((a + 2.0) * x - (a + 3.0)) * x * x + 1.0
+    } else if x < 2.0 {
+        (((x - 5.0) * x + 8.0) * x - 4.0) * a
+    } else {
+        0.0
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = x * PI;
+        x.sin() / x
+    }
+}
+
+// The source indexes and normalized weights contributing to a single destination index
+struct Contribution {
+    start: usize,
+    weights: Vec<f32>,
+}
+
+fn contributions(src_size: u32, dst_size: u32, filter: Filter) -> Vec<Contribution> {
+    let ratio = src_size as f32 / dst_size as f32;
+    // Widen the kernel when downscaling, so every source pixel contributes
+    let scale = ratio.max(1.0);
+    let support = filter.support() * scale;
+
+    (0..dst_size)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * ratio;
+            let start = (center - support).floor().max(0.0) as usize;
+            let end = ((center + support).ceil() as usize).min(src_size as usize);
+
+            let mut weights: Vec<f32> = (start..end)
+                .map(|j| filter.kernel((j as f32 + 0.5 - center) / scale))
+                .collect();
+            let sum: f32 = weights.iter().sum();
+            if sum != 0.0 {
+                for w in &mut weights {
+                    *w /= sum;
+                }
+            } else {
+                // Fall back to the closest pixel if the kernel missed every source pixel
+                let closest = (center as usize).min(src_size as usize - 1);
+                return Contribution {
+                    start: closest,
+                    weights: vec![1.0],
+                };
+            }
+            Contribution { start, weights }
+        })
+        .collect()
+}
+
+fn to_channel(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+impl Image {
+    /// Returns a new `Image` of `width` and `height`, resampled from `self` using
+    /// nearest-neighbor interpolation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(100, 80);
+    /// let thumbnail = img.resize(25, 20);
+    /// assert_eq!((25, 20), (thumbnail.get_width(), thumbnail.get_height()));
+    /// ```
+    pub fn resize(&self, width: u32, height: u32) -> Image {
+        self.resize_with(width, height, Filter::Nearest)
+    }
+
+    /// Returns a new `Image` of `width` and `height`, resampled from `self` using the
+    /// interpolation specified by `filter`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Filter, Image};
+    ///
+    /// let img = Image::new(100, 80);
+    /// let thumbnail = img.resize_with(25, 20, Filter::Lanczos3);
+    /// assert_eq!((25, 20), (thumbnail.get_width(), thumbnail.get_height()));
+    /// ```
+    pub fn resize_with(&self, width: u32, height: u32, filter: Filter) -> Image {
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return Image::new(width, height);
+        }
+
+        // Resample the rows first, then the columns
+        let horizontal = contributions(self.width, width, filter);
+        let mut rows = Vec::with_capacity(width as usize * self.height as usize);
+        for row in self.data.chunks(self.width as usize) {
+            for c in &horizontal {
+                let mut sum = [0.0; 3];
+                for (px, w) in row[c.start..].iter().zip(&c.weights) {
+                    sum[0] += px.r as f32 * w;
+                    sum[1] += px.g as f32 * w;
+                    sum[2] += px.b as f32 * w;
+                }
+                rows.push(sum);
+            }
+        }
+
+        let vertical = contributions(self.height, height, filter);
+        let mut data = Vec::with_capacity(width as usize * height as usize);
+        for c in &vertical {
+            for x in 0..width as usize {
+                let mut sum = [0.0; 3];
+                for (i, w) in c.weights.iter().enumerate() {
+                    let px = rows[(c.start + i) * width as usize + x];
+                    sum[0] += px[0] * w;
+                    sum[1] += px[1] * w;
+                    sum[2] += px[2] * w;
+                }
+                data.push(Pixel::new(
+                    to_channel(sum[0]),
+                    to_channel(sum[1]),
+                    to_channel(sum[2]),
+                ));
+            }
+        }
+        Image::from_data(width, height, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn resizing_a_solid_image_keeps_the_color() {
+        let mut img = Image::new(7, 5);
+        img.fill(consts::ORANGE);
+
+        for &filter in &[
+            Filter::Nearest,
+            Filter::Bilinear,
+            Filter::Bicubic,
+            Filter::Lanczos3,
+        ] {
+            for &(w, h) in &[(3, 2), (7, 5), (20, 11)] {
+                let resized = img.resize_with(w, h, filter);
+                assert_eq!((resized.get_width(), resized.get_height()), (w, h));
+                assert!(resized.data.iter().all(|&px| px == consts::ORANGE));
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_neighbor_upscaling_repeats_pixels() {
+        let img = Image::from_raw_rgb(&[255, 0, 0, 0, 0, 255], 2, 1).unwrap();
+        let resized = img.resize(4, 2);
+        assert_eq!(resized.get_pixel(1, 1), consts::RED);
+        assert_eq!(resized.get_pixel(2, 0), consts::BLUE);
+    }
+
+    #[test]
+    fn bilinear_downscaling_averages_pixels() {
+        let img = Image::from_raw_rgb(&[0, 0, 0, 200, 100, 50], 2, 1).unwrap();
+        let resized = img.resize_with(1, 1, Filter::Bilinear);
+        assert_eq!(resized.get_pixel(0, 0), Pixel::new(100, 50, 25));
+    }
+}
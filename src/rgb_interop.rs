@@ -0,0 +1,106 @@
+use std::slice;
+
+use rgb::{RGB8, RGBA8};
+
+use {Pixel, PixelRgba};
+
+/// Converts an `RGB8` from the `rgb` crate into a `Pixel`.
+impl From<RGB8> for Pixel {
+    fn from(px: RGB8) -> Pixel {
+        Pixel::new(px.r, px.g, px.b)
+    }
+}
+
+/// Converts a `Pixel` into an `RGB8` from the `rgb` crate.
+impl From<Pixel> for RGB8 {
+    fn from(px: Pixel) -> RGB8 {
+        RGB8::new(px.r, px.g, px.b)
+    }
+}
+
+/// Converts a `Pixel` into a fully opaque `RGBA8` from the `rgb` crate.
+impl From<Pixel> for RGBA8 {
+    fn from(px: Pixel) -> RGBA8 {
+        RGBA8::new(px.r, px.g, px.b, 255)
+    }
+}
+
+/// Converts an `RGBA8` from the `rgb` crate into a `PixelRgba`.
+impl From<RGBA8> for PixelRgba {
+    fn from(px: RGBA8) -> PixelRgba {
+        PixelRgba::new(px.r, px.g, px.b, px.a)
+    }
+}
+
+/// Converts a `PixelRgba` into an `RGBA8` from the `rgb` crate.
+impl From<PixelRgba> for RGBA8 {
+    fn from(px: PixelRgba) -> RGBA8 {
+        RGBA8::new(px.r, px.g, px.b, px.a)
+    }
+}
+
+// Safety: `Pixel` and `RGB8` are both `repr(C)` structs of the `u8` fields `r`, `g` and `b`, in
+// that order, so they have the same size, alignment and layout.
+impl Pixel {
+    /// Returns the pixels in `pixels` as a slice of `RGB8` from the `rgb` crate, without copying.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate bmp;
+    /// extern crate rgb;
+    ///
+    /// # fn main() {
+    /// let img = bmp::Image::new(4, 4);
+    /// let row: &[rgb::RGB8] = bmp::Pixel::slice_as_rgb(img.row(0));
+    /// assert_eq!(rgb::RGB8::new(0, 0, 0), row[3]);
+    /// # }
+    /// ```
+    pub fn slice_as_rgb(pixels: &[Pixel]) -> &[RGB8] {
+        unsafe { slice::from_raw_parts(pixels.as_ptr() as *const RGB8, pixels.len()) }
+    }
+
+    /// Returns the pixels in `pixels` as a mutable slice of `RGB8` from the `rgb` crate, without
+    /// copying.
+    pub fn slice_as_rgb_mut(pixels: &mut [Pixel]) -> &mut [RGB8] {
+        unsafe { slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut RGB8, pixels.len()) }
+    }
+
+    /// Returns the `RGB8` values from the `rgb` crate in `pixels` as a slice of `Pixel`, without
+    /// copying.
+    pub fn slice_from_rgb(pixels: &[RGB8]) -> &[Pixel] {
+        unsafe { slice::from_raw_parts(pixels.as_ptr() as *const Pixel, pixels.len()) }
+    }
+
+    /// Returns the `RGB8` values from the `rgb` crate in `pixels` as a mutable slice of `Pixel`,
+    /// without copying.
+    pub fn slice_from_rgb_mut(pixels: &mut [RGB8]) -> &mut [Pixel] {
+        unsafe { slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut Pixel, pixels.len()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+    use std::mem;
+
+    #[test]
+    fn slices_share_the_same_pixels() {
+        assert_eq!(mem::size_of::<Pixel>(), mem::size_of::<RGB8>());
+        assert_eq!(mem::align_of::<Pixel>(), mem::align_of::<RGB8>());
+
+        let mut pixels = [consts::RED, consts::BLUE];
+        assert_eq!(Pixel::slice_as_rgb(&pixels)[1], RGB8::new(0, 0, 255));
+        Pixel::slice_as_rgb_mut(&mut pixels)[0].g = 255;
+        assert_eq!(pixels[0], consts::YELLOW);
+
+        let rgb = [RGB8::from(consts::LIME)];
+        assert_eq!(Pixel::slice_from_rgb(&rgb), &[consts::LIME]);
+        assert_eq!(RGBA8::from(consts::LIME), RGBA8::new(0, 255, 0, 255));
+        assert_eq!(
+            PixelRgba::from(RGBA8::new(1, 2, 3, 4)),
+            PixelRgba::new(1, 2, 3, 4)
+        );
+    }
+}
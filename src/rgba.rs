@@ -0,0 +1,269 @@
+use std::fmt;
+
+use transform::clip;
+use {ColorDistance, Image, ImageIndex, Pixel};
+
+/// A pixel with an alpha channel, used in the `ImageRgba`.
+///
+/// An alpha of 0 is fully transparent, and an alpha of 255 is fully opaque.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PixelRgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl PixelRgba {
+    /// Creates a new `PixelRgba`.
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> PixelRgba {
+        PixelRgba { r, g, b, a }
+    }
+}
+
+/// Creates a fully opaque `PixelRgba`.
+impl From<Pixel> for PixelRgba {
+    fn from(px: Pixel) -> PixelRgba {
+        PixelRgba::new(px.r, px.g, px.b, 255)
+    }
+}
+
+/// Displays the rgba values as an rgba color quadruple
+impl fmt::Display for PixelRgba {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a)
+    }
+}
+
+/// An in-memory image with an alpha channel.
+///
+/// It is used as the source when compositing onto an `Image` with `Image::composite_over`.
+/// Like the `Image`, it is accessed in row-major order from top to bottom, where point (0, 0) is
+/// defined to be in the upper left corner of the image.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImageRgba {
+    width: u32,
+    height: u32,
+    data: Vec<PixelRgba>,
+}
+
+impl ImageRgba {
+    /// Returns a new `ImageRgba` with the `width` and `height` specified. It is initialized to
+    /// a fully transparent image by default.
+    pub fn new(width: u32, height: u32) -> ImageRgba {
+        ImageRgba {
+            width,
+            height,
+            data: vec![PixelRgba::new(0, 0, 0, 0); width as usize * height as usize],
+        }
+    }
+
+    /// Returns the `width` of the image.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the image.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Set the pixel value at the position of `x` and `y`.
+    #[inline]
+    pub fn set_pixel(&mut self, x: u32, y: u32, val: PixelRgba) {
+        let i = self.index(x, y);
+        self.data[i] = val;
+    }
+
+    /// Returns the pixel value at the position of `x` and `y`.
+    #[inline]
+    pub fn get_pixel(&self, x: u32, y: u32) -> PixelRgba {
+        self.data[self.index(x, y)]
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width, "{} is outside of the image", x);
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the image dimensions in top-bottom order.
+    #[inline]
+    pub fn coordinates(&self) -> ImageIndex {
+        ImageIndex::new(self.width, self.height)
+    }
+
+    /// Returns a new `Image` with the alpha channel dropped.
+    pub fn to_image(&self) -> Image {
+        let mut img = Image::new(self.width, self.height);
+        for (x, y) in self.coordinates() {
+            let px = self.get_pixel(x, y);
+            img.set_pixel(x, y, Pixel::new(px.r, px.g, px.b));
+        }
+        img
+    }
+}
+
+/// Creates a fully opaque `ImageRgba`.
+impl<'a> From<&'a Image> for ImageRgba {
+    fn from(img: &'a Image) -> ImageRgba {
+        let mut rgba = ImageRgba::new(img.get_width(), img.get_height());
+        for (x, y) in img.coordinates() {
+            rgba.set_pixel(x, y, img.get_pixel(x, y).into());
+        }
+        rgba
+    }
+}
+
+// Computes `a * b / 255`, rounded to the closest integer
+#[inline]
+pub(crate) fn mul_div_255(a: u8, b: u8) -> u8 {
+    let t = a as u32 * b as u32 + 128;
+    ((t + (t >> 8)) >> 8) as u8
+}
+
+#[inline]
+pub(crate) fn blend_straight(dst: u8, src: u8, alpha: u8) -> u8 {
+    mul_div_255(src, alpha) + mul_div_255(dst, 255 - alpha)
+}
+
+#[inline]
+fn blend_premultiplied(dst: u8, src: u8, alpha: u8) -> u8 {
+    src.saturating_add(mul_div_255(dst, 255 - alpha))
+}
+
+impl Image {
+    /// Blends the pixels of `src` onto the image with the source-over operator, with the upper
+    /// left corner of `src` placed at `x` and `y`. The color channels of `src` are not
+    /// premultiplied by the alpha channel. The parts of `src` that fall outside of the image are
+    /// ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{Image, ImageRgba, Pixel, PixelRgba};
+    ///
+    /// let mut icon = ImageRgba::new(10, 10);
+    /// icon.set_pixel(0, 0, PixelRgba::new(255, 0, 0, 128));
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.composite_over(&icon, 0, 0);
+    /// assert_eq!(Pixel::new(128, 0, 0), img.get_pixel(0, 0));
+    /// ```
+    pub fn composite_over(&mut self, src: &ImageRgba, x: i64, y: i64) {
+        self.composite_with(src, x, y, blend_straight);
+    }
+
+    /// Blends the pixels of `src` onto the image like `composite_over`, but expects the color
+    /// channels of `src` to be premultiplied by the alpha channel.
+    pub fn composite_over_premultiplied(&mut self, src: &ImageRgba, x: i64, y: i64) {
+        self.composite_with(src, x, y, blend_premultiplied);
+    }
+
+    /// Returns a new `ImageRgba` where the pixels close to the color `key` are transparent, like
+    /// the green screen of a film studio. Pixels within the Euclidean distance `tolerance` of
+    /// `key` become fully transparent, and the alpha of the pixels up to twice as far away
+    /// increases gradually, which softens the edges. All other pixels are fully opaque.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::LIME);
+    /// img.set_pixel(0, 0, consts::RED);
+    ///
+    /// let keyed = img.chroma_key(consts::LIME, 40.0);
+    /// assert_eq!(0, keyed.get_pixel(50, 50).a);
+    /// assert_eq!(255, keyed.get_pixel(0, 0).a);
+    /// ```
+    pub fn chroma_key(&self, key: Pixel, tolerance: f32) -> ImageRgba {
+        let mut rgba = ImageRgba::from(self);
+        for px in &mut rgba.data {
+            let distance = ColorDistance::Euclidean.distance(Pixel::new(px.r, px.g, px.b), key);
+            px.a = if distance <= tolerance {
+                0
+            } else if distance < 2.0 * tolerance {
+                ((distance - tolerance) / tolerance * 255.0).round() as u8
+            } else {
+                255
+            };
+        }
+        rgba
+    }
+
+    fn composite_with(&mut self, src: &ImageRgba, x: i64, y: i64, blend: fn(u8, u8, u8) -> u8) {
+        let area = match clip(self, x, y, src.width, src.height) {
+            Some(area) => area,
+            None => return,
+        };
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let (dx, dy) = (area.dst_x + x, area.dst_y + y);
+                let s = src.get_pixel(area.src_x + x, area.src_y + y);
+                let d = self.get_pixel(dx, dy);
+                let px = Pixel::new(
+                    blend(d.r, s.r, s.a),
+                    blend(d.g, s.g, s.a),
+                    blend(d.b, s.b, s.a),
+                );
+                self.set_pixel(dx, dy, px);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn composite_over_blends_with_the_background() {
+        let mut src = ImageRgba::new(3, 1);
+        src.set_pixel(0, 0, PixelRgba::new(255, 255, 255, 255));
+        src.set_pixel(1, 0, PixelRgba::new(0, 0, 255, 64));
+
+        let mut img = Image::new(3, 1);
+        img.fill(consts::RED);
+        let mut premultiplied = img.clone();
+
+        img.composite_over(&src, 0, 0);
+        assert_eq!(img.get_pixel(0, 0), consts::WHITE);
+        assert_eq!(img.get_pixel(1, 0), Pixel::new(191, 0, 64));
+        assert_eq!(img.get_pixel(2, 0), consts::RED);
+
+        src.set_pixel(1, 0, PixelRgba::new(0, 0, 64, 64));
+        premultiplied.composite_over_premultiplied(&src, 0, 0);
+        assert_eq!(premultiplied.get_pixel(1, 0), Pixel::new(191, 0, 64));
+    }
+
+    #[test]
+    fn composite_over_is_clipped_to_the_image() {
+        let mut src = ImageRgba::from(&Image::new(2, 2));
+        src.set_pixel(1, 1, PixelRgba::new(0, 255, 0, 255));
+
+        let mut img = Image::new(2, 2);
+        img.fill(consts::WHITE);
+        img.composite_over(&src, -1, -1);
+        assert_eq!(img.get_pixel(0, 0), consts::LIME);
+        assert_eq!(img.get_pixel(1, 1), consts::WHITE);
+    }
+
+    #[test]
+    fn chroma_key_softens_the_edges() {
+        let mut img = Image::new(4, 1);
+        img.set_pixel(0, 0, Pixel::new(0, 255, 0));
+        img.set_pixel(1, 0, Pixel::new(0, 245, 0));
+        img.set_pixel(2, 0, Pixel::new(0, 225, 0));
+        img.set_pixel(3, 0, Pixel::new(0, 200, 0));
+
+        let keyed = img.chroma_key(Pixel::new(0, 255, 0), 20.0);
+        let alpha: Vec<u8> = keyed.data.iter().map(|px| px.a).collect();
+        assert_eq!(alpha, vec![0, 0, 128, 255]);
+        assert_eq!(keyed.get_pixel(2, 0), PixelRgba::new(0, 225, 0, 128));
+    }
+}
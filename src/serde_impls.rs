@@ -0,0 +1,154 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, SerializeTuple, Serializer};
+
+use {ChannelOrder, Image, Pixel};
+
+/// Serializes the `Pixel` as a compact `(r, g, b)` tuple.
+impl Serialize for Pixel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(3)?;
+        tuple.serialize_element(&self.r)?;
+        tuple.serialize_element(&self.g)?;
+        tuple.serialize_element(&self.b)?;
+        tuple.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Pixel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pixel, D::Error> {
+        let (r, g, b) = <(u8, u8, u8)>::deserialize(deserializer)?;
+        Ok(Pixel::new(r, g, b))
+    }
+}
+
+// The pixel data as tightly packed rgb bytes, serialized as bytes instead of a sequence of
+// numbers for formats that support it
+struct Bytes(Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> Visitor<'de> for BytesVisitor {
+            type Value = Bytes;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a byte array")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Bytes, E> {
+                Ok(Bytes(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Bytes, E> {
+                Ok(Bytes(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Bytes, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(b) = seq.next_element()? {
+                    bytes.push(b);
+                }
+                Ok(Bytes(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+const FIELDS: &[&str] = &["width", "height", "data"];
+
+/// Serializes the `Image` as a struct of the `width`, the `height` and the `data` of the pixels
+/// as tightly packed 8-bit `r`, `g` and `b` values, stored in row-major order from top to bottom.
+impl Serialize for Image {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut image = serializer.serialize_struct("Image", FIELDS.len())?;
+        image.serialize_field("width", &self.width)?;
+        image.serialize_field("height", &self.height)?;
+        image.serialize_field("data", &Bytes(self.as_raw(ChannelOrder::Rgb)))?;
+        image.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Image {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Image, D::Error> {
+        struct ImageVisitor;
+
+        impl ImageVisitor {
+            fn image<E: de::Error>(width: u32, height: u32, data: Bytes) -> Result<Image, E> {
+                Image::from_raw_rgb(&data.0, width, height).map_err(E::custom)
+            }
+        }
+
+        impl<'de> Visitor<'de> for ImageVisitor {
+            type Value = Image;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an image with the fields width, height and data")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Image, A::Error> {
+                let width = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let height = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let data = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                ImageVisitor::image(width, height, data)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Image, A::Error> {
+                let (mut width, mut height, mut data) = (None, None, None);
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "width" => width = Some(map.next_value()?),
+                        "height" => height = Some(map.next_value()?),
+                        "data" => data = Some(map.next_value()?),
+                        other => return Err(de::Error::unknown_field(other, FIELDS)),
+                    }
+                }
+                ImageVisitor::image(
+                    width.ok_or_else(|| de::Error::missing_field("width"))?,
+                    height.ok_or_else(|| de::Error::missing_field("height"))?,
+                    data.ok_or_else(|| de::Error::missing_field("data"))?,
+                )
+            }
+        }
+
+        deserializer.deserialize_struct("Image", FIELDS, ImageVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use consts;
+
+    #[test]
+    fn images_round_trip_through_json() {
+        let mut img = Image::new(2, 1);
+        img.set_pixel(1, 0, consts::RED);
+
+        let json = serde_json::to_string(&img).unwrap();
+        assert_eq!(json, r#"{"width":2,"height":1,"data":[0,0,0,255,0,0]}"#);
+        assert_eq!(serde_json::from_str::<Image>(&json).unwrap(), img);
+
+        assert_eq!(serde_json::to_string(&consts::RED).unwrap(), "[255,0,0]");
+        let wrong_size = r#"{"width":2,"height":2,"data":[0,0,0]}"#;
+        assert!(serde_json::from_str::<Image>(wrong_size).is_err());
+    }
+}
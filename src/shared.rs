@@ -0,0 +1,95 @@
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use Image;
+
+/// A reference-counted `Image` with copy-on-write semantics.
+///
+/// Cloning a `SharedImage` only increments a reference count, so one decoded image can be handed
+/// to many consumers, e.g. on different threads, without copying the pixels. The pixels are only
+/// copied when a clone is modified while it is still shared.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, Image};
+///
+/// let original = Image::new(100, 80).into_shared();
+/// let mut copy = original.clone();
+/// assert!(original.ptr_eq(&copy));
+///
+/// copy.make_mut().set_pixel(10, 10, consts::RED);
+/// assert!(!original.ptr_eq(&copy));
+/// assert_eq!(consts::BLACK, original.get_pixel(10, 10));
+/// assert_eq!(consts::RED, copy.get_pixel(10, 10));
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct SharedImage {
+    image: Arc<Image>,
+}
+
+impl SharedImage {
+    /// Returns a mutable reference to the image, copying it first if it is shared with other
+    /// clones.
+    pub fn make_mut(&mut self) -> &mut Image {
+        Arc::make_mut(&mut self.image)
+    }
+
+    /// Returns the `Image`, copying it only if it is shared with other clones.
+    pub fn into_image(self) -> Image {
+        Arc::try_unwrap(self.image).unwrap_or_else(|image| (*image).clone())
+    }
+
+    /// Returns whether the two images share the same pixels.
+    pub fn ptr_eq(&self, other: &SharedImage) -> bool {
+        Arc::ptr_eq(&self.image, &other.image)
+    }
+}
+
+impl Deref for SharedImage {
+    type Target = Image;
+
+    fn deref(&self) -> &Image {
+        &self.image
+    }
+}
+
+impl From<Image> for SharedImage {
+    fn from(image: Image) -> SharedImage {
+        SharedImage {
+            image: Arc::new(image),
+        }
+    }
+}
+
+impl fmt::Debug for SharedImage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.image, f)
+    }
+}
+
+impl Image {
+    /// Converts the `Image` into a `SharedImage` that can be cloned without copying the pixels.
+    pub fn into_shared(self) -> SharedImage {
+        SharedImage::from(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    #[test]
+    fn unique_images_are_not_copied() {
+        let mut shared = Image::new(4, 4).into_shared();
+        let pixels = shared.data.as_ptr();
+        shared.make_mut().set_pixel(1, 1, consts::RED);
+        assert_eq!(pixels, shared.data.as_ptr());
+
+        let copy = shared.clone();
+        drop(shared);
+        assert_eq!(pixels, copy.into_image().data.as_ptr());
+    }
+}
@@ -0,0 +1,45 @@
+//! Bulk conversion between the BGR byte order of 24 bpp rows and `Pixel`s.
+//!
+//! The loops work on whole rows with fixed-size chunks, so the bounds checks are hoisted and the
+//! compiler is free to vectorize them.
+
+use Pixel;
+
+/// Appends the pixels of a row of BGR triplets in `bytes` to `data`. Trailing bytes that don't
+/// form a whole pixel are ignored.
+pub(crate) fn bgr_to_pixels(bytes: &[u8], data: &mut Vec<Pixel>) {
+    data.extend(
+        bytes
+            .chunks_exact(3)
+            .map(|bgr| Pixel::new(bgr[2], bgr[1], bgr[0])),
+    );
+}
+
+/// Writes the pixels in `data` as BGR triplets to the start of `bytes`.
+///
+/// Panics if `bytes` is shorter than three bytes per pixel.
+pub(crate) fn pixels_to_bgr(data: &[Pixel], bytes: &mut [u8]) {
+    assert!(bytes.len() >= data.len() * 3, "The row buffer is too short");
+    for (bgr, px) in bytes.chunks_exact_mut(3).zip(data) {
+        bgr[0] = px.b;
+        bgr[1] = px.g;
+        bgr[2] = px.r;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversion_round_trips_through_bgr() {
+        let bytes = [1, 2, 3, 4, 5, 6, 7];
+        let mut data = Vec::new();
+        bgr_to_pixels(&bytes, &mut data);
+        assert_eq!(data, vec![Pixel::new(3, 2, 1), Pixel::new(6, 5, 4)]);
+
+        let mut out = [0; 7];
+        pixels_to_bgr(&data, &mut out);
+        assert_eq!(out, [1, 2, 3, 4, 5, 6, 0]);
+    }
+}
@@ -0,0 +1,123 @@
+use {BmpError, BmpErrorKind, BmpResult, Image};
+
+impl Image {
+    /// Splits a sprite sheet into tiles of `tile_width` times `tile_height` pixels, and returns
+    /// copies of them in row-major order, starting in the upper left corner.
+    ///
+    /// Only whole tiles are returned, the pixels left over along the right and bottom edges are
+    /// ignored. Use `tiles` to also get the clipped tiles along the edges, without copying.
+    ///
+    /// Panics if `tile_width` or `tile_height` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let sheet = bmp::Image::new(100, 40);
+    /// let sprites = sheet.slice_grid(32, 32);
+    /// assert_eq!(3, sprites.len());
+    /// assert_eq!((32, 32), (sprites[2].get_width(), sprites[2].get_height()));
+    /// ```
+    pub fn slice_grid(&self, tile_width: u32, tile_height: u32) -> Vec<Image> {
+        self.tiles(tile_width, tile_height)
+            .filter(|tile| tile.get_width() == tile_width && tile.get_height() == tile_height)
+            .map(|tile| tile.to_image())
+            .collect()
+    }
+}
+
+/// Packs `images` into a sprite sheet with `columns` cells per row, in row-major order.
+///
+/// Every cell has the size of the widest and the tallest image, and each image is placed in the
+/// upper left corner of its cell. The image at `i` is thus at `(i % columns * cell_width,
+/// i / columns * cell_height)`, and `slice_grid` with the cell size splits the sheet again.
+/// The unused parts of the sheet are black. With fewer images than `columns`, the sheet is only
+/// as wide as the images in its single row, instead of ending with empty cells.
+///
+/// Returns a `BmpError` if there are no images or columns, or if the sheet would exceed the
+/// default `Limits`.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, Image};
+///
+/// let mut sprite = Image::new(16, 16);
+/// sprite.fill(consts::RED);
+/// let sprites = vec![sprite; 5];
+///
+/// let sheet = bmp::pack_sheet(&sprites, 4).unwrap();
+/// assert_eq!((64, 32), (sheet.get_width(), sheet.get_height()));
+/// assert_eq!(consts::RED, sheet.get_pixel(0, 16));
+/// assert_eq!(consts::BLACK, sheet.get_pixel(16, 16));
+/// assert_eq!(sprites, sheet.slice_grid(16, 16)[..5]);
+/// ```
+pub fn pack_sheet(images: &[Image], columns: u32) -> BmpResult<Image> {
+    if images.is_empty() || columns == 0 {
+        return Err(BmpError::mismatch(
+            BmpErrorKind::InvalidDimensions,
+            "sheet",
+            "at least one image and column",
+            format!("{} images in {} columns", images.len(), columns),
+        ));
+    }
+    let cell_width = images.iter().map(Image::get_width).max().unwrap_or(0);
+    let cell_height = images.iter().map(Image::get_height).max().unwrap_or(0);
+    let count = images.len().min(u32::MAX as usize) as u32;
+    // A single row of images doesn't need empty cells after it
+    let columns = columns.min(count);
+    let rows = count.div_ceil(columns);
+
+    let mut sheet = Image::try_new(
+        cell_width.saturating_mul(columns),
+        cell_height.saturating_mul(rows),
+    )?;
+    for (i, img) in (0..count).zip(images) {
+        let x = (i % columns) as i64 * cell_width as i64;
+        let y = (i / columns) as i64 * cell_height as i64;
+        sheet.paste(img, x, y);
+    }
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {consts, Pixel};
+
+    #[test]
+    fn slicing_a_packed_sheet_returns_the_sprites() {
+        let sprites: Vec<Image> = (0..6u8)
+            .map(|i| {
+                let mut img = Image::new(3, 2);
+                img.fill(Pixel::new(i, i, i));
+                img.set_pixel(0, 0, consts::WHITE);
+                img
+            })
+            .collect();
+
+        let sheet = pack_sheet(&sprites, 4).unwrap();
+        assert_eq!((12, 4), (sheet.get_width(), sheet.get_height()));
+        assert_eq!(sprites, sheet.slice_grid(3, 2)[..6]);
+        assert_eq!(Pixel::new(5, 5, 5), sheet.get_pixel(5, 3));
+    }
+
+    #[test]
+    fn sprites_of_different_sizes_are_placed_in_equal_cells() {
+        let sprites = [Image::new(2, 5), Image::new(4, 1), Image::new(1, 1)];
+        let sheet = pack_sheet(&sprites, 10).unwrap();
+        assert_eq!((12, 5), (sheet.get_width(), sheet.get_height()));
+    }
+
+    #[test]
+    fn partial_tiles_are_skipped() {
+        let sheet = Image::new(10, 7);
+        assert_eq!(6, sheet.slice_grid(3, 3).len());
+        assert!(sheet.slice_grid(11, 1).is_empty());
+    }
+
+    #[test]
+    fn empty_sheets_are_rejected() {
+        assert!(pack_sheet(&[], 4).is_err());
+        assert!(pack_sheet(&[Image::new(1, 1)], 0).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use proptest::prelude::*;
+
+use {Image, Pixel};
+
+/// Returns a strategy that generates pixels of any color.
+pub fn any_pixel() -> impl Strategy<Value = Pixel> {
+    any::<(u8, u8, u8)>().prop_map(|(r, g, b)| Pixel::new(r, g, b))
+}
+
+/// Returns a strategy that generates images of any pixels, with a width of up to `max_width` and
+/// a height of up to `max_height`. Images shrink towards smaller and darker images.
+///
+/// # Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate proptest;
+/// extern crate bmp;
+///
+/// use bmp::strategies::any_image;
+/// use proptest::test_runner::TestRunner;
+///
+/// # fn main() {
+/// TestRunner::default()
+///     .run(&any_image(16, 16), |img| {
+///         prop_assert_eq!(img.flip_horizontal().flip_horizontal(), img);
+///         Ok(())
+///     })
+///     .unwrap();
+/// # }
+/// ```
+pub fn any_image(max_width: u32, max_height: u32) -> impl Strategy<Value = Image> {
+    (0..=max_width, 0..=max_height).prop_flat_map(|(width, height)| {
+        let len = width as usize * height as usize;
+        proptest::collection::vec(any_pixel(), len).prop_map(move |pixels| {
+            let mut img = Image::new(width, height);
+            for (px, (x, y)) in pixels.into_iter().zip(img.coordinates()) {
+                img.set_pixel(x, y, px);
+            }
+            img
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use from_reader;
+
+    proptest! {
+        #[test]
+        fn encoding_and_decoding_is_the_identity(img in any_image(12, 12)) {
+            let mut bytes = Vec::new();
+            img.to_writer(&mut bytes).unwrap();
+            let decoded = from_reader(&mut &bytes[..]).unwrap();
+            prop_assert_eq!(decoded.as_raw_rgb(), img.as_raw_rgb());
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::convert::TryFrom;
+
+use fontdue::Font;
+
+use rgba::blend_straight;
+use {Image, Paint, Pixel};
+
+impl Image {
+    /// Draws `text` with the TrueType or OpenType `font` at a size of `size` pixels per em, in
+    /// the color `val`. The upper left corner of the first line is placed at `x` and `y`, and
+    /// each `'\n'` starts a new line below it. The edges of the glyphs are blended with the image
+    /// according to their coverage, on top of the `BlendMode` of a `Paint`.
+    /// The parts of the text that fall outside of the image are ignored.
+    ///
+    /// Returns the width in pixels of the widest line.
+    ///
+    /// This function is only available with the `ttf` feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// extern crate bmp;
+    /// extern crate fontdue;
+    ///
+    /// # fn main() {
+    /// let data = std::fs::read("DejaVuSans.ttf").unwrap();
+    /// let font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default()).unwrap();
+    ///
+    /// let mut img = bmp::Image::new(200, 40);
+    /// img.draw_text_ttf(&font, 24.0, 5, 5, "Hello, world!", bmp::consts::WHITE);
+    /// # }
+    /// ```
+    pub fn draw_text_ttf<P: Into<Paint>>(
+        &mut self,
+        font: &Font,
+        size: f32,
+        x: i64,
+        y: i64,
+        text: &str,
+        val: P,
+    ) -> u32 {
+        let val = val.into();
+        let (ascent, line_height) = match font.horizontal_line_metrics(size) {
+            Some(metrics) => (metrics.ascent, metrics.new_line_size),
+            None => (size, size),
+        };
+
+        let mut widest = 0.0f32;
+        for (i, line) in text.split('\n').enumerate() {
+            let baseline = y as f32 + ascent + i as f32 * line_height;
+            let mut pen = x as f32;
+            let mut previous = None;
+            for c in line.chars() {
+                if let Some(kern) = previous.and_then(|p| font.horizontal_kern(p, c, size)) {
+                    pen += kern;
+                }
+                let (metrics, coverage) = font.rasterize(c, size);
+                let left = pen.round() as i64 + metrics.xmin as i64;
+                let top = baseline.round() as i64 - metrics.height as i64 - metrics.ymin as i64;
+                for (j, &alpha) in coverage.iter().enumerate() {
+                    let gx = u32::try_from(left + (j % metrics.width) as i64);
+                    let gy = u32::try_from(top + (j / metrics.width) as i64);
+                    let (gx, gy) = match (gx, gy) {
+                        (Ok(gx), Ok(gy)) if alpha > 0 => (gx, gy),
+                        _ => continue,
+                    };
+                    if let Some(dst) = self.try_get_pixel(gx, gy) {
+                        // Scale the painted color by the coverage of the pixel
+                        let src = val.apply(dst);
+                        let px = Pixel::new(
+                            blend_straight(dst.r, src.r, alpha),
+                            blend_straight(dst.g, src.g, alpha),
+                            blend_straight(dst.b, src.b, alpha),
+                        );
+                        self.set_pixel(gx, gy, px);
+                    }
+                }
+                pen += metrics.advance_width;
+                previous = Some(c);
+            }
+            widest = widest.max(pen - x as f32);
+        }
+        widest.ceil() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fontdue::{Font, FontSettings};
+    use {consts, Image};
+
+    fn font() -> Font {
+        let data = include_bytes!("../test/fonts/demo.ttf");
+        Font::from_bytes(&data[..], FontSettings::default()).unwrap()
+    }
+
+    fn painted(img: &Image) -> usize {
+        img.coordinates()
+            .filter(|&(x, y)| img.get_pixel(x, y) != consts::BLACK)
+            .count()
+    }
+
+    #[test]
+    fn text_is_drawn_below_and_right_of_the_position() {
+        let font = font();
+        let mut img = Image::new(40, 40);
+        let width = img.draw_text_ttf(&font, 20.0, 10, 10, "A", consts::WHITE);
+        assert_eq!(width, 11);
+        assert!(painted(&img) > 0);
+        assert!(img
+            .coordinates()
+            .filter(|&(x, y)| img.get_pixel(x, y) != consts::BLACK)
+            .all(|(x, y)| x >= 10 && y >= 10 && x < 10 + width));
+    }
+
+    #[test]
+    fn text_outside_of_the_image_is_ignored() {
+        let font = font();
+        let mut img = Image::new(40, 40);
+        // A position that wraps around to the image when truncated to a u32
+        let far = 1i64 << 32;
+        img.draw_text_ttf(&font, 20.0, far + 10, 10, "A", consts::WHITE);
+        img.draw_text_ttf(&font, 20.0, 10, far + 10, "A", consts::WHITE);
+        img.draw_text_ttf(&font, 20.0, -far, -far, "A", consts::WHITE);
+        assert_eq!(painted(&img), 0);
+    }
+}
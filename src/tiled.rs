@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use {BmpResult, Decoder, Image, Pipeline, Pixel, RowDecoder};
+
+/// An image in a BMP file that is loaded one tile at a time, as its pixels are accessed.
+///
+/// Each tile is decoded from the file with `Decoder::with_region` and `Decoder::decode_seekable`
+/// the first time one of its pixels is read or written, so very large images can be browsed and
+/// edited while only the rows of the tiles that are used are read, and only the tiles are kept
+/// in memory. The edits are written back to the file with `flush`, or to
+/// another file with `save`, by streaming the file through a `Pipeline`.
+///
+/// # Example
+///
+/// ```
+/// use bmp::{consts, TiledImage};
+///
+/// let source = "test/bmptestsuite-0.9/valid/24bpp-320x240.bmp";
+/// let mut img = TiledImage::open(source, 64, 64).unwrap();
+/// img.set_pixel(300, 10, consts::RED).unwrap();
+/// assert_eq!(consts::RED, img.get_pixel(300, 10).unwrap());
+/// assert_eq!(1, img.loaded_tiles());
+///
+/// let path = std::env::temp_dir().join("tiled-edited.bmp");
+/// img.save(&path).unwrap();
+/// let edited = bmp::open(&path).unwrap();
+/// assert_eq!(consts::RED, edited.get_pixel(300, 10));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TiledImage {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tiles: HashMap<(u32, u32), Tile>,
+}
+
+#[derive(Debug)]
+struct Tile {
+    image: Image,
+    dirty: bool,
+}
+
+impl TiledImage {
+    /// Reads the headers of the BMP file specified by `path`, and returns a `TiledImage` with
+    /// tiles of `tile_width` times `tile_height` pixels. No pixels are read until they are
+    /// accessed.
+    ///
+    /// Returns a `BmpError` if the file cannot be decoded. Panics if `tile_width` or `tile_height`
+    /// is zero.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        tile_width: u32,
+        tile_height: u32,
+    ) -> BmpResult<TiledImage> {
+        assert!(
+            tile_width > 0 && tile_height > 0,
+            "The tiles must be at least 1x1"
+        );
+        let path = path.as_ref().to_path_buf();
+        let rows = RowDecoder::new(io::BufReader::new(fs::File::open(&path)?))?;
+        Ok(TiledImage {
+            path,
+            width: rows.get_width(),
+            height: rows.get_height(),
+            tile_width,
+            tile_height,
+            tiles: HashMap::new(),
+        })
+    }
+
+    /// Returns the `width` of the image.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the image.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the number of tiles that are loaded into memory.
+    #[inline]
+    pub fn loaded_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns the pixel value at the position of `x` and `y`, and loads its tile if it isn't
+    /// loaded yet.
+    ///
+    /// Returns a `BmpError` if the tile cannot be decoded. Panics if the position is outside of
+    /// the image.
+    pub fn get_pixel(&mut self, x: u32, y: u32) -> BmpResult<Pixel> {
+        let (tile, x, y) = self.locate(x, y)?;
+        Ok(tile.image.get_pixel(x, y))
+    }
+
+    /// Set the pixel value at the position of `x` and `y`, and loads its tile if it isn't loaded
+    /// yet. The edit is kept in memory until the image is flushed.
+    ///
+    /// Returns a `BmpError` if the tile cannot be decoded. Panics if the position is outside of
+    /// the image.
+    pub fn set_pixel(&mut self, x: u32, y: u32, val: Pixel) -> BmpResult<()> {
+        let (tile, x, y) = self.locate(x, y)?;
+        tile.image.set_pixel(x, y, val);
+        tile.dirty = true;
+        Ok(())
+    }
+
+    /// Returns the tile in column `tile_x` and row `tile_y` of the tiles, to edit many pixels at
+    /// once. The tile is loaded if it isn't loaded yet, and is written when the image is flushed.
+    /// The tiles along the right and bottom edges are clipped to the image.
+    ///
+    /// Returns a `BmpError` if the tile cannot be decoded. Panics if the tile is outside of the
+    /// image.
+    pub fn tile_mut(&mut self, tile_x: u32, tile_y: u32) -> BmpResult<&mut Image> {
+        let (x, y) = (
+            tile_x as u64 * self.tile_width as u64,
+            tile_y as u64 * self.tile_height as u64,
+        );
+        assert!(
+            x < self.width as u64 && y < self.height as u64,
+            "The tile ({}, {}) is outside of the image",
+            tile_x,
+            tile_y
+        );
+        let tile = self.load(tile_x, tile_y)?;
+        tile.dirty = true;
+        Ok(&mut tile.image)
+    }
+
+    /// Drops the loaded tiles that haven't been edited since the image was last flushed, to free
+    /// their memory. They are loaded from the file again when they are accessed.
+    pub fn unload_clean_tiles(&mut self) {
+        self.tiles.retain(|_, tile| tile.dirty);
+    }
+
+    /// Writes the image with every edit to the file specified by `path`, with 24 bits per pixel.
+    /// The rows of the source file are streamed to the new file one at a time.
+    ///
+    /// Returns a `BmpError` if the source file cannot be read, or if the new file cannot be
+    /// written. `path` must not be the source file, use `flush` for that.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> BmpResult<()> {
+        let tiles = &self.tiles;
+        let (tile_width, tile_height) = (self.tile_width, self.tile_height);
+        let mut pipeline = Pipeline::new().map_rows(|y, row| {
+            let tile_y = y / tile_height;
+            let dirty = tiles
+                .iter()
+                .filter(|&(&(_, ty), tile)| ty == tile_y && tile.dirty);
+            for (&(tile_x, _), tile) in dirty {
+                let start = (tile_x * tile_width) as usize;
+                let pixels = tile.image.row(y - tile_y * tile_height);
+                row[start..start + pixels.len()].copy_from_slice(pixels);
+            }
+        });
+        pipeline.run_file(&self.path, path)
+    }
+
+    /// Writes every edit back to the source file, which is replaced by a file with 24 bits per
+    /// pixel. The image is first written to a temporary file next to the source file, which is
+    /// then renamed to the source file.
+    ///
+    /// Returns a `BmpError` if the file cannot be written, in which case the source file is left
+    /// as it was.
+    pub fn flush(&mut self) -> BmpResult<()> {
+        if self.tiles.values().all(|tile| !tile.dirty) {
+            return Ok(());
+        }
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        self.save(&temp)?;
+        fs::rename(&temp, &self.path)?;
+        for tile in self.tiles.values_mut() {
+            tile.dirty = false;
+        }
+        Ok(())
+    }
+
+    // Returns the tile of the pixel at `x` and `y`, and the position of the pixel in the tile
+    fn locate(&mut self, x: u32, y: u32) -> BmpResult<(&mut Tile, u32, u32)> {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside of the image",
+            x,
+            y
+        );
+        let (tile_width, tile_height) = (self.tile_width, self.tile_height);
+        let tile = self.load(x / tile_width, y / tile_height)?;
+        Ok((tile, x % tile_width, y % tile_height))
+    }
+
+    fn load(&mut self, tile_x: u32, tile_y: u32) -> BmpResult<&mut Tile> {
+        if !self.tiles.contains_key(&(tile_x, tile_y)) {
+            let (x, y) = (tile_x * self.tile_width, tile_y * self.tile_height);
+            let width = self.tile_width.min(self.width - x);
+            let height = self.tile_height.min(self.height - y);
+            let source = io::BufReader::new(fs::File::open(&self.path)?);
+            let image = Decoder::new()
+                .with_keep_indexed(false)
+                .with_region(x, y, width, height)
+                .decode_seekable(source)?;
+            let tile = Tile {
+                image,
+                dirty: false,
+            };
+            self.tiles.insert((tile_x, tile_y), tile);
+        }
+        Ok(self
+            .tiles
+            .get_mut(&(tile_x, tile_y))
+            .expect("The tile was just loaded"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use consts;
+
+    const SOURCE: &str = "test/bmptestsuite-0.9/valid/8bpp-320x240.bmp";
+
+    #[test]
+    fn pixels_match_the_decoded_image() {
+        let img = ::open(SOURCE).unwrap();
+        let mut tiled = TiledImage::open(SOURCE, 100, 50).unwrap();
+        assert_eq!((320, 240), (tiled.get_width(), tiled.get_height()));
+        for &(x, y) in &[(0, 0), (319, 239), (150, 120), (300, 10)] {
+            assert_eq!(img.get_pixel(x, y), tiled.get_pixel(x, y).unwrap());
+        }
+        assert_eq!(4, tiled.loaded_tiles());
+
+        tiled.unload_clean_tiles();
+        assert_eq!(0, tiled.loaded_tiles());
+    }
+
+    #[test]
+    fn edits_are_flushed_to_the_file() {
+        let path = &::tests::temp_path("tiled-flush.bmp");
+        fs::copy(SOURCE, path).unwrap();
+        let mut expected = ::open(SOURCE).unwrap();
+
+        let mut tiled = TiledImage::open(path, 64, 64).unwrap();
+        tiled.set_pixel(319, 239, consts::RED).unwrap();
+        tiled.tile_mut(1, 1).unwrap().fill(consts::BLUE);
+        tiled.get_pixel(0, 0).unwrap();
+        tiled.flush().unwrap();
+
+        expected.set_pixel(319, 239, consts::RED);
+        expected.view_mut(64, 64, 64, 64).fill(consts::BLUE);
+        let flushed = ::open(path).unwrap();
+        assert_eq!(expected.data, flushed.data);
+        assert_eq!(24, flushed.source_format().unwrap().bits_per_pixel);
+
+        // The tiles are loaded from the flushed file again
+        tiled.unload_clean_tiles();
+        assert_eq!(0, tiled.loaded_tiles());
+        assert_eq!(consts::RED, tiled.get_pixel(319, 239).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    fn pixels_outside_of_the_image_panic() {
+        let mut tiled = TiledImage::open(SOURCE, 64, 64).unwrap();
+        let _ = tiled.get_pixel(320, 0);
+    }
+}
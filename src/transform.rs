@@ -0,0 +1,471 @@
+use {BlendMode, BmpError, BmpErrorKind, BmpResult, Image, Pixel, SubImage};
+
+/// The position of an image within a larger area, used when padding an image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // The offset of an item of `size` placed in `area` along both axes, as fractions of the space
+    // left over
+    fn offset(self, size: (u32, u32), area: (u32, u32)) -> (i64, i64) {
+        let (fx, fy) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        };
+        let x = (area.0 as i64 - size.0 as i64) * fx / 2;
+        let y = (area.1 as i64 - size.1 as i64) * fy / 2;
+        (x, y)
+    }
+}
+
+// The part of a source placed on a destination image that overlaps the destination
+pub(crate) struct Overlap {
+    pub src_x: u32,
+    pub src_y: u32,
+    pub dst_x: u32,
+    pub dst_y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Clips a `width` by `height` source placed at `x` and `y` to the destination `dst`
+pub(crate) fn clip(dst: &Image, x: i64, y: i64, width: u32, height: u32) -> Option<Overlap> {
+    let x_start = x.max(0);
+    let y_start = y.max(0);
+    let x_end = x.saturating_add(width as i64).min(dst.width as i64);
+    let y_end = y.saturating_add(height as i64).min(dst.height as i64);
+    if x_start >= x_end || y_start >= y_end {
+        return None;
+    }
+    Some(Overlap {
+        src_x: (x_start - x) as u32,
+        src_y: (y_start - y) as u32,
+        dst_x: x_start as u32,
+        dst_y: y_start as u32,
+        width: (x_end - x_start) as u32,
+        height: (y_end - y_start) as u32,
+    })
+}
+
+impl Image {
+    /// Returns a new `Image` containing a copy of the rectangle with the upper left corner at `x`
+    /// and `y`, and the size of `width` and `height`.
+    ///
+    /// Returns a `BmpError` if the rectangle is not inside the image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.set_pixel(10, 20, bmp::consts::RED);
+    ///
+    /// let crop = img.crop(10, 20, 30, 30).unwrap();
+    /// assert_eq!((30, 30), (crop.get_width(), crop.get_height()));
+    /// assert_eq!(bmp::consts::RED, crop.get_pixel(0, 0));
+    /// ```
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> BmpResult<Image> {
+        if !self.contains_rect(x, y, width, height) {
+            return Err(BmpError::mismatch(
+                BmpErrorKind::OutOfBounds,
+                "rectangle",
+                format!("within the {}x{} image", self.width, self.height),
+                format!("{}x{} at ({}, {})", width, height, x, y),
+            ));
+        }
+        Ok(self.view(x, y, width, height).to_image())
+    }
+
+    /// Copies the pixels of `src` into the image, with the upper left corner of `src` placed at
+    /// `x` and `y`. The source can be another `Image` or a `SubImage` view.
+    /// The parts of `src` that fall outside of the image are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut sprite = bmp::Image::new(10, 10);
+    /// sprite.fill(bmp::consts::RED);
+    ///
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.paste(&sprite, 95, -5);
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(99, 0));
+    /// assert_eq!(bmp::consts::RED, img.get_pixel(95, 4));
+    /// assert_eq!(bmp::consts::BLACK, img.get_pixel(95, 5));
+    /// ```
+    pub fn paste<'a, S: Into<SubImage<'a>>>(&mut self, src: S, x: i64, y: i64) {
+        self.paste_with(src.into(), x, y, |_, px| px);
+    }
+
+    /// Draws the pixels of `src` onto the image like `paste`, blending them with the image
+    /// according to `mode`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, BlendMode, Image};
+    ///
+    /// let mut overlay = Image::new(10, 10);
+    /// overlay.fill(consts::RED);
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.fill(consts::BLUE);
+    /// img.paste_blended(&overlay, 0, 0, BlendMode::Screen);
+    /// assert_eq!(consts::MAGENTA, img.get_pixel(0, 0));
+    /// ```
+    pub fn paste_blended<'a, S: Into<SubImage<'a>>>(
+        &mut self,
+        src: S,
+        x: i64,
+        y: i64,
+        mode: BlendMode,
+    ) {
+        self.paste_with(src.into(), x, y, |dst, px| mode.blend(dst, px));
+    }
+
+    /// Copies the pixels of `src` into the image like `paste`, but skips the source pixels that
+    /// are equal to the color `key`. This makes the key color transparent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Image};
+    ///
+    /// let mut sprite = Image::new(10, 10);
+    /// sprite.fill(consts::MAGENTA);
+    /// sprite.set_pixel(5, 5, consts::RED);
+    ///
+    /// let mut img = Image::new(100, 80);
+    /// img.paste_keyed(&sprite, 0, 0, consts::MAGENTA);
+    /// assert_eq!(consts::RED, img.get_pixel(5, 5));
+    /// assert_eq!(consts::BLACK, img.get_pixel(0, 0));
+    /// ```
+    pub fn paste_keyed<'a, S: Into<SubImage<'a>>>(&mut self, src: S, x: i64, y: i64, key: Pixel) {
+        self.paste_with(src.into(), x, y, |dst, px| if px == key { dst } else { px });
+    }
+
+    // Pastes `src` by combining each destination and source pixel with `f`
+    pub(crate) fn paste_with<F>(&mut self, src: SubImage, x: i64, y: i64, mut f: F)
+    where
+        F: FnMut(Pixel, Pixel) -> Pixel,
+    {
+        let area = match clip(self, x, y, src.get_width(), src.get_height()) {
+            Some(area) => area,
+            None => return,
+        };
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let (dx, dy) = (area.dst_x + x, area.dst_y + y);
+                let px = f(
+                    self.get_pixel(dx, dy),
+                    src.get_pixel(area.src_x + x, area.src_y + y),
+                );
+                self.set_pixel(dx, dy, px);
+            }
+        }
+    }
+
+    /// Returns a new `Image` surrounded by a border of `thickness` pixels in the color `val`.
+    ///
+    /// Returns a `BmpError` if the bordered image would be wider or higher than `u32::MAX`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(100, 80);
+    /// let framed = img.with_border(5, bmp::consts::WHITE).unwrap();
+    /// assert_eq!((110, 90), (framed.get_width(), framed.get_height()));
+    /// assert_eq!(bmp::consts::WHITE, framed.get_pixel(0, 0));
+    /// assert_eq!(bmp::consts::BLACK, framed.get_pixel(5, 5));
+    /// ```
+    pub fn with_border(&self, thickness: u32, val: Pixel) -> BmpResult<Image> {
+        let grow = |size: u32| thickness.checked_mul(2).and_then(|t| size.checked_add(t));
+        let (width, height) = match (grow(self.width), grow(self.height)) {
+            (Some(width), Some(height)) => (width, height),
+            _ => {
+                return Err(BmpError::mismatch(
+                    BmpErrorKind::InvalidDimensions,
+                    "border",
+                    format!("at most {} pixels wide and high", u32::MAX),
+                    format!(
+                        "{} pixels around the {}x{} image",
+                        thickness, self.width, self.height
+                    ),
+                ))
+            }
+        };
+        let mut img = Image::new(width, height);
+        img.fill(val);
+        img.paste(self, thickness as i64, thickness as i64);
+        Ok(img)
+    }
+
+    /// Returns a new `Image` of `width` and `height`, with `self` placed according to `anchor`
+    /// and the remaining area filled with the color `val`.
+    /// If the new size is smaller than the image, the image is cropped according to `anchor`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bmp::{consts, Anchor, Image};
+    ///
+    /// let img = Image::new(100, 80);
+    /// let letterboxed = img.pad_to(100, 100, consts::WHITE, Anchor::Center);
+    /// assert_eq!(consts::WHITE, letterboxed.get_pixel(0, 9));
+    /// assert_eq!(consts::BLACK, letterboxed.get_pixel(0, 10));
+    /// assert_eq!(consts::BLACK, letterboxed.get_pixel(0, 89));
+    /// assert_eq!(consts::WHITE, letterboxed.get_pixel(0, 90));
+    /// ```
+    pub fn pad_to(&self, width: u32, height: u32, val: Pixel, anchor: Anchor) -> Image {
+        let mut img = Image::new(width, height);
+        img.fill(val);
+        let (x, y) = anchor.offset((self.width, self.height), (width, height));
+        img.paste(self, x, y);
+        img
+    }
+
+    /// Returns a new `Image` of `width` and `height`, filled by repeating `self` from the upper
+    /// left corner.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut pattern = bmp::Image::new(2, 2);
+    /// pattern.set_pixel(0, 0, bmp::consts::WHITE);
+    ///
+    /// let background = pattern.tiled(100, 80);
+    /// assert_eq!(bmp::consts::WHITE, background.get_pixel(98, 78));
+    /// assert_eq!(bmp::consts::BLACK, background.get_pixel(99, 79));
+    /// ```
+    pub fn tiled(&self, width: u32, height: u32) -> Image {
+        let mut img = Image::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return img;
+        }
+        for (x, y) in img.coordinates() {
+            img.set_pixel(x, y, self.get_pixel(x % self.width, y % self.height));
+        }
+        img
+    }
+
+    /// Returns a new `Image` mirrored across the diagonal from the upper left corner, so the
+    /// pixel at (`x`, `y`) is moved to (`y`, `x`). The width and height are swapped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut img = bmp::Image::new(100, 80);
+    /// img.set_pixel(10, 20, bmp::consts::RED);
+    ///
+    /// let transposed = img.transpose();
+    /// assert_eq!((80, 100), (transposed.get_width(), transposed.get_height()));
+    /// assert_eq!(bmp::consts::RED, transposed.get_pixel(20, 10));
+    /// ```
+    pub fn transpose(&self) -> Image {
+        let mut data = Vec::with_capacity(self.data.len());
+        // The rows are stored from bottom to top internally
+        for x in (0..self.width).rev() {
+            for y in 0..self.height {
+                data.push(self.get_pixel(x, y));
+            }
+        }
+        Image::from_data(self.height, self.width, data)
+    }
+
+    /// Returns a new `Image` mirrored from left to right.
+    pub fn flip_horizontal(&self) -> Image {
+        let mut data = self.data.clone();
+        for row in data.chunks_mut(self.width.max(1) as usize) {
+            row.reverse();
+        }
+        Image::from_data(self.width, self.height, data)
+    }
+
+    /// Returns a new `Image` mirrored from top to bottom.
+    pub fn flip_vertical(&self) -> Image {
+        let mut data = Vec::with_capacity(self.data.len());
+        for row in self.data.chunks(self.width.max(1) as usize).rev() {
+            data.extend_from_slice(row);
+        }
+        Image::from_data(self.width, self.height, data)
+    }
+
+    /// Returns a new `Image` rotated 90 degrees clockwise.
+    pub fn rotate90(&self) -> Image {
+        self.transpose().flip_horizontal()
+    }
+
+    /// Returns a new `Image` rotated 180 degrees.
+    pub fn rotate180(&self) -> Image {
+        let mut data = self.data.clone();
+        data.reverse();
+        Image::from_data(self.width, self.height, data)
+    }
+
+    /// Returns a new `Image` rotated 270 degrees clockwise.
+    pub fn rotate270(&self) -> Image {
+        self.transpose().flip_vertical()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Anchor;
+    use {consts, BmpError, BmpErrorKind, Image, Pixel};
+
+    #[test]
+    fn crop_copies_the_rectangle() {
+        let mut img = Image::new(4, 3);
+        img.set_pixel(1, 1, consts::RED);
+        img.set_pixel(3, 2, consts::BLUE);
+
+        let crop = img.crop(1, 1, 3, 2).unwrap();
+        assert_eq!((crop.get_width(), crop.get_height()), (3, 2));
+        assert_eq!(crop.get_pixel(0, 0), consts::RED);
+        assert_eq!(crop.get_pixel(2, 1), consts::BLUE);
+        assert_eq!(crop.get_pixel(1, 0), consts::BLACK);
+    }
+
+    #[test]
+    fn transpose_and_mirror_move_the_corners() {
+        let (r, g, b, w, k, n) = (
+            consts::RED,
+            consts::LIME,
+            consts::BLUE,
+            consts::WHITE,
+            consts::BLACK,
+            px!(9, 9, 9),
+        );
+        let mut img = Image::new(3, 2);
+        for (x, y) in img.coordinates() {
+            img.set_pixel(x, y, [[r, g, b], [w, k, n]][y as usize][x as usize]);
+        }
+        let corners = |img: &Image| {
+            let (x, y) = (img.get_width() - 1, img.get_height() - 1);
+            [
+                img.get_pixel(0, 0),
+                img.get_pixel(x, 0),
+                img.get_pixel(0, y),
+                img.get_pixel(x, y),
+            ]
+        };
+
+        assert_eq!(corners(&img), [r, b, w, n]);
+        assert_eq!(corners(&img.transpose()), [r, w, b, n]);
+        assert_eq!(corners(&img.flip_horizontal()), [b, r, n, w]);
+        assert_eq!(corners(&img.flip_vertical()), [w, n, r, b]);
+        assert_eq!(corners(&img.rotate90()), [w, r, n, b]);
+        assert_eq!(corners(&img.rotate180()), [n, w, b, r]);
+        assert_eq!(corners(&img.rotate270()), [b, n, r, w]);
+        assert_eq!(img.transpose().get_pixel(1, 1), k);
+        assert_eq!(img.transpose().transpose(), img);
+    }
+
+    #[test]
+    fn paste_is_clipped_to_the_image() {
+        let mut src = Image::new(3, 3);
+        src.fill(consts::WHITE);
+        src.set_pixel(2, 2, consts::RED);
+
+        let mut img = Image::new(4, 4);
+        img.paste(&src, -1, 2);
+        img.paste(src.view(2, 2, 1, 1), 3, 0);
+        img.paste(&src, 4, 0);
+        img.paste(&src, -3, 0);
+        img.paste(&src, i64::MAX, i64::MAX);
+
+        let painted: Vec<_> = img
+            .coordinates()
+            .filter(|&(x, y)| img.get_pixel(x, y) != consts::BLACK)
+            .map(|(x, y)| (x, y, img.get_pixel(x, y)))
+            .collect();
+        assert_eq!(
+            painted,
+            vec![
+                (3, 0, consts::RED),
+                (0, 2, consts::WHITE),
+                (1, 2, consts::WHITE),
+                (0, 3, consts::WHITE),
+                (1, 3, consts::WHITE),
+            ]
+        );
+    }
+
+    #[test]
+    fn paste_keyed_skips_the_key_color() {
+        let mut src = Image::new(2, 1);
+        src.set_pixel(0, 0, consts::MAGENTA);
+        src.set_pixel(1, 0, consts::RED);
+
+        let mut img = Image::new(2, 1);
+        img.fill(consts::WHITE);
+        img.paste_keyed(&src, 0, 0, consts::MAGENTA);
+        assert_eq!(img.get_pixel(0, 0), consts::WHITE);
+        assert_eq!(img.get_pixel(1, 0), consts::RED);
+    }
+
+    #[test]
+    fn pad_to_places_the_image_at_the_anchor() {
+        let mut img = Image::new(2, 2);
+        img.fill(consts::RED);
+
+        let padded = img.pad_to(5, 4, consts::WHITE, Anchor::BottomRight);
+        assert_eq!(padded.get_pixel(2, 1), consts::WHITE);
+        assert_eq!(padded.get_pixel(3, 2), consts::RED);
+        assert_eq!(padded.get_pixel(4, 3), consts::RED);
+
+        let padded = img.pad_to(5, 4, consts::WHITE, Anchor::Top);
+        assert_eq!(padded.get_pixel(0, 0), consts::WHITE);
+        assert_eq!(padded.get_pixel(1, 0), consts::RED);
+        assert_eq!(padded.get_pixel(2, 1), consts::RED);
+        assert_eq!(padded.get_pixel(3, 0), consts::WHITE);
+
+        let cropped = img.pad_to(1, 1, consts::WHITE, Anchor::Center);
+        assert_eq!(cropped.get_pixel(0, 0), consts::RED);
+    }
+
+    #[test]
+    fn tiled_repeats_the_image() {
+        let mut img = Image::new(2, 3);
+        img.set_pixel(1, 2, consts::RED);
+
+        let tiled = img.tiled(5, 4);
+        let red: Vec<_> = tiled
+            .coordinates()
+            .filter(|&(x, y)| tiled.get_pixel(x, y) == consts::RED)
+            .collect();
+        assert_eq!(red, vec![(1, 2), (3, 2)]);
+        assert_eq!(Image::new(0, 0).tiled(2, 2), Image::new(2, 2));
+    }
+
+    #[test]
+    fn error_when_cropping_outside_of_the_image() {
+        match Image::new(4, 3).crop(2, 0, 3, 1) {
+            Err(BmpError { kind: BmpErrorKind::OutOfBounds, .. }) => (/* Expected */),
+            _ => panic!("The rectangle is outside of the image"),
+        }
+    }
+
+    #[test]
+    fn error_when_the_border_is_too_thick() {
+        match Image::new(4, 3).with_border(u32::MAX / 2, consts::WHITE) {
+            Err(BmpError { kind: BmpErrorKind::InvalidDimensions, .. }) => (/* Expected */),
+            _ => panic!("The bordered image is too large"),
+        }
+    }
+}
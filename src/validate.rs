@@ -0,0 +1,414 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use decoder::bit_index;
+use {row_stride, ErrorDetails, Image, HEADER_SIZE};
+
+// The size of the BMP header, after which the DIB header starts
+const BMP_HEADER_SIZE: usize = 14;
+
+/// How serious a `Finding` is.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// The image is inconsistent, but it can still be decoded.
+    Warning,
+    /// The image cannot be decoded or encoded.
+    Error,
+}
+
+/// A single inconsistency found while validating an image.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub details: ErrorDetails,
+}
+
+/// Displays the finding as `error: <details>` or `warning: <details>`.
+impl fmt::Display for Finding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(fmt, "warning: {}", self.details),
+            Severity::Error => write!(fmt, "error: {}", self.details),
+        }
+    }
+}
+
+/// The findings of validating an image with `validate_file`, `validate_bytes` or
+/// `Image::validate`, in the order they were found.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if none of the findings are errors.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Returns the findings that are errors.
+    pub fn errors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+    }
+
+    /// Returns the findings that are warnings.
+    pub fn warnings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Warning)
+    }
+
+    fn push<E, F>(&mut self, severity: Severity, field: &'static str, expected: E, found: F)
+    where
+        E: fmt::Display,
+        F: fmt::Display,
+    {
+        self.findings.push(Finding {
+            severity,
+            details: ErrorDetails {
+                field: Some(field),
+                expected: Some(expected.to_string()),
+                found: Some(found.to_string()),
+                note: None,
+            },
+        });
+    }
+
+    fn error<E: fmt::Display, F: fmt::Display>(&mut self, field: &'static str, e: E, f: F) {
+        self.push(Severity::Error, field, e, f);
+    }
+
+    fn warning<E: fmt::Display, F: fmt::Display>(&mut self, field: &'static str, e: E, f: F) {
+        self.push(Severity::Warning, field, e, f);
+    }
+}
+
+/// Validates the BMP file specified by `path`, see `validate_bytes`. A file that cannot be read is
+/// reported as an error.
+///
+/// # Example
+///
+/// ```
+/// let report = bmp::validate_file("test/rgbw.bmp");
+/// assert!(report.is_valid());
+/// ```
+pub fn validate_file<P: AsRef<Path>>(path: P) -> ValidationReport {
+    match fs::read(path) {
+        Ok(bytes) => validate_bytes(&bytes),
+        Err(err) => ValidationReport {
+            findings: vec![Finding {
+                severity: Severity::Error,
+                details: ErrorDetails {
+                    note: Some(err.to_string()),
+                    ..ErrorDetails::default()
+                },
+            }],
+        },
+    }
+}
+
+/// Validates the encoded BMP image in `bytes`, without decoding it.
+///
+/// The headers are cross-checked against each other and against the length of the file, the
+/// dimensions are checked for overflows, and the pixels of images with a color palette are checked
+/// to only use colors within the palette. Everything that prevents the image from being decoded is
+/// reported as an error, and the inconsistencies the decoder tolerates as warnings.
+///
+/// # Example
+///
+/// ```
+/// let mut bytes = std::fs::read("test/rgbw.bmp").unwrap();
+/// bytes.truncate(bytes.len() - 1);
+///
+/// let report = bmp::validate_bytes(&bytes);
+/// assert!(report.is_valid());
+/// assert_eq!(Some("file_size"), report.warnings().next().unwrap().details.field);
+/// ```
+pub fn validate_bytes(bytes: &[u8]) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let len = bytes.len() as u64;
+
+    if bytes.len() < BMP_HEADER_SIZE + 4 {
+        report.error(
+            "file_size",
+            format!("at least {}", BMP_HEADER_SIZE + 4),
+            len,
+        );
+        return report;
+    }
+    if &bytes[0..2] != b"BM" {
+        report.error("magic", "[66, 77]", format!("{:?}", &bytes[0..2]));
+        return report;
+    }
+    let header_size = LittleEndian::read_u32(&bytes[14..18]);
+    match header_size {
+        40 | 108 | 124 => (),
+        other => {
+            report.error("header_size", "40, 108, or 124", other);
+            return report;
+        }
+    }
+    let headers_end = BMP_HEADER_SIZE as u64 + header_size as u64;
+    if len < headers_end {
+        report.error("file_size", format!("at least {}", headers_end), len);
+        return report;
+    }
+
+    let file_size = LittleEndian::read_u32(&bytes[2..6]);
+    let pixel_offset = LittleEndian::read_u32(&bytes[10..14]) as u64;
+    let width = LittleEndian::read_i32(&bytes[18..22]);
+    let height = LittleEndian::read_i32(&bytes[22..26]);
+    let num_planes = LittleEndian::read_u16(&bytes[26..28]);
+    let bpp = LittleEndian::read_u16(&bytes[28..30]);
+    let compress_type = LittleEndian::read_u32(&bytes[30..34]);
+    let data_size = LittleEndian::read_u32(&bytes[34..38]);
+    let num_colors = LittleEndian::read_u32(&bytes[46..50]);
+
+    if file_size as u64 != len {
+        report.warning("file_size", len, file_size);
+    }
+    if num_planes != 1 {
+        report.warning("num_planes", 1, num_planes);
+    }
+    if width <= 0 {
+        report.error("width", "greater than 0", width);
+    }
+    if height == 0 {
+        report.error("height", "not 0", height);
+    }
+    match bpp {
+        1 | 4 | 8 | 24 => (),
+        other => report.error("bits_per_pixel", "1, 4, 8, or 24", other),
+    }
+    if compress_type != 0 {
+        report.error("compress_type", 0, compress_type);
+    }
+    if !report.is_valid() {
+        return report;
+    }
+
+    let (width, height) = (width as u32, height.unsigned_abs());
+    let stride = row_stride(bpp, width);
+    let pixel_size = stride * height as u64;
+    if pixel_size > u32::MAX as u64 {
+        report.error(
+            "dimensions",
+            "a pixel array of at most 4 GiB",
+            format!("{}x{}", width, height),
+        );
+        return report;
+    }
+    if data_size != 0 && data_size as u64 != pixel_size {
+        report.warning("data_size", pixel_size, data_size);
+    }
+
+    let num_entries = match bpp {
+        _ if num_colors != 0 => num_colors as u64,
+        1 | 4 | 8 => 1 << bpp,
+        _ => 0,
+    };
+    if bpp <= 8 && num_entries > 1 << bpp {
+        report.warning("num_colors", format!("at most {}", 1 << bpp), num_entries);
+    }
+    let palette_end = headers_end + num_entries * 4;
+    if len < palette_end {
+        report.error("file_size", format!("at least {}", palette_end), len);
+        return report;
+    }
+    if pixel_offset < palette_end {
+        report.error(
+            "pixel_offset",
+            format!("at least {}", palette_end),
+            pixel_offset,
+        );
+        return report;
+    }
+
+    // The padding of the last row may be missing
+    let bytes_per_row = (bpp as u64 * width as u64).div_ceil(8);
+    let pixels_end = pixel_offset + stride * (height as u64 - 1) + bytes_per_row;
+    if len < pixels_end {
+        report.error("file_size", format!("at least {}", pixels_end), len);
+        return report;
+    }
+
+    if bpp <= 8 {
+        let pixels = &bytes[pixel_offset as usize..];
+        let outside = pixels
+            .chunks(stride as usize)
+            .take(height as usize)
+            .flat_map(|row| bit_index(row, bpp as usize, width as usize))
+            .filter(|&i| i as u64 >= num_entries)
+            .count();
+        if outside > 0 {
+            report.warning(
+                "palette_index",
+                format!("less than {}", num_entries),
+                format!("{} pixels outside of the palette", outside),
+            );
+        }
+    }
+    report
+}
+
+impl Image {
+    /// Validates the image before it is encoded.
+    ///
+    /// The dimensions are checked against the headers, against the number of pixels, and for
+    /// overflows when the image is encoded. Images decoded with a color palette are also checked
+    /// to only contain colors from the palette.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let img = bmp::Image::new(2, 2);
+    /// assert!(img.validate().is_valid());
+    ///
+    /// let report = bmp::Image::new(0, 2).validate();
+    /// assert!(report.is_valid());
+    /// assert_eq!(1, report.warnings().count());
+    /// ```
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let dimensions = format!("{}x{}", self.width, self.height);
+
+        if self.width == 0 || self.height == 0 {
+            report.warning("dimensions", "at least 1x1", &dimensions);
+        }
+        if self.width > i32::MAX as u32 || self.height > i32::MAX as u32 {
+            report.error(
+                "dimensions",
+                format!("at most {0}x{0}", i32::MAX),
+                &dimensions,
+            );
+        } else {
+            let header = format!("{}x{}", self.dib_header.width, self.dib_header.height);
+            if header != dimensions {
+                report.error("dib_header", &dimensions, header);
+            }
+        }
+        let file_size = HEADER_SIZE as u64 + row_stride(24, self.width) * self.height as u64;
+        if file_size > u32::MAX as u64 {
+            report.error("file_size", format!("at most {}", u32::MAX), file_size);
+        }
+        let num_pixels = self.width as u64 * self.height as u64;
+        if self.data.len() as u64 != num_pixels {
+            report.error("data.len()", num_pixels, self.data.len());
+        }
+
+        if let Some(ref palette) = self.color_palette {
+            let outside = self.data.iter().filter(|px| !palette.contains(px)).count();
+            if outside > 0 {
+                report.warning(
+                    "palette",
+                    "every pixel within the palette",
+                    format!("{} pixels outside of the palette", outside),
+                );
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Pixel;
+
+    #[test]
+    fn decodable_files_are_valid() {
+        for entry in fs::read_dir("test/bmptestsuite-0.9/valid").unwrap() {
+            let path = entry.unwrap().path();
+            if ::open(&path).is_ok() {
+                let report = validate_file(&path);
+                assert!(report.is_valid(), "{:?}: {:?}", path, report);
+            }
+        }
+    }
+
+    #[test]
+    fn valid_files_are_decodable() {
+        let dirs = [
+            "test/bmptestsuite-0.9/corrupt",
+            "test/bmpsuite-2.5/g",
+            "test/bmpsuite-2.5/q",
+        ];
+        for dir in &dirs {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if validate_file(&path).is_valid() {
+                    assert!(::open(&path).is_ok(), "{:?}", path);
+                }
+            }
+        }
+        // A 24 bpp image with a color table, which once made the decoder panic
+        assert!(validate_file("test/bmpsuite-2.5/g/rgb24pal.bmp").is_valid());
+    }
+
+    #[test]
+    fn missing_files_are_errors() {
+        let report = validate_file("test/does-not-exist.bmp");
+        assert!(!report.is_valid());
+        assert_eq!(None, report.findings[0].details.field);
+    }
+
+    #[test]
+    fn header_cross_checks() {
+        let mut bytes = Vec::new();
+        Image::new(3, 2).to_writer(&mut bytes).unwrap();
+        assert_eq!(ValidationReport::default(), validate_bytes(&bytes));
+
+        // A data size that doesn't match the dimensions, and an impossible pixel offset
+        LittleEndian::write_u32(&mut bytes[34..38], 7);
+        LittleEndian::write_u32(&mut bytes[10..14], 20);
+        let report = validate_bytes(&bytes);
+        let fields: Vec<_> = report.findings.iter().map(|f| f.details.field).collect();
+        assert_eq!(vec![Some("data_size"), Some("pixel_offset")], fields);
+        assert_eq!(Severity::Warning, report.findings[0].severity);
+        assert_eq!(Severity::Error, report.findings[1].severity);
+    }
+
+    #[test]
+    fn dimension_overflows_are_errors() {
+        let mut bytes = Vec::new();
+        Image::new(1, 1).to_writer(&mut bytes).unwrap();
+        LittleEndian::write_i32(&mut bytes[18..22], i32::MAX);
+        LittleEndian::write_i32(&mut bytes[22..26], i32::MAX);
+
+        let report = validate_bytes(&bytes);
+        assert_eq!(1, report.errors().count());
+        assert_eq!(Some("dimensions"), report.findings[0].details.field);
+    }
+
+    #[test]
+    fn palette_indexes_outside_of_the_palette_are_warnings() {
+        let mut bytes = fs::read("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+        // Shrink the palette to a single color, which the pixel doesn't use
+        LittleEndian::write_u32(&mut bytes[46..50], 1);
+        let pixel_offset = LittleEndian::read_u32(&bytes[10..14]) as usize;
+        bytes[pixel_offset] = 1;
+
+        let report = validate_bytes(&bytes);
+        assert!(report.is_valid());
+        assert_eq!(
+            Some("palette_index"),
+            report.warnings().next().unwrap().details.field
+        );
+    }
+
+    #[test]
+    fn in_memory_palette_coverage() {
+        let mut img = ::open("test/bmptestsuite-0.9/valid/8bpp-1x1.bmp").unwrap();
+        assert!(img.validate().findings.is_empty());
+
+        img.set_pixel(0, 0, px!(1, 2, 3));
+        let report = img.validate();
+        assert!(report.is_valid());
+        assert_eq!(Some("palette"), report.findings[0].details.field);
+    }
+}
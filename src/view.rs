@@ -0,0 +1,310 @@
+use std::io::{self, Write};
+use std::iter::Iterator;
+use std::path::Path;
+
+use {Image, ImageIndex, Pixel};
+
+/// A read-only view into a rectangular region of an `Image`.
+///
+/// The view borrows the pixel data of the parent image, so no pixels are copied.
+/// Coordinates are relative to the upper left corner of the region.
+#[derive(Clone, Copy, Debug)]
+pub struct SubImage<'a> {
+    image: &'a Image,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Views the whole image.
+impl<'a> From<&'a Image> for SubImage<'a> {
+    fn from(image: &'a Image) -> SubImage<'a> {
+        SubImage::new(image, 0, 0, image.width, image.height)
+    }
+}
+
+impl<'a> SubImage<'a> {
+    pub(crate) fn new(image: &'a Image, x: u32, y: u32, width: u32, height: u32) -> SubImage<'a> {
+        SubImage {
+            image,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the `x` and `y` coordinates of the upper left corner of the view in the parent
+    /// image.
+    #[inline]
+    pub fn position(&self) -> (u32, u32) {
+        (self.x, self.y)
+    }
+
+    /// Returns the `width` of the view.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the view.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the pixel value at the position of `x` and `y`, relative to the view.
+    ///
+    /// Panics if the position is outside of the view.
+    #[inline]
+    pub fn get_pixel(&self, x: u32, y: u32) -> Pixel {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside of the {}x{} view",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        self.image.get_pixel(self.x + x, self.y + y)
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the view dimensions in top-bottom order.
+    #[inline]
+    pub fn coordinates(&self) -> ImageIndex {
+        ImageIndex::new(self.width, self.height)
+    }
+
+    /// Returns a new `Image` containing a copy of the pixels in the view.
+    pub fn to_image(&self) -> Image {
+        let mut data = Vec::with_capacity(self.width as usize * self.height as usize);
+        // The rows are stored from bottom to top internally
+        for y in (self.y..self.y + self.height).rev() {
+            let start = (self.image.height - y - 1) as usize * self.image.width as usize
+                + self.x as usize;
+            data.extend_from_slice(&self.image.data[start..start + self.width as usize]);
+        }
+        Image::from_data(self.width, self.height, data)
+    }
+
+    /// Saves the pixels in the view as a new image to the path specified by `path`.
+    /// The function will overwrite the contents if a file already exists at the given path.
+    ///
+    /// The function returns the `io::Result` from the underlying writer.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.to_image().save(path)
+    }
+
+    /// Writes the pixels in the view as a new image to the writer referenced by `destination`.
+    pub fn to_writer<W: Write>(&self, destination: &mut W) -> io::Result<()> {
+        self.to_image().to_writer(destination)
+    }
+}
+
+/// A mutable view into a rectangular region of an `Image`.
+///
+/// The view borrows the pixel data of the parent image, so edits are written directly to the
+/// parent. Coordinates are relative to the upper left corner of the region, and edits never
+/// reach outside of it.
+#[derive(Debug)]
+pub struct SubImageMut<'a> {
+    // The pixels from the start of the bottom row of the view, in the order of the parent image
+    data: &'a mut [Pixel],
+    stride: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl<'a> SubImageMut<'a> {
+    pub(crate) fn new(
+        image: &'a mut Image,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> SubImageMut<'a> {
+        let stride = image.width;
+        let start = if height == 0 {
+            0
+        } else {
+            ((image.height - y - height) * stride + x) as usize
+        };
+        SubImageMut {
+            data: &mut image.data[start..],
+            stride,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the `x` and `y` coordinates of the upper left corner of the view in the parent
+    /// image.
+    #[inline]
+    pub fn position(&self) -> (u32, u32) {
+        (self.x, self.y)
+    }
+
+    /// Returns the `width` of the view.
+    #[inline]
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the view.
+    #[inline]
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) is outside of the {}x{} view",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        (self.height - y - 1) as usize * self.stride as usize + x as usize
+    }
+
+    /// Returns the pixel value at the position of `x` and `y`, relative to the view.
+    ///
+    /// Panics if the position is outside of the view.
+    #[inline]
+    pub fn get_pixel(&self, x: u32, y: u32) -> Pixel {
+        self.data[self.index(x, y)]
+    }
+
+    /// Set the pixel value at the position of `x` and `y`, relative to the view.
+    ///
+    /// Panics if the position is outside of the view.
+    #[inline]
+    pub fn set_pixel(&mut self, x: u32, y: u32, val: Pixel) {
+        let i = self.index(x, y);
+        self.data[i] = val;
+    }
+
+    /// Returns a new `ImageIndex` that iterates over the view dimensions in top-bottom order.
+    #[inline]
+    pub fn coordinates(&self) -> ImageIndex {
+        ImageIndex::new(self.width, self.height)
+    }
+
+    /// Sets every pixel of the view to `val`.
+    pub fn fill(&mut self, val: Pixel) {
+        let (width, height) = (self.width, self.height);
+        self.fill_rect(0, 0, width, height, val);
+    }
+
+    /// Sets every pixel in the rectangle with the upper left corner at `x` and `y`, relative to
+    /// the view, to `val`. The parts of the rectangle that fall outside of the view are ignored.
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, val: Pixel) {
+        let x_end = x.saturating_add(width).min(self.width);
+        let y_end = y.saturating_add(height).min(self.height);
+        for y in y.min(y_end)..y_end {
+            for x in x.min(x_end)..x_end {
+                self.set_pixel(x, y, val);
+            }
+        }
+    }
+
+    /// Replaces every pixel of the view with the result of applying `f` to it.
+    pub fn map_in_place<F: FnMut(Pixel) -> Pixel>(&mut self, mut f: F) {
+        for (x, y) in self.coordinates() {
+            let px = self.get_pixel(x, y);
+            self.set_pixel(x, y, f(px));
+        }
+    }
+
+    /// Divides the view into two disjoint views at the row `mid`. The first view contains the
+    /// rows above `mid`, and the second view contains the rest.
+    ///
+    /// The two views can be edited independently, e.g. from different threads.
+    ///
+    /// Panics if `mid` is larger than the height of the view.
+    pub fn split_at_row(self, mid: u32) -> (SubImageMut<'a>, SubImageMut<'a>) {
+        assert!(mid <= self.height, "{} is outside of the view", mid);
+        // The bottom rows are stored first
+        let below = self.height - mid;
+        let split = (below as usize * self.stride as usize).min(self.data.len());
+        let (bottom, top) = self.data.split_at_mut(split);
+        let top = SubImageMut {
+            data: top,
+            stride: self.stride,
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: mid,
+        };
+        let bottom = SubImageMut {
+            data: bottom,
+            stride: self.stride,
+            x: self.x,
+            y: self.y + mid,
+            width: self.width,
+            height: below,
+        };
+        (top, bottom)
+    }
+}
+
+/// An `Iterator` returning views of the tiles covering an image.
+///
+/// The tiles are returned in row-major order, starting in the upper left corner of the image.
+/// Tiles along the right and bottom edges are clipped to the image.
+#[derive(Clone, Debug)]
+pub struct Tiles<'a> {
+    image: &'a Image,
+    tile_width: u32,
+    tile_height: u32,
+    x: u32,
+    y: u32,
+}
+
+impl<'a> Tiles<'a> {
+    pub(crate) fn new(image: &'a Image, tile_width: u32, tile_height: u32) -> Tiles<'a> {
+        assert!(
+            tile_width > 0 && tile_height > 0,
+            "The tiles must be at least 1x1"
+        );
+        Tiles {
+            image,
+            tile_width,
+            tile_height,
+            x: 0,
+            y: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Tiles<'a> {
+    type Item = SubImage<'a>;
+
+    fn next(&mut self) -> Option<SubImage<'a>> {
+        let (width, height) = (self.image.get_width(), self.image.get_height());
+        if self.x >= width || self.y >= height {
+            return None;
+        }
+
+        let tile = SubImage::new(
+            self.image,
+            self.x,
+            self.y,
+            self.tile_width.min(width - self.x),
+            self.tile_height.min(height - self.y),
+        );
+        self.x = self.x.saturating_add(self.tile_width);
+        if self.x >= width {
+            self.x = 0;
+            self.y = self.y.saturating_add(self.tile_height);
+        }
+        Some(tile)
+    }
+}
@@ -0,0 +1,57 @@
+// The encoded images are passed to and from JavaScript as `Uint8Array`s, since there is no file
+// system to read from in the browser
+
+use wasm_bindgen::prelude::*;
+
+use {from_reader, ChannelOrder, Image};
+
+/// A decoded image with its pixels as tightly packed RGBA bytes, stored in row-major order from
+/// top to bottom, which is the layout expected by `ImageData` in the browser.
+#[wasm_bindgen]
+pub struct DecodedImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl DecodedImage {
+    /// Returns the `width` of the image.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the `height` of the image.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns a copy of the RGBA bytes as a `Uint8Array`.
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// Decodes the BMP image in `bytes`, given as a `Uint8Array`.
+#[wasm_bindgen]
+pub fn decode(bytes: &[u8]) -> Result<DecodedImage, JsError> {
+    let img = from_reader(&mut &bytes[..])?;
+    Ok(DecodedImage {
+        width: img.get_width(),
+        height: img.get_height(),
+        data: img.into_raw(ChannelOrder::Rgba),
+    })
+}
+
+/// Encodes the tightly packed RGBA bytes in `data` as a BMP image of `width` and `height`, and
+/// returns it as a `Uint8Array`. The alpha channel is ignored.
+#[wasm_bindgen]
+pub fn encode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    let img = Image::from_raw_rgba(data, width, height)?;
+    let mut bytes = Vec::new();
+    img.to_writer(&mut bytes)?;
+    Ok(bytes)
+}